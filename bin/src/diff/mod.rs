@@ -0,0 +1,124 @@
+mod output;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use shelflib::{
+    action::{diff::FileDiff, Action, Diff},
+    graph::{PackageData, PackageGraph},
+};
+
+use crate::ctxpath::CtxPath;
+use crate::process::Describe;
+
+#[derive(Debug, Clone)]
+pub struct DiffOptions {
+    pub dest: PathBuf,
+}
+
+/// Shows the changes that packages would make to the files they write without performing
+/// anything.
+#[derive(Debug)]
+pub struct Differ<'g> {
+    opts: DiffOptions,
+
+    graph: &'g PackageGraph,
+    paths: &'g HashMap<PathBuf, CtxPath>,
+}
+
+impl<'g> Differ<'g> {
+    #[inline]
+    pub fn new(
+        opts: DiffOptions,
+        graph: &'g PackageGraph,
+        paths: &'g HashMap<PathBuf, CtxPath>,
+    ) -> Self {
+        Self { opts, graph, paths }
+    }
+
+    /// Print the diff of every file that would be written, and return the number of files that
+    /// would change.
+    #[inline]
+    pub fn diff(&self) -> Result<usize, ()> {
+        match self.graph.order() {
+            Ok(order) => order
+                .map(|pd| self.diff_package(pd))
+                .sum::<Result<usize, _>>(),
+            Err(err) => {
+                output::error_circular(err);
+                Err(())
+            }
+        }
+    }
+
+    #[inline]
+    fn diff_package(&self, pd: &PackageData) -> Result<usize, ()> {
+        // SAFETY: Path guaranteed to be in it by `load`.
+        let path = self.paths.get(&pd.path).unwrap();
+
+        output::diffing(path);
+
+        pd.action_iter(&self.opts.dest)
+            .map(|action| diff_action(&action, path, &self.opts.dest))
+            .sum()
+    }
+}
+
+/// Print the diff of the file that `action` would write, and return 1 if it would change or 0
+/// otherwise.
+#[inline]
+pub fn diff_action(action: &Action<'_>, path: &CtxPath, dest: &Path) -> Result<usize, ()> {
+    match action {
+        Action::Write(a) => Ok(report(a.diff(), dest)),
+        Action::Handlebars(a) => handle_result(a.diff(), a, path, dest),
+        Action::Liquid(a) => handle_result(a.diff(), a, path, dest),
+        Action::Yaml(a) => handle_result(a.diff(), a, path, dest),
+        Action::Toml(a) => handle_result(a.diff(), a, path, dest),
+        Action::Json(a) => handle_result(a.diff(), a, path, dest),
+        Action::Ini(a) => handle_result(a.diff(), a, path, dest),
+        Action::Env(a) => handle_result(a.diff(), a, path, dest),
+        Action::Block(a) => handle_result(a.diff(), a, path, dest),
+        // Only the contents of written files are diffed.
+        Action::Link(_)
+        | Action::Tree(_)
+        | Action::Mkdir(_)
+        | Action::Command(_)
+        | Action::Function(_) => Ok(0),
+    }
+}
+
+/// Print the diff of a file, e.g. when it was requested in a prompt.
+#[inline]
+pub fn show(diff: &FileDiff, dest: &Path) {
+    output::diff(diff, dest);
+}
+
+#[inline]
+fn handle_result<A, E>(
+    res: Result<Option<FileDiff>, E>,
+    action: &A,
+    path: &CtxPath,
+    dest: &Path,
+) -> Result<usize, ()>
+where
+    A: Describe,
+{
+    match res {
+        Ok(diff) => Ok(report(diff, dest)),
+        Err(_) => {
+            output::error_resolving(action, path, dest);
+            Err(())
+        }
+    }
+}
+
+#[inline]
+fn report(diff: Option<FileDiff>, dest: &Path) -> usize {
+    match diff {
+        Some(diff) => {
+            output::diff(&diff, dest);
+            1
+        }
+        None => 0,
+    }
+}
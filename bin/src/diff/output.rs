@@ -0,0 +1,69 @@
+use std::io::{self, Write};
+use std::path::Path;
+
+use crossterm::tty::IsTty;
+use shelflib::{action::diff::FileDiff, graph::CircularDependencyError};
+
+use crate::ctxpath::CtxPath;
+use crate::output::{comb::pretty, Prettify, Pretty, Section, Step};
+use crate::process::{describe, Describe};
+
+#[inline]
+pub fn diffing(path: &CtxPath) {
+    Section::message("diffing", path.rel().display());
+}
+
+/// Print the unified diff of `diff` to stdout, so that it can be piped, e.g. into a patch. It is
+/// only colored if stdout is a terminal.
+#[inline]
+pub fn diff(diff: &FileDiff, dest: &Path) {
+    let stdout = io::stdout();
+    let color = stdout.is_tty();
+    let mut stdout = stdout.lock();
+    for line in lines(diff, dest) {
+        let _ = if color {
+            writeln!(stdout, "{}", line)
+        } else {
+            writeln!(stdout, "{}", line.content())
+        };
+    }
+}
+
+/// Return the colored lines of the unified diff of `diff`.
+#[inline]
+fn lines(diff: &FileDiff, dest: &Path) -> Vec<Pretty> {
+    let name = describe::dest_relative(&diff.dest, dest);
+    let unified = diff.unified(&name.rel().display().to_string());
+
+    unified
+        .lines()
+        .map(|line| {
+            if line.starts_with("+++") || line.starts_with("---") {
+                pretty(line).bold()
+            } else if line.starts_with('+') {
+                pretty(line).green()
+            } else if line.starts_with('-') {
+                pretty(line).red()
+            } else if line.starts_with("@@") {
+                pretty(line).cyan()
+            } else {
+                pretty(line)
+            }
+        })
+        .collect()
+}
+
+#[inline]
+pub fn error_resolving<A>(action: &A, path: &CtxPath, dest: &Path)
+where
+    A: Describe,
+{
+    Step::error().message("couldn't resolve action");
+    Step::error().context(action.describe_error(path, dest));
+}
+
+#[inline]
+pub fn error_circular(err: CircularDependencyError) {
+    Section::error().message("circular dependency detected");
+    Section::error().context(err.path().display());
+}
@@ -0,0 +1,111 @@
+mod output;
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use shelflib::{
+    journal::writer::WriteError,
+    op::journal::{OpJournal, RollbackIter, Transaction},
+};
+
+/// A journal (or a handle to one) that can be written to a [`JournalFile`].
+pub trait Persist {
+    fn size(&self) -> usize;
+
+    fn write<W: Write>(&self, w: W, start: usize) -> Result<(), WriteError>;
+}
+
+macro_rules! persist_impl {
+    ($($ty:ty),*) => {
+        $(
+            impl Persist for $ty {
+                #[inline]
+                fn size(&self) -> usize {
+                    <$ty>::size(self)
+                }
+
+                #[inline]
+                fn write<W: Write>(&self, w: W, start: usize) -> Result<(), WriteError> {
+                    <$ty>::write(self, w, start)
+                }
+            }
+        )*
+    };
+}
+
+persist_impl!(OpJournal, Transaction<'_>, RollbackIter<'_>);
+
+/// The on-disk copy of the [`OpJournal`]. Records are appended to the file as they are added to
+/// the journal, so that an interrupted run can be detected on the next invocation.
+#[derive(Debug)]
+pub struct JournalFile {
+    path: PathBuf,
+    /// Number of records that have already been written to the file.
+    synced: usize,
+}
+
+impl JournalFile {
+    #[inline]
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self {
+            path: path.into(),
+            synced: 0,
+        }
+    }
+
+    /// Load the journal from the file. If the file doesn't exist, an empty journal is returned.
+    #[inline]
+    pub fn load(&mut self) -> Result<OpJournal, ()> {
+        let journal = match File::open(&self.path) {
+            Ok(file) => {
+                OpJournal::load(file).map_err(|err| output::error_reading(&self.path, err))?
+            }
+            Err(err) if err.kind() == io::ErrorKind::NotFound => OpJournal::new(),
+            Err(err) => {
+                output::error_opening(&self.path, err);
+                return Err(());
+            }
+        };
+
+        self.synced = journal.size();
+        Ok(journal)
+    }
+
+    /// Append all records of `journal` that haven't been written yet to the file.
+    #[inline]
+    pub fn sync<J>(&mut self, journal: &J) -> Result<(), ()>
+    where
+        J: Persist,
+    {
+        if journal.size() <= self.synced {
+            return Ok(());
+        }
+
+        let mut file = self.open_append()?;
+        journal
+            .write(&mut file, self.synced)
+            .map_err(|err| output::error_writing(&self.path, err))?;
+        file.sync_data()
+            .map_err(|err| output::error_writing(&self.path, err.into()))?;
+
+        self.synced = journal.size();
+        Ok(())
+    }
+
+    #[inline]
+    fn open_append(&self) -> Result<File, ()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|err| output::error_opening(&self.path, err))?;
+        }
+
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .map_err(|err| output::error_opening(&self.path, err))
+    }
+}
@@ -0,0 +1,31 @@
+use std::io;
+use std::path::Path;
+
+use shelflib::journal::writer::{ReadError, WriteError};
+
+use crate::output::{comb::sjoin2, spath, Section};
+
+#[inline]
+pub fn error_opening(path: &Path, err: io::Error) {
+    Section::error()
+        .message(sjoin2("couldn't open journal", spath(path)))
+        .reason(err);
+}
+
+#[inline]
+pub fn error_reading(path: &Path, err: ReadError) {
+    let error = Section::error().message(sjoin2("couldn't read journal", spath(path)));
+    match err {
+        ReadError::Io(err) => error.reason(err),
+        ReadError::Serde(err) => error.reason(err),
+    };
+}
+
+#[inline]
+pub fn error_writing(path: &Path, err: WriteError) {
+    let error = Section::error().message(sjoin2("couldn't write journal", spath(path)));
+    match err {
+        WriteError::Io(err) => error.reason(err),
+        WriteError::Serde(err) => error.reason(err),
+    };
+}
@@ -75,7 +75,7 @@ impl Loader {
             output::skip(path);
             vec![]
         } else {
-            let loader = SpecLoader::new(&path.abs())?;
+            let loader = SpecLoader::new(path.abs())?;
 
             output::reading();
             let loader = loader.read()?;
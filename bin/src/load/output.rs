@@ -27,7 +27,7 @@ pub fn evaling() {
 
 #[inline]
 pub fn queueing_dep(dep: &CtxPath, parent: &Path) {
-    let dep_rel = CtxPath::new(dep.abs(), &parent).unwrap();
+    let dep_rel = CtxPath::new(dep.abs(), parent).unwrap();
     Step::message(comb::sjoin2("queueing dependency", spath(dep_rel.rel())));
 }
 
@@ -3,30 +3,53 @@
 mod ctxpath;
 mod output;
 
+mod diff;
+mod journal;
 mod load;
 mod process;
+mod prompt;
+mod state;
+mod status;
+mod undo;
 
 use std::env;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-use clap::{ArgGroup, Parser};
+use clap::{ArgGroup, Parser, Subcommand};
 use directories_next::BaseDirs;
 use once_cell::unsync::Lazy;
+use shelflib::action::Overwrite;
 use shelflib::op::{
     ctx::{FileSafe, FinishCtx},
     journal::OpJournal,
 };
+use shelflib::state::State;
 use stderrlog::ColorChoice;
 
+use crate::diff::{DiffOptions, Differ};
+use crate::journal::JournalFile;
 use crate::load::Loader;
 use crate::output::{Prettify, Section};
 use crate::process::{Processor, ProcessorOptions};
+use crate::state::StateFile;
+use crate::status::{Checker, StatusOptions};
+use crate::undo::{UndoOptions, Undoer};
+
+/// Name of the journal file in the data directory.
+static JOURNAL_FILE: &str = "journal";
+/// Name of the deployment state file in the data directory.
+static STATE_FILE: &str = "state.json";
+
+/// Exit code when errors were encountered.
+const EXIT_ERROR: i32 = 1;
+/// Exit code when `status` finds destinations that are out of date.
+const EXIT_DRIFT: i32 = 2;
 
 fn main() {
     let opts = Options::parse();
 
-    if cli(opts).is_err() {
-        std::process::exit(1);
+    if let Err(code) = cli(opts) {
+        std::process::exit(code);
     }
 }
 
@@ -40,24 +63,80 @@ fn main() {
     ArgGroup::new("vers")
         .args(&["verbosity", "quiet"]),
 ))]
+#[clap(subcommand_negates_reqs = true, args_conflicts_with_subcommands = true)]
 pub struct Options {
-    #[clap(short, long, parse(from_occurrences), help = "Message verbosity")]
+    #[clap(
+        short,
+        long,
+        parse(from_occurrences),
+        global = true,
+        help = "Message verbosity"
+    )]
     pub verbosity: usize,
-    #[clap(short, long, help = "Silence all output")]
+    #[clap(short, long, global = true, help = "Silence all output")]
     pub quiet: bool,
 
-    #[clap(short, long, help = "Pretend to process")]
+    #[clap(short, long, global = true, help = "Pretend to process")]
     pub noop: bool,
 
-    #[clap(long, help = "Set linking destination")]
+    #[clap(long, global = true, help = "Set linking destination")]
     pub home: Option<String>,
 
+    #[clap(
+        long,
+        global = true,
+        help = "Roll back the unfinished ops of an interrupted run"
+    )]
+    pub recover: bool,
+
+    #[clap(long, help = "Show diffs of files before writing them")]
+    pub diff: bool,
+
+    #[clap(
+        long,
+        value_name = "POLICY",
+        default_value = "replace",
+        possible_values = ["replace", "skip", "backup", "adopt", "ask", "error"],
+        help = "Set how existing destinations are handled by default"
+    )]
+    pub overwrite: Overwrite,
+
+    #[clap(long, help = "Create relative symlinks by default")]
+    pub relative: bool,
+
+    #[clap(subcommand)]
+    pub command: Option<Command>,
+
     #[clap(required = true)]
     pub packages: Vec<String>,
 }
 
+#[derive(Subcommand, Debug, Clone)]
+pub enum Command {
+    #[clap(about = "Undo the last run")]
+    Undo,
+    #[clap(about = "Remove the files deployed by packages")]
+    Uninstall {
+        #[clap(required = true)]
+        packages: Vec<String>,
+    },
+    #[clap(
+        about = "Report destinations that are out of date",
+        after_help = "Exits with status 2 if any destination is out of date."
+    )]
+    Status {
+        #[clap(required = true)]
+        packages: Vec<String>,
+    },
+    #[clap(about = "Show the changes that would be made to written files")]
+    Diff {
+        #[clap(required = true)]
+        packages: Vec<String>,
+    },
+}
+
 #[inline]
-pub fn cli(opts: Options) -> Result<(), ()> {
+pub fn cli(opts: Options) -> Result<(), i32> {
     stderrlog::new()
         .quiet(opts.quiet)
         .verbosity(opts.verbosity + 2)
@@ -66,21 +145,76 @@ pub fn cli(opts: Options) -> Result<(), ()> {
         .init()
         .unwrap();
 
-    run(opts)
-        .map_err(|_| Section::fatal().message("errors were encountered; see above"))
-        .map_err(|_| ())
+    let res = match opts.command.clone() {
+        Some(Command::Undo) => run_undo(opts),
+        Some(Command::Uninstall { packages }) => run_uninstall(opts, packages),
+        Some(Command::Status { packages }) => return run_status(opts, packages),
+        Some(Command::Diff { packages }) => run_diff(opts, packages),
+        None => run_apply(opts),
+    };
+
+    res.map_err(fatal)
 }
 
 #[inline]
-fn run(opts: Options) -> Result<(), ()> {
+fn fatal(_: ()) -> i32 {
+    Section::fatal().message("errors were encountered; see above");
+    EXIT_ERROR
+}
+
+#[inline]
+fn run_apply(opts: Options) -> Result<(), ()> {
+    let mut data = Data::load(&opts)?;
+
     let packages: Vec<_> = opts.packages.iter().map(PathBuf::from).collect();
     let loaded = Loader::new(packages).load()?;
 
-    // TODO: Load journal from filesystem.
-    let mut journal = OpJournal::new();
+    let popts = process_opts(opts, &data.dir)?;
+    let mut processor = Processor::new(popts, &mut data.journal, &mut data.file, &mut data.state);
+    let res = processor.process(&loaded.graph, &loaded.paths);
+    data.save_state().and(res)?;
 
-    let mut processor = Processor::new(process_opts(opts)?, &mut journal);
-    processor.process(&loaded.graph, &loaded.paths)?;
+    Section::message("", "");
+    Section::message("done:".green().bold(), "no issues encountered");
+
+    Ok(())
+}
+
+#[inline]
+fn run_uninstall(opts: Options, packages: Vec<String>) -> Result<(), ()> {
+    let mut data = Data::load(&opts)?;
+
+    let packages: Vec<_> = packages.iter().map(PathBuf::from).collect();
+    let loaded = Loader::new(packages).load()?;
+
+    let popts = process_opts(opts, &data.dir)?;
+    let mut processor = Processor::new(popts, &mut data.journal, &mut data.file, &mut data.state);
+    let res = processor.uninstall(&loaded.graph, &loaded.paths);
+    data.save_state().and(res)?;
+
+    Section::message("", "");
+    Section::message("done:".green().bold(), "no issues encountered");
+
+    Ok(())
+}
+
+#[inline]
+fn run_undo(opts: Options) -> Result<(), ()> {
+    let mut data = Data::load(&opts)?;
+
+    let undo_opts = UndoOptions {
+        noop: opts.noop,
+        dest: home_dir(opts.home)?,
+    };
+
+    let mut undoer = Undoer::new(
+        undo_opts,
+        &mut data.journal,
+        &mut data.file,
+        &mut data.state,
+    );
+    let res = undoer.undo_last();
+    data.save_state().and(res)?;
 
     Section::message("", "");
     Section::message("done:".green().bold(), "no issues encountered");
@@ -89,10 +223,137 @@ fn run(opts: Options) -> Result<(), ()> {
 }
 
 #[inline]
-fn process_opts(opts: Options) -> Result<ProcessorOptions, ()> {
+fn run_status(opts: Options, packages: Vec<String>) -> Result<(), i32> {
+    let packages: Vec<_> = packages.iter().map(PathBuf::from).collect();
+    let loaded = Loader::new(packages).load().map_err(fatal)?;
+
+    let status_opts = StatusOptions {
+        dest: home_dir(opts.home).map_err(fatal)?,
+    };
+    let checker = Checker::new(status_opts, &loaded.graph, &loaded.paths);
+    let drifted = checker.check().map_err(fatal)?;
+
+    Section::message("", "");
+    if drifted == 0 {
+        Section::message("done:".green().bold(), "all destinations are up to date");
+        Ok(())
+    } else {
+        Section::warning().message(format!("{} destinations are out of date", drifted));
+        Err(EXIT_DRIFT)
+    }
+}
+
+#[inline]
+fn run_diff(opts: Options, packages: Vec<String>) -> Result<(), ()> {
+    let packages: Vec<_> = packages.iter().map(PathBuf::from).collect();
+    let loaded = Loader::new(packages).load()?;
+
+    let diff_opts = DiffOptions {
+        dest: home_dir(opts.home)?,
+    };
+    let differ = Differ::new(diff_opts, &loaded.graph, &loaded.paths);
+    let changed = differ.diff()?;
+
+    Section::message("", "");
+    Section::message(
+        "done:".green().bold(),
+        format!("{} files would change", changed),
+    );
+
+    Ok(())
+}
+
+/// Auxiliary data persisted between runs.
+#[derive(Debug)]
+struct Data {
+    dir: PathBuf,
+
+    file: JournalFile,
+    journal: OpJournal,
+
+    state_file: StateFile,
+    state: State,
+}
+
+impl Data {
+    /// Load the journal and deployment state, and check for unfinished ops left in the journal by
+    /// an interrupted run. They are rolled back if `--recover` was given; otherwise, they are
+    /// reported and nothing further is done.
+    #[inline]
+    fn load(opts: &Options) -> Result<Self, ()> {
+        let dir = data_dir()?;
+
+        let mut file = JournalFile::new(dir.join(JOURNAL_FILE));
+        let journal = file.load()?;
+
+        let state_file = StateFile::new(dir.join(STATE_FILE));
+        let state = state_file.load()?;
+
+        let mut data = Self {
+            dir,
+            file,
+            journal,
+            state_file,
+            state,
+        };
+        data.recover(opts)?;
+
+        Ok(data)
+    }
+
+    #[inline]
+    fn recover(&mut self, opts: &Options) -> Result<(), ()> {
+        let undo_opts = UndoOptions {
+            noop: opts.noop,
+            dest: home_dir(opts.home.clone())?,
+        };
+
+        let mut undoer = Undoer::new(
+            undo_opts,
+            &mut self.journal,
+            &mut self.file,
+            &mut self.state,
+        );
+        if !undoer.interrupted() {
+            Ok(())
+        } else if opts.recover {
+            let res = undoer.recover();
+            self.save_state().and(res)
+        } else {
+            undoer.report_interrupted();
+            Err(())
+        }
+    }
+
+    #[inline]
+    fn save_state(&self) -> Result<(), ()> {
+        self.state_file.save(&self.state)
+    }
+}
+
+/// Return the directory in which auxiliary data (the journal and backed-up files) is stored.
+#[inline]
+fn data_dir() -> Result<PathBuf, ()> {
+    match BaseDirs::new() {
+        Some(bd) => {
+            let path = bd.data_local_dir().join(env!("CARGO_PKG_NAME"));
+            debug_assert!(path.is_absolute());
+            Ok(path)
+        }
+        None => {
+            Section::error().message("couldn't determine a suitable location for auxiliary data");
+            Err(())
+        }
+    }
+}
+
+/// Return the linking destination, which is `home` if given or the user's home directory
+/// otherwise.
+#[inline]
+fn home_dir(home: Option<String>) -> Result<PathBuf, ()> {
     let bd = Lazy::new(BaseDirs::new);
 
-    let dest = match opts.home.map(PathBuf::from) {
+    let dest = match home.map(PathBuf::from) {
         Some(home) => {
             // Ensure home directory is absolute.
             let cwd = match env::current_dir() {
@@ -114,29 +375,27 @@ fn process_opts(opts: Options) -> Result<ProcessorOptions, ()> {
     };
 
     debug_assert!(dest.is_absolute());
+    Ok(dest)
+}
+
+#[inline]
+fn process_opts(opts: Options, data_dir: &Path) -> Result<ProcessorOptions, ()> {
+    let dest = home_dir(opts.home)?;
 
     // TODO: No journal option.
-    let file_safe_path = match &*bd {
-        Some(bd) => {
-            // TODO: Extract this logic.
-            let timestamp = chrono::offset::Local::now()
-                .format("%Y-%m-%d-%H-%M-%S")
-                .to_string();
-            bd.data_local_dir()
-                .join(env!("CARGO_PKG_NAME"))
-                .join(timestamp)
-        }
-        None => {
-            Section::error().message("couldn't determine a suitable location for auxiliary data");
-            return Err(());
-        }
-    };
+    let timestamp = chrono::offset::Local::now()
+        .format("%Y-%m-%d-%H-%M-%S")
+        .to_string();
+    let file_safe_path = data_dir.join(timestamp);
     debug_assert!(file_safe_path.is_absolute());
 
     let ctx = FinishCtx::new(FileSafe::new(file_safe_path));
 
     Ok(ProcessorOptions {
         noop: opts.noop,
+        diff: opts.diff,
+        overwrite: opts.overwrite,
+        relative: opts.relative,
         dest,
         ctx,
     })
@@ -1,5 +1,6 @@
 // TODO: Efficiency of this stuff is probably awful.
 use std::fmt::Display;
+use std::io::{self, Write};
 use std::path::Path;
 
 pub use self::comb::{Prettify, Pretty};
@@ -25,6 +26,27 @@ impl Step {
     }
 }
 
+/// Interactive prompt, which is answered on the line that it is printed on.
+#[derive(Debug, Clone, Copy)]
+pub struct Prompt;
+
+impl Prompt {
+    /// Print `message` without ending the line, so that the answer can follow it.
+    #[inline]
+    pub fn message(message: impl Display) {
+        let mut stderr = io::stderr();
+        let _ = write!(stderr, "{} ", message);
+        let _ = stderr.flush();
+    }
+
+    /// Echo the pressed key, if any, and end the line of the prompt.
+    #[inline]
+    pub fn answer(key: Option<char>) {
+        let answer = key.map(|key| comb::pretty(key).bold());
+        let _ = writeln!(io::stderr(), "{}", answer.unwrap_or_else(comb::empty));
+    }
+}
+
 macro_rules! Prefixes {
     (
         $Name:ident, $name:ident, $prefix:expr,
@@ -0,0 +1,77 @@
+use shelflib::{
+    action::{write::Res, BlockAction, Resolve},
+    op::Op,
+};
+
+use super::GraphProcessor;
+use crate::ctxpath::CtxPath;
+
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
+    #[inline]
+    pub fn resolve_block(
+        &self,
+        action: BlockAction,
+        path: &CtxPath,
+    ) -> Result<Vec<Op<'static>>, ()> {
+        let res = match action.resolve() {
+            Ok(res) => res,
+            Err(err) => {
+                output::error_resolving(&err, &action, path, &self.opts.dest);
+                return Err(());
+            }
+        };
+
+        match res {
+            Res::Normal(ops) | Res::Permissions(ops) => Ok(super::write::map_ops(ops)),
+            // The rest of the file is kept, so it isn't overwritten.
+            Res::OverwriteContents(ops) | Res::OverwriteFile(ops) => {
+                output::updating(&action, path, &self.opts.dest);
+                Ok(super::write::map_ops(ops))
+            }
+            Res::Skip(_skip) => Ok(vec![]),
+        }
+    }
+}
+
+mod output {
+    use std::path::Path;
+
+    use shelflib::action::{block::Error, BlockAction};
+
+    use super::super::{describe, Describe, DescribeMode};
+    use crate::ctxpath::CtxPath;
+    use crate::output::{comb::sjoin2, Pretty, Step};
+
+    impl Describe for BlockAction {
+        #[inline]
+        fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
+            let dest = describe::dest_relative(&self.dest, dest);
+            sjoin2("writing block to", describe::mode_spath(dest, mode))
+        }
+    }
+
+    #[inline]
+    pub fn updating(action: &BlockAction, path: &CtxPath, dest: &Path) {
+        Step::note().message(sjoin2(
+            "updating block in existing",
+            describe::sdest_relative(&action.dest, dest),
+        ));
+        Step::note().context(action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn error_resolving(err: &Error, action: &BlockAction, path: &CtxPath, dest: &Path) {
+        Step::error().message(sjoin2(
+            "can't write block to",
+            describe::sdest_relative(&action.dest, dest),
+        ));
+        Step::error().context(action.describe_error(path, dest));
+        match err {
+            Error::NotFile => Step::error().reason("the destination is not a file"),
+            Error::Read(_) => Step::error().reason("the destination couldn't be read"),
+            Error::Unbalanced => {
+                Step::error().reason("the destination has a begin marker but no end marker")
+            }
+        };
+    }
+}
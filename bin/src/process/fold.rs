@@ -0,0 +1,108 @@
+use std::fs;
+use std::path::Path;
+
+use shelflib::{
+    action::{tree, Action},
+    op::Op,
+    state::{Entry, Kind},
+};
+
+use super::GraphProcessor;
+use crate::ctxpath::CtxPath;
+
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
+    /// Unfold the directory symlinks deployed by trees in fold mode that `action` would deploy
+    /// into, so that it doesn't write into the package of the tree.
+    #[inline]
+    pub fn unfold(&mut self, action: &Action, path: &CtxPath) -> Result<(), ()> {
+        for dest in action.dests() {
+            // Unfold from the top down, since unfolding a directory may expose folded
+            // directories inside it.
+            let parents: Vec<_> = dest.ancestors().skip(1).map(Path::to_path_buf).collect();
+            for parent in parents.into_iter().rev() {
+                self.unfold_dir(action, &parent, path)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn unfold_dir(&mut self, action: &Action, target: &Path, path: &CtxPath) -> Result<(), ()> {
+        let opts = self.opts;
+
+        let entry = match self.state.get(target) {
+            Some(entry) if is_folded(entry, target) => entry.clone(),
+            Some(_) | None => return Ok(()),
+        };
+        // SAFETY: Folded entries have a source.
+        let src = entry.src.as_ref().unwrap();
+
+        let ops = match tree::unfold_ops(target, src) {
+            Ok(ops) => super::link::map_ops(ops),
+            Err(_) => {
+                output::error_unfolding(target, &opts.dest);
+                return Err(());
+            }
+        };
+
+        output::unfolding(target, &opts.dest);
+        if opts.noop {
+            return Ok(());
+        }
+
+        for op in ops {
+            // The new links still belong to the package that deployed the folded directory.
+            let op_entry = match &op {
+                Op::Link(op) => Some(Entry {
+                    src: Some(op.src.clone()),
+                    ..entry.clone()
+                }),
+                _ => None,
+            };
+
+            self.finish_op(action, op.clone(), op_entry.clone(), path, &opts.dest)?;
+            self.state.apply(&op, op_entry);
+
+            if let Op::Link(op) = op {
+                self.unfolded.push(op.dest);
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Return true if `entry` is for a directory symlink at `target` deployed by a tree in fold mode.
+#[inline]
+fn is_folded(entry: &Entry, target: &Path) -> bool {
+    entry.kind == Kind::Tree
+        && fs::symlink_metadata(target).is_ok_and(|meta| meta.is_symlink())
+        && fs::metadata(target).is_ok_and(|meta| meta.is_dir())
+        && entry.matches(target)
+}
+
+mod output {
+    use std::path::Path;
+
+    use super::super::describe;
+    use crate::output::{comb::sjoin2, Step};
+
+    #[inline]
+    pub fn unfolding(target: &Path, dest: &Path) {
+        Step::warning().message(sjoin2(
+            "unfolding directory",
+            describe::sdest_relative(target, dest),
+        ));
+        Step::warning().reason("another action deploys into it");
+    }
+
+    #[inline]
+    pub fn error_unfolding(target: &Path, dest: &Path) {
+        Step::error().message(sjoin2(
+            "couldn't unfold directory",
+            describe::sdest_relative(target, dest),
+        ));
+        Step::error().reason("its source couldn't be read");
+    }
+}
@@ -9,7 +9,7 @@ use shelflib::{
 use super::GraphProcessor;
 use crate::ctxpath::CtxPath;
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn resolve_function<'lua>(
         &self,
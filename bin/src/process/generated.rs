@@ -1,20 +1,22 @@
+use std::path::Path;
+
 use shelflib::{
     action::{
-        generated::{self, Res},
-        JsonAction, Resolve, TomlAction, YamlAction,
+        generated::Res, ArrayMerge, EnvAction, IniAction, JsonAction, Overwrite, Resolve,
+        TomlAction, YamlAction,
     },
     op::Op,
 };
 
-use super::GraphProcessor;
+use super::{Describe, GraphProcessor};
 use crate::ctxpath::CtxPath;
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn resolve_yaml(
-        &self,
+        &mut self,
         action: YamlAction,
-        _path: &CtxPath,
+        path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
         let res = match action.resolve() {
             Ok(res) => res,
@@ -24,14 +26,21 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             }
         };
 
-        handle_res(res)
+        self.handle_generated_res(
+            res,
+            &action,
+            action.merge,
+            action.overwrite,
+            &action.dest,
+            path,
+        )
     }
 
     #[inline]
     pub fn resolve_toml(
-        &self,
+        &mut self,
         action: TomlAction,
-        _path: &CtxPath,
+        path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
         let res = match action.resolve() {
             Ok(res) => res,
@@ -41,14 +50,21 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             }
         };
 
-        handle_res(res)
+        self.handle_generated_res(
+            res,
+            &action,
+            action.merge,
+            action.overwrite,
+            &action.dest,
+            path,
+        )
     }
 
     #[inline]
     pub fn resolve_json(
-        &self,
+        &mut self,
         action: JsonAction,
-        _path: &CtxPath,
+        path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
         let res = match action.resolve() {
             Ok(res) => res,
@@ -58,52 +74,94 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             }
         };
 
-        handle_res(res)
+        self.handle_generated_res(
+            res,
+            &action,
+            action.merge,
+            action.overwrite,
+            &action.dest,
+            path,
+        )
     }
-}
 
-#[inline]
-fn handle_res(res: Res) -> Result<Vec<Op<'static>>, ()> {
-    match res {
-        Res::Normal(ops) => {
-            // TODO: Output
-            Ok(map_ops(ops))
-        }
-        Res::OverwriteContents(ops) => {
-            // TODO: Output
-            Ok(map_ops(ops))
-        }
-        Res::OverwriteFile(ops) => {
-            // TODO: Output
-            Ok(map_ops(ops))
-        }
-        Res::Skip(_skip) => {
-            // TODO: Output
-            Ok(vec![])
-        }
+    #[inline]
+    pub fn resolve_ini(
+        &mut self,
+        action: IniAction,
+        path: &CtxPath,
+    ) -> Result<Vec<Op<'static>>, ()> {
+        let res = match action.resolve() {
+            Ok(res) => res,
+            Err(err) => {
+                output::error_resolving(&err, &action, &action.dest, path, &self.opts.dest);
+                return Err(());
+            }
+        };
+
+        self.handle_write_res(res, &action, action.overwrite, &action.dest, path)
     }
-}
 
-#[inline]
-fn map_ops(ops: Vec<generated::Op>) -> Vec<Op<'static>> {
-    ops.into_iter()
-        .map(|op| match op {
-            generated::Op::Rm(op) => Op::Rm(op),
-            generated::Op::Create(op) => Op::Create(op),
-            generated::Op::Write(op) => Op::Write(op),
-            generated::Op::Mkdir(op) => Op::Mkdir(op),
-        })
-        .collect()
+    #[inline]
+    pub fn resolve_env(
+        &mut self,
+        action: EnvAction,
+        path: &CtxPath,
+    ) -> Result<Vec<Op<'static>>, ()> {
+        let res = match action.resolve() {
+            Ok(res) => res,
+            Err(err) => {
+                output::error_resolving(&err, &action, &action.dest, path, &self.opts.dest);
+                return Err(());
+            }
+        };
+
+        self.handle_write_res(res, &action, action.overwrite, &action.dest, path)
+    }
+
+    /// Return the operations of the resolution of a generated file. Merging into an existing
+    /// destination keeps its other contents, so it doesn't go through the overwrite policy.
+    #[inline]
+    fn handle_generated_res(
+        &mut self,
+        res: Res,
+        action: &dyn Describe,
+        merge: Option<ArrayMerge>,
+        policy: Option<Overwrite>,
+        target: &Path,
+        path: &CtxPath,
+    ) -> Result<Vec<Op<'static>>, ()> {
+        match res {
+            Res::OverwriteContents(ops) if merge.is_some() => {
+                output::merging(action, target, path, &self.opts.dest);
+                Ok(super::write::map_ops(ops))
+            }
+            res => self.handle_write_res(res, action, policy, target, path),
+        }
+    }
 }
 
 mod output {
     use std::path::Path;
 
-    use shelflib::action::{JsonAction, TomlAction, YamlAction};
+    use std::fmt::Display;
+
+    use shelflib::action::{EnvAction, IniAction, JsonAction, TomlAction, YamlAction};
 
     use super::super::{describe, Describe, DescribeMode};
     use crate::ctxpath::CtxPath;
-    use crate::output::{comb::sjoin4, Pretty};
+    use crate::output::{
+        comb::{sjoin2, sjoin4},
+        Pretty, Step,
+    };
+
+    #[inline]
+    pub fn merging(action: &dyn Describe, target: &Path, path: &CtxPath, dest: &Path) {
+        Step::note().message(sjoin2(
+            "merging into existing",
+            describe::sdest_relative(target, dest),
+        ));
+        Step::note().context(action.describe_info(path, dest));
+    }
 
     impl Describe for YamlAction {
         #[inline]
@@ -126,6 +184,38 @@ mod output {
         }
     }
 
+    impl Describe for IniAction {
+        #[inline]
+        fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
+            common_describe("ini", &self.dest, dest, mode)
+        }
+    }
+
+    impl Describe for EnvAction {
+        #[inline]
+        fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
+            common_describe("env", &self.dest, dest, mode)
+        }
+    }
+
+    #[inline]
+    pub fn error_resolving<E>(
+        err: &E,
+        action: &dyn Describe,
+        target: &Path,
+        path: &CtxPath,
+        dest: &Path,
+    ) where
+        E: Display,
+    {
+        Step::error().message(sjoin2(
+            "can't write",
+            describe::sdest_relative(target, dest),
+        ));
+        Step::error().context(action.describe_error(path, dest));
+        Step::error().reason(err);
+    }
+
     #[inline]
     fn common_describe(
         format: &str,
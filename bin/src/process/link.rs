@@ -1,37 +1,49 @@
 use shelflib::{
     action::{
-        link::{self, Error, Res},
+        diff::FileDiff,
+        link::{self, Res, Skip},
         LinkAction, Resolve,
     },
     op::Op,
 };
 
+use super::overwrite::Overwritten;
 use super::GraphProcessor;
 use crate::ctxpath::CtxPath;
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
-    pub fn resolve_link(&self, action: LinkAction, path: &CtxPath) -> Result<Vec<Op<'static>>, ()> {
+    pub fn resolve_link(
+        &mut self,
+        action: LinkAction,
+        path: &CtxPath,
+    ) -> Result<Vec<Op<'static>>, ()> {
         output::processing_link(&action, path, &self.opts.dest);
 
+        let action = LinkAction {
+            relative: action.relative.or(Some(self.opts.relative)),
+            ..action
+        };
+
         let res = match action.resolve() {
             Ok(res) => res,
             Err(err) => {
-                match err {
-                    Error::SrcMissing => output::src_missing(&action, path, &self.opts.dest),
-                }
-
+                output::error_resolving(&err, &action, path, &self.opts.dest);
                 return Err(());
             }
         };
 
         match res {
             Res::Normal(ops) => Ok(map_ops(ops)),
-            Res::Overwrite(ops) => {
-                output::overwriting(&action, path, &self.opts.dest);
+            Res::Permissions(ops) => {
+                self.up_to_date.push(action.dest.clone());
                 Ok(map_ops(ops))
             }
+            Res::Overwrite(ops) => self.overwrite(overwritten(&action, ops), path),
             Res::Skip(skip) => {
+                if let Skip::DestExists = skip {
+                    self.up_to_date.push(action.dest.clone());
+                }
                 output::skipping(&skip, &action, path, &self.opts.dest);
                 Ok(vec![])
             }
@@ -39,22 +51,41 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
     }
 }
 
+/// Return the existing destination that `action` would overwrite with `ops`.
+#[inline]
+pub fn overwritten(action: &LinkAction, ops: Vec<link::Op>) -> Overwritten<'_> {
+    Overwritten {
+        action,
+        policy: action.overwrite,
+        target: &action.dest,
+        ops: map_ops(ops),
+        adopt: action.adopt_ops().map(map_ops),
+        diff: FileDiff::of_replace(&action.src, &action.dest),
+    }
+}
+
 #[inline]
 pub fn map_ops(ops: Vec<link::Op>) -> Vec<Op<'static>> {
     ops.into_iter()
         .map(|op| match op {
             link::Op::Rm(op) => Op::Rm(op),
             link::Op::Link(op) => Op::Link(op),
+            link::Op::Hardlink(op) => Op::Hardlink(op),
             link::Op::Copy(op) => Op::Copy(op),
             link::Op::Mkdir(op) => Op::Mkdir(op),
+            link::Op::Chmod(op) => Op::Chmod(op),
+            link::Op::Chown(op) => Op::Chown(op),
         })
         .collect()
 }
 
-mod output {
+pub(super) mod output {
     use std::path::Path;
 
-    use shelflib::action::{link::Skip, LinkAction};
+    use shelflib::action::{
+        link::{Error, Skip},
+        LinkAction,
+    };
 
     use super::super::{describe, Describe, DescribeMode};
     use crate::ctxpath::CtxPath;
@@ -66,7 +97,13 @@ mod output {
     impl Describe for LinkAction {
         #[inline]
         fn describe(&self, path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-            let verb = if self.copy { "copying"} else {"linking"};
+            let verb = if self.copy {
+                "copying"
+            } else if self.hard {
+                "hard linking"
+            } else {
+                "linking"
+            };
             let src = describe::path_relative(&self.src, path);
             let dest = describe::dest_relative(&self.dest, dest);
             sjoin4(
@@ -83,6 +120,37 @@ mod output {
         Step::message(action.describe_info(path, dest));
     }
 
+    #[inline]
+    pub fn error_resolving(err: &Error, action: &LinkAction, path: &CtxPath, dest: &Path) {
+        match err {
+            Error::SrcMissing => src_missing(action, path, dest),
+            Error::SrcDir => {
+                Step::error().message(sjoin2(
+                    "can't hard link directory",
+                    describe::spath_relative(&action.src, path),
+                ));
+                Step::error().context(action.describe_info(path, dest));
+                Step::error().reason("hard links can only be made to files");
+            }
+            Error::CrossDevice => {
+                Step::error().message(sjoin2(
+                    "can't hard link to",
+                    describe::sdest_relative(&action.dest, dest),
+                ));
+                Step::error().context(action.describe_info(path, dest));
+                Step::error().reason("the source and destination are on different filesystems");
+            }
+            Error::LinkAttrs => {
+                Step::error().message(sjoin2(
+                    "can't set the mode or owner of link",
+                    describe::sdest_relative(&action.dest, dest),
+                ));
+                Step::error().context(action.describe_info(path, dest));
+                Step::error().reason("links share them with the source; use type = 'copy' instead");
+            }
+        }
+    }
+
     #[inline]
     pub fn src_missing(action: &LinkAction, path: &CtxPath, dest: &Path) {
         Step::error().message(sjoin2(
@@ -92,15 +160,6 @@ mod output {
         Step::error().context(action.describe_info(path, dest));
     }
 
-    #[inline]
-    pub fn overwriting(action: &LinkAction, path: &CtxPath, dest: &Path) {
-        Step::warning().message(sjoin2(
-            "overwriting existing",
-            describe::spath_relative(&action.dest, path),
-        ));
-        Step::warning().context(action.describe_info(path, dest));
-    }
-
     #[inline]
     pub fn skipping(skip: &Skip, action: &LinkAction, path: &CtxPath, dest: &Path) {
         let message = match skip {
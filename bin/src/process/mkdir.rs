@@ -9,10 +9,10 @@ use shelflib::{
 use super::GraphProcessor;
 use crate::ctxpath::CtxPath;
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn resolve_mkdir(
-        &self,
+        &mut self,
         action: MkdirAction,
         _path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
@@ -22,12 +22,16 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
                 // TODO: Output
                 Ok(map_ops(ops))
             }
+            Res::Permissions(ops) => {
+                self.up_to_date.push(action.path.clone());
+                Ok(map_ops(ops))
+            }
             Res::Overwrite(ops) => {
                 // TODO: Output
                 Ok(map_ops(ops))
             }
-            Res::Skip(_skip) => {
-                // TODO: Output
+            Res::Skip(mkdir::Skip::DestExists) => {
+                self.up_to_date.push(action.path.clone());
                 Ok(vec![])
             }
         }
@@ -40,6 +44,8 @@ fn map_ops(ops: Vec<mkdir::Op>) -> Vec<Op<'static>> {
         .map(|op| match op {
             mkdir::Op::Rm(op) => Op::Rm(op),
             mkdir::Op::Mkdir(op) => Op::Mkdir(op),
+            mkdir::Op::Chmod(op) => Op::Chmod(op),
+            mkdir::Op::Chown(op) => Op::Chown(op),
         })
         .collect()
 }
@@ -1,3 +1,4 @@
+mod block;
 mod command;
 mod function;
 mod generated;
@@ -7,28 +8,43 @@ mod template;
 mod tree;
 mod write;
 
+mod uninstall;
+
+mod fold;
 mod op;
+mod overwrite;
+mod prune;
 
-mod describe;
+pub mod describe;
 mod output;
 
-use std::path::PathBuf;
-use std::{collections::HashMap, path::Path};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
 
 use shelflib::{
-    action::Action,
+    action::{Action, Overwrite},
     graph::{PackageData, PackageGraph},
-    op::{ctx::FinishCtx, journal::OpJournal},
+    op::{
+        ctx::FinishCtx,
+        journal::{OpJournal, Transaction},
+    },
+    state::State,
 };
 
 use crate::ctxpath::CtxPath;
+use crate::journal::JournalFile;
 use crate::output::Pretty;
 
-pub(self) use self::describe::{Describe, DescribeMode};
+pub use self::describe::Describe;
+
+use self::describe::DescribeMode;
 
 #[derive(Debug, Clone)]
 pub struct ProcessorOptions {
     pub noop: bool,
+    pub diff: bool,
+    pub overwrite: Overwrite,
+    pub relative: bool,
     pub dest: PathBuf,
 
     pub ctx: FinishCtx,
@@ -38,21 +54,44 @@ pub struct ProcessorOptions {
 pub struct Processor<'j> {
     opts: ProcessorOptions,
     journal: &'j mut OpJournal,
+    file: &'j mut JournalFile,
+    state: &'j mut State,
 }
 
 #[derive(Debug)]
-pub(self) struct GraphProcessor<'p, 'g> {
+struct GraphProcessor<'p, 'g, 't> {
     opts: &'p ProcessorOptions,
-    journal: &'p mut OpJournal,
+    journal: &'p mut Transaction<'t>,
+    file: &'p mut JournalFile,
+    state: &'p mut State,
 
     graph: &'g PackageGraph,
     paths: &'g HashMap<PathBuf, CtxPath>,
+
+    /// Map of destinations declared by more than one package to the package that deploys them.
+    overrides: HashMap<PathBuf, PathBuf>,
+    /// Destinations of the action being processed that are already up to date. They are recorded
+    /// in the state along with the destinations that the action's ops deploy.
+    up_to_date: Vec<PathBuf>,
+    /// Destinations of the links created by unfolding directories while processing the current
+    /// package. They still belong to the trees that were folded, so they aren't pruned.
+    unfolded: Vec<PathBuf>,
 }
 
 impl<'j> Processor<'j> {
     #[inline]
-    pub fn new(opts: ProcessorOptions, journal: &'j mut OpJournal) -> Self {
-        Self { opts, journal }
+    pub fn new(
+        opts: ProcessorOptions,
+        journal: &'j mut OpJournal,
+        file: &'j mut JournalFile,
+        state: &'j mut State,
+    ) -> Self {
+        Self {
+            opts,
+            journal,
+            file,
+            state,
+        }
     }
 
     #[inline]
@@ -61,35 +100,88 @@ impl<'j> Processor<'j> {
         graph: &PackageGraph,
         paths: &HashMap<PathBuf, CtxPath>,
     ) -> Result<(), ()> {
-        let mut processor = GraphProcessor::new(&self.opts, self.journal, graph, paths);
-        processor.process()
+        // Check for conflicting destinations before anything is performed.
+        let conflicts = graph.conflicts(&self.opts.dest);
+        if !conflicts.unresolved.is_empty() {
+            for conflict in &conflicts.unresolved {
+                output::error_conflict(conflict, paths, &self.opts.dest);
+            }
+            return Err(());
+        }
+
+        let created = prune::created_dirs(self.journal);
+        self.transact(graph, paths, |gp| {
+            gp.overrides = conflicts.overrides;
+            gp.process(&created)
+        })
+    }
+
+    /// Run `f` with a [`GraphProcessor`] whose ops are all recorded in a single transaction.
+    #[inline]
+    fn transact<F>(
+        &mut self,
+        graph: &PackageGraph,
+        paths: &HashMap<PathBuf, CtxPath>,
+        f: F,
+    ) -> Result<(), ()>
+    where
+        F: FnOnce(&mut GraphProcessor<'_, '_, '_>) -> Result<(), ()>,
+    {
+        let mut t = self.journal.lock();
+        let res = f(&mut GraphProcessor::new(
+            &self.opts, &mut t, self.file, self.state, graph, paths,
+        ));
+
+        // Commit the transaction and write the commit record.
+        drop(t);
+        let synced = self.file.sync(self.journal);
+
+        res.and(synced)
     }
 }
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn new(
         opts: &'p ProcessorOptions,
-        journal: &'p mut OpJournal,
+        journal: &'p mut Transaction<'t>,
+        file: &'p mut JournalFile,
+        state: &'p mut State,
         graph: &'g PackageGraph,
         paths: &'g HashMap<PathBuf, CtxPath>,
     ) -> Self {
         Self {
             opts,
             journal,
+            file,
+            state,
             graph,
             paths,
+            overrides: HashMap::new(),
+            up_to_date: Vec::new(),
+            unfolded: Vec::new(),
         }
     }
+
+    /// Return the package that overrides the package at `path` for `dest`, if `dest` is also
+    /// declared by one.
+    #[inline]
+    pub fn overriding(&self, dest: &Path, path: &CtxPath) -> Option<&CtxPath> {
+        self.overrides
+            .get(dest)
+            .filter(|package| *package != path.abs())
+            // SAFETY: Overriding packages are in the graph, so their paths are in `paths`.
+            .map(|package| self.paths.get(package).unwrap())
+    }
 }
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
-    pub fn process(&mut self) -> Result<(), ()> {
+    pub fn process(&mut self, created: &HashSet<PathBuf>) -> Result<(), ()> {
         match self.graph.order() {
             Ok(order) => {
                 order
-                    .map(|pd| self.process_package(pd))
+                    .map(|pd| self.process_package(pd, created))
                     .collect::<Result<Vec<_>, _>>()?;
                 Ok(())
             }
@@ -101,18 +193,28 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
     }
 
     #[inline]
-    pub fn process_package(&mut self, pd: &PackageData) -> Result<(), ()> {
+    pub fn process_package(
+        &mut self,
+        pd: &PackageData,
+        created: &HashSet<PathBuf>,
+    ) -> Result<(), ()> {
         // SAFETY: Path guaranteed to be in it by `load`.
         let path = self.paths.get(&pd.path).unwrap();
 
         output::processing(path);
 
+        // Destinations declared by the package; any others it deployed before are pruned.
+        let mut declared = HashSet::new();
+
         let aiter = pd.action_iter(&self.opts.dest);
         aiter
-            .map(|action| self.process_action(action, path, &self.opts.dest))
+            .map(|action| {
+                declared.extend(action.dests());
+                self.process_action(action, path, &self.opts.dest)
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(())
+        self.prune_package(path, &declared, created)
     }
 
     #[inline]
@@ -122,6 +224,27 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
         path: &CtxPath,
         dest: &Path,
     ) -> Result<(), ()> {
+        // Skip actions whose destinations are all deployed by an overriding package. Trees
+        // skip the overridden files themselves.
+        let dests = action.dests();
+        if !matches!(action, Action::Tree(_))
+            && !dests.is_empty()
+            && dests
+                .iter()
+                .all(|adest| self.overriding(adest, path).is_some())
+        {
+            // SAFETY: Checked above.
+            let package = self.overriding(&dests[0], path).unwrap();
+            output::skipping_overridden(&action, package, path, dest);
+            return Ok(());
+        }
+
+        self.unfold(&action, path)?;
+
+        if self.opts.diff {
+            crate::diff::diff_action(&action, path, dest)?;
+        }
+
         let ops = match action.clone() {
             Action::Link(action) => self.resolve_link(action, path),
             Action::Write(action) => self.resolve_write(action, path),
@@ -132,6 +255,9 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             Action::Yaml(action) => self.resolve_yaml(action, path),
             Action::Toml(action) => self.resolve_toml(action, path),
             Action::Json(action) => self.resolve_json(action, path),
+            Action::Ini(action) => self.resolve_ini(action, path),
+            Action::Env(action) => self.resolve_env(action, path),
+            Action::Block(action) => self.resolve_block(action, path),
             Action::Command(action) => self.resolve_command(action, path),
             Action::Function(action) => self.resolve_function(action, path),
         }?;
@@ -140,6 +266,14 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             .map(|op| self.process_op(&action, op, path, dest))
             .collect::<Result<Vec<_>, _>>()?;
 
+        // Refresh the entries of owned destinations that were already up to date.
+        let up_to_date = std::mem::take(&mut self.up_to_date);
+        if !self.opts.noop {
+            for adest in up_to_date {
+                self.state.record_up_to_date(path.abs(), &action, &adest);
+            }
+        }
+
         Ok(())
     }
 }
@@ -156,6 +290,9 @@ impl<'lua> Describe for Action<'lua> {
             Action::Yaml(action) => action.describe(path, dest, mode),
             Action::Toml(action) => action.describe(path, dest, mode),
             Action::Json(action) => action.describe(path, dest, mode),
+            Action::Ini(action) => action.describe(path, dest, mode),
+            Action::Env(action) => action.describe(path, dest, mode),
+            Action::Block(action) => action.describe(path, dest, mode),
             Action::Mkdir(action) => action.describe(path, dest, mode),
             Action::Command(action) => action.describe(path, dest, mode),
             Action::Function(action) => action.describe(path, dest, mode),
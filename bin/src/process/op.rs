@@ -3,20 +3,26 @@ use std::path::Path;
 use shelflib::{
     action::Action,
     op::{
-        copy::{CopyOpError, CopyUndoOpError},
-        create::{CreateOpError, CreateUndoOpError},
+        chmod::{ChmodFinish, ChmodOpError, ChmodUndoFinish, ChmodUndoOpError},
+        chown::{ChownFinish, ChownOpError, ChownUndoFinish, ChownUndoOpError},
+        copy::{CopyFinish, CopyOpError, CopyUndoFinish, CopyUndoOpError},
+        create::{CreateFinish, CreateOpError, CreateUndoFinish, CreateUndoOpError},
         error::{
-            CopyError, CreateError, MetadataError, MkdirError, MoveError, OpenError, ReadError,
-            ReadLinkError, RemoveError, RenameError, SymlinkError, WriteError,
+            ChownError, ChownPermissionError, CopyError, CreateError, HardlinkError, MetadataError,
+            MkdirError, MoveError, OpenError, PermissionsError, ReadError, ReadLinkError,
+            RemoveError, RenameError, SymlinkError, WriteError,
         },
+        hardlink::{HardlinkFinish, HardlinkOpError, HardlinkUndoFinish, HardlinkUndoOpError},
         journal::JournalOpFinish,
-        link::{LinkOpError, LinkUndoOpError},
-        mkdir::{MkdirOpError, MkdirUndoOpError},
-        rm::{RmOpError, RmUndoOpError},
-        write::{WriteOpError, WriteUndoOpError},
-        CommandOp, CopyOp, CopyUndoOp, CreateOp, CreateUndoOp, Finish, FunctionOp, LinkOp,
-        LinkUndoOp, MkdirOp, MkdirUndoOp, Op, RmOp, RmUndoOp, WriteOp, WriteUndoOp,
+        link::{LinkFinish, LinkOpError, LinkUndoFinish, LinkUndoOpError},
+        mkdir::{MkdirFinish, MkdirOpError, MkdirUndoFinish, MkdirUndoOpError},
+        rm::{RmFinish, RmOpError, RmUndoFinish, RmUndoOpError},
+        write::{WriteFinish, WriteOpError, WriteUndoFinish, WriteUndoOpError},
+        ChmodOp, ChmodUndoOp, ChownOp, ChownUndoOp, CommandOp, CopyOp, CopyUndoOp, CreateOp,
+        CreateUndoOp, Finish, FunctionOp, HardlinkOp, HardlinkUndoOp, LinkOp, LinkUndoOp, MkdirOp,
+        MkdirUndoOp, Op, RmOp, RmUndoOp, WriteOp, WriteUndoOp,
     },
+    state::Entry,
 };
 
 use super::{describe, Describe, DescribeMode, GraphProcessor};
@@ -26,29 +32,60 @@ use crate::output::{
     spath, Pretty, Step,
 };
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn process_op<'lua>(
         &mut self,
         action: &Action<'lua>,
-        op: Op,
+        op: Op<'lua>,
+        path: &CtxPath,
+        dest: &Path,
+    ) -> Result<(), ()> {
+        // Pretend to process; nothing is performed or recorded.
+        if self.opts.noop {
+            return Ok(());
+        }
+
+        let entry = self.state.op_entry(path.abs(), action, &op);
+        self.finish_op(action, op.clone(), entry.clone(), path, dest)?;
+        self.state.apply(&op, entry);
+        Ok(())
+    }
+
+    /// Finish `op` and append it to the journal along with the state `entry` of the destination
+    /// it deploys or removes after it is finished, and the current entry of the destination.
+    /// `action` describes the context of the op in error messages.
+    #[inline]
+    pub fn finish_op<'lua>(
+        &mut self,
+        action: &dyn Describe,
+        op: Op<'lua>,
+        entry: Option<Entry>,
         path: &CtxPath,
         dest: &Path,
     ) -> Result<(), ()> {
         // TODO: Lots of cloning :(
         match op.clone() {
-            Op::Link(iop) => self.process_link_op(action, op, iop, path, dest),
-            Op::LinkUndo(iop) => self.process_link_undo_op(action, op, iop, path, dest),
-            Op::Copy(iop) => self.process_copy_op(action, op, iop, path, dest),
-            Op::CopyUndo(iop) => self.process_copy_undo_op(action, op, iop, path, dest),
-            Op::Create(iop) => self.process_create_op(action, op, iop, path, dest),
-            Op::CreateUndo(iop) => self.process_create_undo_op(action, op, iop, path, dest),
-            Op::Write(iop) => self.process_write_op(action, op, iop, path, dest),
-            Op::WriteUndo(iop) => self.process_write_undo_op(action, op, iop, path, dest),
-            Op::Mkdir(iop) => self.process_mkdir_op(action, op, iop, path, dest),
-            Op::MkdirUndo(iop) => self.process_mkdir_undo_op(action, op, iop, path, dest),
-            Op::Rm(iop) => self.process_rm_op(action, op, iop, path, dest),
-            Op::RmUndo(iop) => self.process_rm_undo_op(action, op, iop, path, dest),
+            Op::Link(iop) => self.process_link_op(action, op, iop, entry, path, dest),
+            Op::LinkUndo(iop) => self.process_link_undo_op(action, op, iop, entry, path, dest),
+            Op::Hardlink(iop) => self.process_hardlink_op(action, op, iop, entry, path, dest),
+            Op::HardlinkUndo(iop) => {
+                self.process_hardlink_undo_op(action, op, iop, entry, path, dest)
+            }
+            Op::Copy(iop) => self.process_copy_op(action, op, iop, entry, path, dest),
+            Op::CopyUndo(iop) => self.process_copy_undo_op(action, op, iop, entry, path, dest),
+            Op::Create(iop) => self.process_create_op(action, op, iop, entry, path, dest),
+            Op::CreateUndo(iop) => self.process_create_undo_op(action, op, iop, entry, path, dest),
+            Op::Write(iop) => self.process_write_op(action, op, iop, entry, path, dest),
+            Op::WriteUndo(iop) => self.process_write_undo_op(action, op, iop, entry, path, dest),
+            Op::Mkdir(iop) => self.process_mkdir_op(action, op, iop, entry, path, dest),
+            Op::MkdirUndo(iop) => self.process_mkdir_undo_op(action, op, iop, entry, path, dest),
+            Op::Rm(iop) => self.process_rm_op(action, op, iop, entry, path, dest),
+            Op::RmUndo(iop) => self.process_rm_undo_op(action, op, iop, entry, path, dest),
+            Op::Chmod(iop) => self.process_chmod_op(action, op, iop, entry, path, dest),
+            Op::ChmodUndo(iop) => self.process_chmod_undo_op(action, op, iop, entry, path, dest),
+            Op::Chown(iop) => self.process_chown_op(action, op, iop, entry, path, dest),
+            Op::ChownUndo(iop) => self.process_chown_undo_op(action, op, iop, entry, path, dest),
             Op::Command(iop) => {
                 // TODO: Output
                 match iop.finish(&self.opts.ctx) {
@@ -79,13 +116,25 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
     }
 
     #[inline]
-    pub fn op_append_finish<O>(&mut self, op: O) -> Result<(), O::Error>
+    pub fn op_append_finish<O>(
+        &mut self,
+        op: O,
+        entry: Option<Entry>,
+        prev_entry: Option<Entry>,
+    ) -> Result<(), O::Error>
     where
         O: Finish,
         O::Output: Into<JournalOpFinish>,
     {
-        let mut t = self.journal.lock();
-        t.append_finish(op, &self.opts.ctx).map(|_| ())
+        self.journal
+            .append_finish(op, &self.opts.ctx, entry, prev_entry)
+            .map(|_| ())
+    }
+
+    /// Write newly appended journal records to the journal file.
+    #[inline]
+    pub fn sync_journal(&mut self) -> Result<(), ()> {
+        self.file.sync(&*self.journal)
     }
 }
 
@@ -97,14 +146,16 @@ macro_rules! process_op_impl {
         #[inline]
         pub fn $name<'lua>(
             &mut self,
-            $action: &Action<'lua>,
+            $action: &dyn Describe,
             $op: Op<'lua>,
             $iop: $op_ty,
+            entry: Option<Entry>,
             $path: &CtxPath,
             $dest: &Path,
         ) -> Result<(), ()> {
-            match self.op_append_finish($iop) {
-                Ok(_) => Ok(()),
+            let prev_entry = self.state.prev_entry(&$op).cloned();
+            match self.op_append_finish($iop, entry, prev_entry) {
+                Ok(_) => self.sync_journal(),
                 Err($err) => {
                     $out;
                     Err(())
@@ -115,7 +166,7 @@ macro_rules! process_op_impl {
 }
 
 #[allow(unreachable_code)]
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     process_op_impl!(process_link_op, LinkOp,
         action, op, iop, path, dest, err => match err {
             LinkOpError::Symlink(err) => emit_symlink_error(err, action, op, path, dest)
@@ -128,6 +179,18 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
         }
     );
 
+    process_op_impl!(process_hardlink_op, HardlinkOp,
+        action, op, iop, path, dest, err => match err {
+            HardlinkOpError::Hardlink(err) => emit_hardlink_error(err, action, op, path, dest)
+        }
+    );
+
+    process_op_impl!(process_hardlink_undo_op, HardlinkUndoOp,
+        action, op, iop, path, dest, err => match err {
+            HardlinkUndoOpError::Remove(err) => emit_remove_error(err, action, op, path, dest)
+        }
+    );
+
     process_op_impl!(process_copy_op, CopyOp,
         action, op, iop, path, dest, err => match err {
             CopyOpError::Copy(err) => emit_copy_error(err, action, op, path, dest)
@@ -142,7 +205,11 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
 
     process_op_impl!(process_create_op, CreateOp,
         action, op, iop, path, dest, err => match err {
-            CreateOpError::Create(err) => emit_create_error(err, action, op, path, dest)
+            CreateOpError::Metadata(err) => emit_metadata_error(err, action, op, path, dest),
+            CreateOpError::Create(err) => emit_create_error(err, action, op, path, dest),
+            CreateOpError::Write(err) => emit_write_error(err, action, op, path, dest),
+            CreateOpError::Permissions(err) => emit_permissions_error(err, action, op, path, dest),
+            CreateOpError::Rename(err) => emit_rename_error(err, action, op, path, dest),
         }
     );
 
@@ -156,7 +223,11 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
         action, op, iop, path, dest, err => match err {
             WriteOpError::Open(err) => emit_open_error(err, action, op, path, dest),
             WriteOpError::Read(err) => emit_read_error(err, action, op, path, dest),
+            WriteOpError::Metadata(err) => emit_metadata_error(err, action, op, path, dest),
+            WriteOpError::Create(err) => emit_create_error(err, action, op, path, dest),
             WriteOpError::Write(err) => emit_write_error(err, action, op, path, dest),
+            WriteOpError::Permissions(err) => emit_permissions_error(err, action, op, path, dest),
+            WriteOpError::Rename(err) => emit_rename_error(err, action, op, path, dest),
         }
     );
 
@@ -164,7 +235,11 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
         action, op, iop, path, dest, err => match err {
             WriteUndoOpError::Open(err) => emit_open_error(err, action, op, path, dest),
             WriteUndoOpError::Read(err) => emit_read_error(err, action, op, path, dest),
+            WriteUndoOpError::Metadata(err) => emit_metadata_error(err, action, op, path, dest),
+            WriteUndoOpError::Create(err) => emit_create_error(err, action, op, path, dest),
             WriteUndoOpError::Write(err) => emit_write_error(err, action, op, path, dest),
+            WriteUndoOpError::Permissions(err) => emit_permissions_error(err, action, op, path, dest),
+            WriteUndoOpError::Rename(err) => emit_rename_error(err, action, op, path, dest),
         }
     );
 
@@ -205,6 +280,36 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             RmUndoOpError::Mkdir(err) => emit_mkdir_error(err, action, op, path, dest),
         }
     );
+
+    process_op_impl!(process_chmod_op, ChmodOp,
+        action, op, iop, path, dest, err => match err {
+            ChmodOpError::Metadata(err) => emit_metadata_error(err, action, op, path, dest),
+            ChmodOpError::Permissions(err) => emit_permissions_error(err, action, op, path, dest),
+        }
+    );
+
+    process_op_impl!(process_chmod_undo_op, ChmodUndoOp,
+        action, op, iop, path, dest, err => match err {
+            ChmodUndoOpError::Metadata(err) => emit_metadata_error(err, action, op, path, dest),
+            ChmodUndoOpError::Permissions(err) => emit_permissions_error(err, action, op, path, dest),
+        }
+    );
+
+    process_op_impl!(process_chown_op, ChownOp,
+        action, op, iop, path, dest, err => match err {
+            ChownOpError::Metadata(err) => emit_metadata_error(err, action, op, path, dest),
+            ChownOpError::Chown(err) => emit_chown_error(err, action, op, path, dest),
+            ChownOpError::NotPermitted(err) => emit_chown_permission_error(err, action, op, path, dest),
+        }
+    );
+
+    process_op_impl!(process_chown_undo_op, ChownUndoOp,
+        action, op, iop, path, dest, err => match err {
+            ChownUndoOpError::Metadata(err) => emit_metadata_error(err, action, op, path, dest),
+            ChownUndoOpError::Chown(err) => emit_chown_error(err, action, op, path, dest),
+            ChownUndoOpError::NotPermitted(err) => emit_chown_permission_error(err, action, op, path, dest),
+        }
+    );
 }
 
 macro_rules! emit_error_impl {
@@ -212,7 +317,7 @@ macro_rules! emit_error_impl {
         #[inline]
         fn $name<'lua>(
             $err: $ty,
-            action: &Action<'lua>,
+            action: &dyn Describe,
             op: Op<'lua>,
             path: &CtxPath,
             dest: &Path,
@@ -220,8 +325,8 @@ macro_rules! emit_error_impl {
             Step::error()
                 .message($message)
                 .reason($err.inner)
-                .context(op.describe(path, dest, DescribeMode::Error))
-                .context(action.describe(path, dest, DescribeMode::Error));
+                .context(op.describe_error(path, dest))
+                .context(action.describe_error(path, dest));
         }
     };
 }
@@ -230,6 +335,29 @@ emit_error_impl!(emit_symlink_error, SymlinkError:
     err => sjoin2("couldn't symlink to", spath(err.dest))
 );
 
+emit_error_impl!(emit_hardlink_error, HardlinkError:
+    err => sjoin2("couldn't hard link to", spath(err.dest))
+);
+
+emit_error_impl!(emit_chown_error, ChownError:
+    err => sjoin2("couldn't change owner of", spath(err.path))
+);
+
+#[inline]
+fn emit_chown_permission_error<'lua>(
+    err: ChownPermissionError,
+    action: &dyn Describe,
+    op: Op<'lua>,
+    path: &CtxPath,
+    dest: &Path,
+) {
+    Step::error()
+        .message(sjoin2("not permitted to change owner of", spath(err.path)))
+        .reason("changing the owner of a file usually requires running as root")
+        .context(op.describe_error(path, dest))
+        .context(action.describe_error(path, dest));
+}
+
 emit_error_impl!(emit_copy_error, CopyError:
     err => sjoin2("couldn't copy to", spath(err.dest))
 );
@@ -254,6 +382,10 @@ emit_error_impl!(emit_open_error, OpenError:
     err => sjoin2("couldn't open", spath(err.path))
 );
 
+emit_error_impl!(emit_permissions_error, PermissionsError:
+    err => sjoin2("couldn't change permissions of", spath(err.path))
+);
+
 emit_error_impl!(emit_read_error, ReadError:
     err => sjoin2("couldn't open", spath(err.path))
 );
@@ -280,6 +412,8 @@ impl<'lua> Describe for Op<'lua> {
         match self {
             Op::Link(op) => op.describe(path, dest, mode),
             Op::LinkUndo(op) => op.describe(path, dest, mode),
+            Op::Hardlink(op) => op.describe(path, dest, mode),
+            Op::HardlinkUndo(op) => op.describe(path, dest, mode),
             Op::Copy(op) => op.describe(path, dest, mode),
             Op::CopyUndo(op) => op.describe(path, dest, mode),
             Op::Create(op) => op.describe(path, dest, mode),
@@ -290,132 +424,92 @@ impl<'lua> Describe for Op<'lua> {
             Op::MkdirUndo(op) => op.describe(path, dest, mode),
             Op::Rm(op) => op.describe(path, dest, mode),
             Op::RmUndo(op) => op.describe(path, dest, mode),
+            Op::Chmod(op) => op.describe(path, dest, mode),
+            Op::ChmodUndo(op) => op.describe(path, dest, mode),
+            Op::Chown(op) => op.describe(path, dest, mode),
+            Op::ChownUndo(op) => op.describe(path, dest, mode),
             Op::Command(op) => op.describe(path, dest, mode),
             Op::Function(op) => op.describe(path, dest, mode),
         }
     }
 }
 
-impl Describe for LinkOp {
-    #[inline]
-    fn describe(&self, path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let src = describe::path_relative(&self.src, path);
-        let dest = describe::dest_relative(&self.dest, dest);
-        sjoin4(
-            "creating symlink from",
-            describe::mode_spath(src, mode),
-            "to",
-            describe::mode_spath(dest, mode),
-        )
-    }
-}
-
-impl Describe for LinkUndoOp {
-    #[inline]
-    fn describe(&self, path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let src = describe::path_relative(&self.src, path);
-        let dest = describe::dest_relative(&self.dest, dest);
-        sjoin4(
-            "undoing symlink from",
-            describe::mode_spath(src, mode),
-            "to",
-            describe::mode_spath(dest, mode),
-        )
-    }
+macro_rules! describe_src_dest_impl {
+    ($message:expr; $($ty:ty),*) => {
+        $(
+            impl Describe for $ty {
+                #[inline]
+                fn describe(&self, path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
+                    let src = describe::path_relative(&self.src, path);
+                    let dest = describe::dest_relative(&self.dest, dest);
+                    sjoin4(
+                        $message,
+                        describe::mode_spath(src, mode),
+                        "to",
+                        describe::mode_spath(dest, mode),
+                    )
+                }
+            }
+        )*
+    };
 }
 
-impl Describe for CopyOp {
-    #[inline]
-    fn describe(&self, path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let src = describe::path_relative(&self.src, path);
-        let dest = describe::dest_relative(&self.dest, dest);
-        sjoin4(
-            "copying file from",
-            describe::mode_spath(src, mode),
-            "to",
-            describe::mode_spath(dest, mode),
-        )
-    }
+macro_rules! describe_path_impl {
+    ($message:expr; $($ty:ty),*) => {
+        $(
+            impl Describe for $ty {
+                #[inline]
+                fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
+                    let path = describe::dest_relative(&self.path, dest);
+                    sjoin2($message, describe::mode_spath(path, mode))
+                }
+            }
+        )*
+    };
 }
 
-impl Describe for CopyUndoOp {
+describe_src_dest_impl!("creating symlink from"; LinkOp, LinkFinish);
+describe_src_dest_impl!("undoing symlink from"; LinkUndoOp, LinkUndoFinish);
+describe_src_dest_impl!("creating hard link from"; HardlinkOp, HardlinkFinish);
+describe_src_dest_impl!("undoing hard link from"; HardlinkUndoOp, HardlinkUndoFinish);
+describe_src_dest_impl!("copying file from"; CopyOp, CopyFinish);
+describe_src_dest_impl!("undoing copy file from"; CopyUndoOp, CopyUndoFinish);
+describe_path_impl!("creating file"; CreateOp, CreateFinish);
+describe_path_impl!("removing created file"; CreateUndoOp, CreateUndoFinish);
+describe_path_impl!("writing to"; WriteOp, WriteFinish);
+describe_path_impl!("undoing write to"; WriteUndoOp, WriteUndoFinish);
+describe_path_impl!("creating directory"; MkdirOp, MkdirFinish);
+describe_path_impl!("removing created directory"; MkdirUndoOp, MkdirUndoFinish);
+describe_path_impl!("removing file"; RmOp, RmFinish);
+describe_path_impl!("restoring removed file"; RmUndoOp, RmUndoFinish);
+describe_path_impl!("changing permissions of"; ChmodOp, ChmodFinish);
+describe_path_impl!("restoring permissions of"; ChmodUndoOp, ChmodUndoFinish);
+describe_path_impl!("changing owner of"; ChownOp, ChownFinish);
+describe_path_impl!("restoring owner of"; ChownUndoOp, ChownUndoFinish);
+
+impl Describe for JournalOpFinish {
     #[inline]
     fn describe(&self, path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let src = describe::path_relative(&self.src, path);
-        let dest = describe::dest_relative(&self.dest, dest);
-        sjoin4(
-            "undoing copy file from",
-            describe::mode_spath(src, mode),
-            "to",
-            describe::mode_spath(dest, mode),
-        )
-    }
-}
-
-impl Describe for CreateOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2("creating file", describe::mode_spath(path, mode))
-    }
-}
-
-impl Describe for CreateUndoOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2("removing created file", describe::mode_spath(path, mode))
-    }
-}
-
-impl Describe for WriteOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2("writing to", describe::mode_spath(path, mode))
-    }
-}
-
-impl Describe for WriteUndoOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2("undoing write to", describe::mode_spath(path, mode))
-    }
-}
-
-impl Describe for MkdirOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2("creating directory", describe::mode_spath(path, mode))
-    }
-}
-
-impl Describe for MkdirUndoOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2(
-            "removing created directory",
-            describe::mode_spath(path, mode),
-        )
-    }
-}
-
-impl Describe for RmOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2("removing file", describe::mode_spath(path, mode))
-    }
-}
-
-impl Describe for RmUndoOp {
-    #[inline]
-    fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
-        let path = describe::dest_relative(&self.path, dest);
-        sjoin2("restoring removed file", describe::mode_spath(path, mode))
+        match self {
+            JournalOpFinish::Link(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::LinkUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Hardlink(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::HardlinkUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Copy(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::CopyUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Create(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::CreateUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Write(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::WriteUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Mkdir(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::MkdirUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Rm(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::RmUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Chmod(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::ChmodUndo(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::Chown(fin) => fin.describe(path, dest, mode),
+            JournalOpFinish::ChownUndo(fin) => fin.describe(path, dest, mode),
+        }
     }
 }
 
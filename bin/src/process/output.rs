@@ -1,7 +1,11 @@
-use shelflib::graph::CircularDependencyError;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 
+use shelflib::graph::{CircularDependencyError, Conflict};
+
+use super::{describe, Describe};
 use crate::ctxpath::CtxPath;
-use crate::output::Section;
+use crate::output::{comb::sjoin2, Section, Step};
 
 #[inline]
 pub fn processing(path: &CtxPath) {
@@ -13,3 +17,26 @@ pub fn error_circular(err: CircularDependencyError) {
     Section::error().message("circular dependency detected");
     Section::error().context(err.path().display());
 }
+
+#[inline]
+pub fn error_conflict(conflict: &Conflict<'_>, paths: &HashMap<PathBuf, CtxPath>, dest: &Path) {
+    Section::error().message(sjoin2(
+        "conflicting destination",
+        describe::sdest_relative(&conflict.dest, dest),
+    ));
+    for decl in &conflict.declarations {
+        // SAFETY: Path guaranteed to be in it by `load`.
+        let path = paths.get(decl.package).unwrap();
+        Section::error().context(sjoin2(
+            path.rel().display(),
+            decl.action.describe_error(path, dest),
+        ));
+    }
+    Section::error().reason("set a priority with `override` in one of the packages");
+}
+
+#[inline]
+pub fn skipping_overridden(action: &dyn Describe, package: &CtxPath, path: &CtxPath, dest: &Path) {
+    Step::skipping().message(sjoin2("destination overridden by", package.rel().display()));
+    Step::skipping().context(action.describe_info(path, dest));
+}
@@ -0,0 +1,279 @@
+use std::path::Path;
+
+use shelflib::{
+    action::{diff::FileDiff, overwrite, Overwrite},
+    op::Op,
+};
+
+use super::{Describe, GraphProcessor};
+use crate::ctxpath::CtxPath;
+use crate::prompt;
+
+/// Existing destination that an action would overwrite.
+pub struct Overwritten<'a> {
+    /// Action that would overwrite the destination.
+    pub action: &'a dyn Describe,
+    /// Overwrite policy of the action; if `None`, the default policy is used.
+    pub policy: Option<Overwrite>,
+    /// Path of the destination.
+    pub target: &'a Path,
+    /// Operations that replace the destination.
+    pub ops: Vec<Op<'static>>,
+    /// Operations that adopt the destination into the package instead, if possible.
+    pub adopt: Option<Vec<Op<'static>>>,
+    /// Change to the contents of the destination, if it can be shown.
+    pub diff: Option<FileDiff>,
+}
+
+/// Choice when asked about an existing destination.
+#[derive(Debug, Clone, Copy)]
+enum Choice {
+    Diff,
+    Keep,
+    Replace,
+    Adopt,
+}
+
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
+    /// Return the operations to perform for an existing destination according to its overwrite
+    /// policy.
+    #[inline]
+    pub fn overwrite(&self, ow: Overwritten<'_>, path: &CtxPath) -> Result<Vec<Op<'static>>, ()> {
+        let dest = &self.opts.dest;
+
+        match ow.policy.unwrap_or(self.opts.overwrite) {
+            Overwrite::Replace => {
+                output::overwriting(&ow, path, dest);
+                Ok(ow.ops)
+            }
+            Overwrite::Skip => {
+                output::skipping(&ow, path, dest);
+                Ok(vec![])
+            }
+            Overwrite::Backup => {
+                let Overwritten {
+                    action,
+                    target,
+                    mut ops,
+                    ..
+                } = ow;
+
+                let op = overwrite::backup_op(target);
+                output::backing_up(action, target, &op.dest, path, dest);
+
+                ops.insert(0, Op::Copy(op));
+                Ok(ops)
+            }
+            Overwrite::Adopt if ow.adopt.is_some() => {
+                output::adopting(&ow, path, dest);
+                Ok(ow.adopt.unwrap_or_default())
+            }
+            Overwrite::Adopt => {
+                output::error_adopt(&ow, path, dest);
+                Err(())
+            }
+            Overwrite::Ask => self.ask(ow, path),
+            Overwrite::Error => {
+                output::error_existing(&ow, path, dest);
+                Err(())
+            }
+        }
+    }
+
+    #[inline]
+    fn ask(&self, ow: Overwritten<'_>, path: &CtxPath) -> Result<Vec<Op<'static>>, ()> {
+        let dest = &self.opts.dest;
+
+        // Nothing is done in noop mode, so there's no point in blocking on the prompt.
+        if self.opts.noop {
+            output::would_ask(&ow, path, dest);
+            return Ok(vec![]);
+        }
+
+        if !prompt::interactive() {
+            output::error_not_interactive(&ow, path, dest);
+            return Err(());
+        }
+
+        let mut choices = Vec::new();
+        if ow.diff.is_some() {
+            choices.push(Choice::Diff);
+        }
+        choices.extend([Choice::Keep, Choice::Replace]);
+        if ow.adopt.is_some() {
+            choices.push(Choice::Adopt);
+        }
+
+        output::asking(&ow, path, dest);
+        loop {
+            let keys: Vec<_> = choices.iter().map(|choice| choice.key()).collect();
+            let key = match prompt::key(output::prompt(&choices), &keys) {
+                Some(key) => key,
+                None => {
+                    output::error_aborted();
+                    return Err(());
+                }
+            };
+
+            // SAFETY: The key is one of the choices.
+            match choices.iter().find(|choice| choice.key() == key).unwrap() {
+                Choice::Diff => {
+                    if let Some(diff) = &ow.diff {
+                        crate::diff::show(diff, dest);
+                    }
+                }
+                Choice::Keep => {
+                    output::skipping(&ow, path, dest);
+                    return Ok(vec![]);
+                }
+                Choice::Replace => {
+                    output::overwriting(&ow, path, dest);
+                    return Ok(ow.ops);
+                }
+                Choice::Adopt => {
+                    output::adopting(&ow, path, dest);
+                    return Ok(ow.adopt.unwrap_or_default());
+                }
+            }
+        }
+    }
+}
+
+impl Choice {
+    #[inline]
+    fn key(&self) -> char {
+        match self {
+            Self::Diff => 'd',
+            Self::Keep => 'k',
+            Self::Replace => 'r',
+            Self::Adopt => 'a',
+        }
+    }
+
+    #[inline]
+    fn label(&self) -> &'static str {
+        match self {
+            Self::Diff => "[d]iff",
+            Self::Keep => "[k]eep",
+            Self::Replace => "[r]eplace",
+            Self::Adopt => "[a]dopt",
+        }
+    }
+}
+
+mod output {
+    use std::path::Path;
+
+    use super::super::describe;
+    use super::{Choice, Overwritten};
+    use crate::ctxpath::CtxPath;
+    use crate::output::{
+        comb::{indent, pretty, sjoin2, sjoin4},
+        Prettify, Pretty, Step,
+    };
+
+    #[inline]
+    pub fn overwriting(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::warning().message(sjoin2(
+            "overwriting existing",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::warning().context(ow.action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn skipping(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::skipping().message(sjoin2(
+            "keeping existing",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::skipping().context(ow.action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn backing_up(
+        action: &dyn super::Describe,
+        target: &Path,
+        backup: &Path,
+        path: &CtxPath,
+        dest: &Path,
+    ) {
+        Step::warning().message(sjoin4(
+            "backing up existing",
+            describe::sdest_relative(target, dest),
+            "to",
+            describe::sdest_relative(backup, dest),
+        ));
+        Step::warning().context(action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn adopting(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::warning().message(sjoin2(
+            "adopting existing",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::warning().context(ow.action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn asking(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::note().message(sjoin2(
+            "existing destination",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::note().context(ow.action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn would_ask(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::note().message(sjoin2(
+            "would ask about existing",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::note().context(ow.action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn prompt(choices: &[Choice]) -> Pretty {
+        let labels: Vec<_> = choices.iter().map(Choice::label).collect();
+        indent(
+            5,
+            sjoin2(pretty("    ?: ").bold(), format!("{}?", labels.join(", "))),
+        )
+    }
+
+    #[inline]
+    pub fn error_existing(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::error().message(sjoin2(
+            "refusing to overwrite existing",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::error().context(ow.action.describe_error(path, dest));
+    }
+
+    #[inline]
+    pub fn error_adopt(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::error().message(sjoin2(
+            "can't adopt existing",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::error().context(ow.action.describe_error(path, dest));
+        Step::error().reason("only files and trees can adopt their destinations");
+    }
+
+    #[inline]
+    pub fn error_not_interactive(ow: &Overwritten<'_>, path: &CtxPath, dest: &Path) {
+        Step::error().message(sjoin2(
+            "can't ask about existing",
+            describe::sdest_relative(ow.target, dest),
+        ));
+        Step::error().context(ow.action.describe_error(path, dest));
+        Step::error().reason("not running in a terminal");
+    }
+
+    #[inline]
+    pub fn error_aborted() {
+        Step::error().message("aborted");
+    }
+}
@@ -0,0 +1,463 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use shelflib::{
+    fse,
+    journal::Record,
+    op::{journal::JournalOpFinish, journal::OpJournal, MkdirUndoOp, Op, RmOp},
+    state::{Entry, Kind},
+};
+
+use super::{Describe, GraphProcessor};
+use crate::ctxpath::CtxPath;
+
+/// Return the directories that were created by [`MkdirOp`](shelflib::op::MkdirOp)s recorded in
+/// the journal and not since removed.
+#[inline]
+pub fn created_dirs(journal: &OpJournal) -> HashSet<PathBuf> {
+    let mut dirs = HashSet::new();
+    for record in journal.iter() {
+        match record {
+            Record::Atom(JournalOpFinish::Mkdir(fin)) => {
+                dirs.insert(fin.path.clone());
+            }
+            Record::Atom(JournalOpFinish::MkdirUndo(fin)) => {
+                dirs.remove(&fin.path);
+            }
+            Record::Atom(_) | Record::Commit | Record::RollbackCommit => {}
+        }
+    }
+
+    dirs
+}
+
+/// A destination that was deployed by a package but is no longer declared by it.
+#[derive(Debug, Clone)]
+pub struct Orphan {
+    pub target: PathBuf,
+    pub entry: Entry,
+}
+
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
+    /// Remove the destinations previously deployed by the package at `path` that aren't in
+    /// `declared` anymore.
+    #[inline]
+    pub fn prune_package(
+        &mut self,
+        path: &CtxPath,
+        declared: &HashSet<PathBuf>,
+        created: &HashSet<PathBuf>,
+    ) -> Result<(), ()> {
+        let unfolded = std::mem::take(&mut self.unfolded);
+        let orphans: Vec<_> = self
+            .state
+            .orphans(path.abs(), declared)
+            .filter(|(target, _)| !unfolded.iter().any(|path| path == target))
+            .map(|(target, entry)| Orphan {
+                target: target.to_path_buf(),
+                entry: entry.clone(),
+            })
+            .collect();
+
+        orphans
+            .into_iter()
+            .map(|orphan| self.prune_orphan(orphan, path, created))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(())
+    }
+
+    #[inline]
+    fn prune_orphan(
+        &mut self,
+        orphan: Orphan,
+        path: &CtxPath,
+        created: &HashSet<PathBuf>,
+    ) -> Result<(), ()> {
+        let opts = self.opts;
+        let Orphan { target, entry } = &orphan;
+
+        // If the destination is already gone, or was changed since deployment, only forget it.
+        if !fse::symlink_exists(target) {
+            return self.forget(target);
+        } else if !entry.matches(target) {
+            output::skipping_modified(&orphan, path, &opts.dest);
+            return self.forget(target);
+        }
+
+        let op = match entry.kind {
+            Kind::Mkdir if !is_empty_dir(target) => {
+                output::skipping_nonempty(&orphan, path, &opts.dest);
+                return self.forget(target);
+            }
+            Kind::Mkdir => Op::MkdirUndo(MkdirUndoOp {
+                path: target.clone(),
+            }),
+            _ => Op::Rm(RmOp {
+                path: target.clone(),
+                dir: fs::symlink_metadata(target)
+                    .map(|meta| meta.is_dir())
+                    .unwrap_or(false),
+            }),
+        };
+
+        output::pruning(&orphan, path, &opts.dest);
+        if opts.noop {
+            return Ok(());
+        }
+
+        self.finish_op(&orphan, op, None, path, &opts.dest)?;
+        self.forget(target)?;
+
+        let dirs = target.parent().map(Path::to_path_buf).into_iter().collect();
+        self.prune_dirs(&orphan, dirs, path, created)
+    }
+
+    #[inline]
+    fn forget(&mut self, target: &Path) -> Result<(), ()> {
+        if !self.opts.noop {
+            self.state.remove(target);
+        }
+        Ok(())
+    }
+
+    /// Remove `dirs` and their ancestors that were created by shelf and are now empty.
+    #[inline]
+    pub fn prune_dirs(
+        &mut self,
+        action: &dyn Describe,
+        dirs: Vec<PathBuf>,
+        path: &CtxPath,
+        created: &HashSet<PathBuf>,
+    ) -> Result<(), ()> {
+        let opts = self.opts;
+
+        let mut candidates: Vec<_> = dirs
+            .iter()
+            .flat_map(|dir| dir.ancestors().take_while(|dir| created.contains(*dir)))
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        // Remove the deepest directories first, so that their parents may be empty in turn.
+        candidates.sort_by_key(|dir| std::cmp::Reverse(dir.components().count()));
+
+        for dir in candidates {
+            if !is_empty_dir(dir) {
+                continue;
+            }
+
+            output::removing_dir(dir, &opts.dest);
+            if opts.noop {
+                continue;
+            }
+
+            let op = Op::MkdirUndo(MkdirUndoOp {
+                path: dir.to_path_buf(),
+            });
+            self.finish_op(action, op.clone(), None, path, &opts.dest)?;
+            self.state.apply(&op, None);
+        }
+
+        Ok(())
+    }
+}
+
+#[inline]
+fn is_empty_dir(path: &Path) -> bool {
+    fs::read_dir(path)
+        .map(|mut entries| entries.next().is_none())
+        .unwrap_or(false)
+}
+
+mod output {
+    use std::path::Path;
+
+    use super::super::{describe, Describe, DescribeMode};
+    use super::Orphan;
+    use crate::ctxpath::CtxPath;
+    use crate::output::{comb::sjoin2, Pretty, Step};
+
+    impl Describe for Orphan {
+        #[inline]
+        fn describe(&self, _path: &CtxPath, dest: &Path, mode: DescribeMode) -> Pretty {
+            let target = describe::dest_relative(&self.target, dest);
+            sjoin2("pruning", describe::mode_spath(target, mode))
+        }
+    }
+
+    #[inline]
+    pub fn pruning(orphan: &Orphan, path: &CtxPath, dest: &Path) {
+        Step::message(orphan.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn removing_dir(target: &Path, dest: &Path) {
+        Step::message(sjoin2(
+            "removing empty directory",
+            describe::sdest_relative(target, dest),
+        ));
+    }
+
+    #[inline]
+    pub fn skipping_modified(orphan: &Orphan, path: &CtxPath, dest: &Path) {
+        Step::skipping().message(sjoin2(
+            "modified destination",
+            describe::sdest_relative(&orphan.target, dest),
+        ));
+        Step::skipping().context(orphan.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn skipping_nonempty(orphan: &Orphan, path: &CtxPath, dest: &Path) {
+        Step::skipping().message(sjoin2(
+            "non-empty directory",
+            describe::sdest_relative(&orphan.target, dest),
+        ));
+        Step::skipping().context(orphan.describe_info(path, dest));
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::{HashMap, HashSet};
+    use std::fs;
+    use std::os::unix::fs::symlink;
+    use std::path::PathBuf;
+
+    use shelflib::{
+        action::{Action, LinkAction, Overwrite, TreeAction, WriteAction},
+        graph::PackageGraph,
+        op::{
+            ctx::{FileSafe, FinishCtx},
+            journal::OpJournal,
+        },
+        state::State,
+    };
+    use tempfile::TempDir;
+
+    use super::super::{GraphProcessor, ProcessorOptions};
+    use crate::ctxpath::CtxPath;
+    use crate::journal::JournalFile;
+    use crate::undo::{UndoOptions, Undoer};
+
+    struct Fixture {
+        _dir: TempDir,
+        path: CtxPath,
+        src: PathBuf,
+        dest: PathBuf,
+        opts: ProcessorOptions,
+        journal: OpJournal,
+        file: JournalFile,
+        state: State,
+    }
+
+    impl Fixture {
+        fn new() -> Self {
+            let dir = tempfile::tempdir().unwrap();
+            let pkg = dir.path().join("pkg");
+            let home = dir.path().join("home");
+            fs::create_dir(&pkg).unwrap();
+            fs::create_dir(&home).unwrap();
+
+            let src = pkg.join("a");
+            fs::write(&src, "a").unwrap();
+
+            Self {
+                path: CtxPath::new(&pkg, dir.path()).unwrap(),
+                src,
+                dest: home.join("a"),
+                opts: ProcessorOptions {
+                    noop: false,
+                    diff: false,
+                    overwrite: Overwrite::Skip,
+                    relative: false,
+                    dest: home,
+                    ctx: FinishCtx::new(FileSafe::new(dir.path().join("safe"))),
+                },
+                journal: OpJournal::new(),
+                file: JournalFile::new(dir.path().join("journal")),
+                state: State::new(),
+                _dir: dir,
+            }
+        }
+
+        fn link(&self) -> Action<'static> {
+            Action::Link(LinkAction {
+                src: self.src.clone(),
+                dest: self.dest.clone(),
+                copy: false,
+                hard: false,
+                relative: None,
+                prune: false,
+                optional: false,
+                overwrite: None,
+                mode: None,
+                owner: Default::default(),
+            })
+        }
+
+        /// Process `actions` as the package's declared actions in one transaction, pruning
+        /// anything else it deployed before.
+        fn run(&mut self, actions: Vec<Action<'static>>) {
+            let graph = PackageGraph::new();
+            let paths = HashMap::new();
+
+            let mut t = self.journal.lock();
+            let mut gp = GraphProcessor::new(
+                &self.opts,
+                &mut t,
+                &mut self.file,
+                &mut self.state,
+                &graph,
+                &paths,
+            );
+
+            let mut declared = HashSet::new();
+            for action in actions {
+                declared.extend(action.dests());
+                gp.process_action(action, &self.path, &self.opts.dest)
+                    .unwrap();
+            }
+            gp.prune_package(&self.path, &declared, &HashSet::new())
+                .unwrap();
+
+            drop(t);
+            self.file.sync(&self.journal).unwrap();
+        }
+
+        fn undo(&mut self) {
+            let opts = UndoOptions {
+                noop: false,
+                dest: self.opts.dest.clone(),
+            };
+            Undoer::new(opts, &mut self.journal, &mut self.file, &mut self.state)
+                .undo_last()
+                .unwrap();
+        }
+
+        fn is_deployed(&self) -> bool {
+            fs::read_link(&self.dest).ok().as_ref() == Some(&self.src)
+        }
+    }
+
+    #[test]
+    fn test_prune_pre_state() {
+        let mut fx = Fixture::new();
+
+        // Deployed by a version of shelf that didn't record state.
+        symlink(&fx.src, &fx.dest).unwrap();
+        assert!(fx.state.get(&fx.dest).is_none());
+
+        // A matching destination isn't taken over by processing the unchanged package.
+        fx.run(vec![fx.link()]);
+        assert!(fx.is_deployed());
+        assert!(fx.state.get(&fx.dest).is_none());
+
+        // And so it's left alone once the package drops it.
+        fx.run(vec![]);
+        assert!(fx.is_deployed());
+    }
+
+    #[test]
+    fn test_prune_undo_cycle() {
+        let mut fx = Fixture::new();
+
+        fx.run(vec![fx.link()]);
+        assert!(fx.is_deployed());
+
+        fx.run(vec![]);
+        assert!(fs::symlink_metadata(&fx.dest).is_err());
+        assert!(fx.state.get(&fx.dest).is_none());
+
+        // Undoing the prune restores both the link and its entry.
+        fx.undo();
+        assert!(fx.is_deployed());
+        assert!(fx.state.get(&fx.dest).is_some());
+
+        // Undoing the deploy removes them again.
+        fx.undo();
+        assert!(fs::symlink_metadata(&fx.dest).is_err());
+        assert!(fx.state.get(&fx.dest).is_none());
+
+        // Redeploying and pruning again behaves as the first time around.
+        fx.run(vec![fx.link()]);
+        assert!(fx.is_deployed());
+        fx.run(vec![]);
+        assert!(fs::symlink_metadata(&fx.dest).is_err());
+        assert!(fx.state.get(&fx.dest).is_none());
+    }
+
+    #[test]
+    fn test_prune_unfolded() {
+        let mut fx = Fixture::new();
+
+        let src = fx.path.abs().join("nvim");
+        let dest = fx.opts.dest.join(".config/nvim");
+        fs::create_dir_all(src.join("lua")).unwrap();
+        fs::write(src.join("init.lua"), "").unwrap();
+        fs::write(src.join("lua/a.lua"), "").unwrap();
+
+        let tree = Action::Tree(TreeAction {
+            src: src.clone(),
+            dest: dest.clone(),
+            globs: vec!["**/*".to_string()],
+            ignore: vec![],
+            gitignore: false,
+            copy: false,
+            hard: false,
+            relative: None,
+            prune: false,
+            fold: true,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        });
+        fx.run(vec![tree.clone()]);
+        assert_eq!(fs::read_link(&dest).unwrap(), src);
+
+        // Deploying into the folded directory unfolds it, and the links that replace it still
+        // belong to the tree.
+        let mut link = fx.link();
+        if let Action::Link(link) = &mut link {
+            link.dest = dest.join("extra");
+        }
+        fx.run(vec![tree, link]);
+        assert!(fs::symlink_metadata(&dest).unwrap().is_dir());
+        assert_eq!(
+            fs::read_link(dest.join("init.lua")).unwrap(),
+            src.join("init.lua")
+        );
+        assert_eq!(fs::read_link(dest.join("lua")).unwrap(), src.join("lua"));
+        assert!(fx.state.get(dest.join("init.lua")).is_some());
+    }
+
+    #[test]
+    fn test_prune_undo_overwrite() {
+        let mut fx = Fixture::new();
+        fx.opts.overwrite = Overwrite::Replace;
+
+        // A file that shelf didn't deploy is overwritten, and then restored by undoing.
+        fs::write(&fx.dest, "user").unwrap();
+        let write = Action::Write(WriteAction {
+            dest: fx.dest.clone(),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        });
+        fx.run(vec![write]);
+        assert_eq!(fs::read_to_string(&fx.dest).unwrap(), "contents");
+        assert!(fx.state.get(&fx.dest).is_some());
+
+        fx.undo();
+        assert_eq!(fs::read_to_string(&fx.dest).unwrap(), "user");
+        assert!(fx.state.get(&fx.dest).is_none());
+
+        // The restored file isn't owned, so it's left alone by pruning.
+        fx.run(vec![]);
+        assert_eq!(fs::read_to_string(&fx.dest).unwrap(), "user");
+    }
+}
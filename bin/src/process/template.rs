@@ -1,20 +1,24 @@
+use std::path::Path;
+
 use shelflib::{
     action::{
+        diff::FileDiff,
         template::{self, Res},
-        HandlebarsAction, LiquidAction, Resolve,
+        HandlebarsAction, LiquidAction, Overwrite, Resolve,
     },
     op::Op,
 };
 
-use super::GraphProcessor;
+use super::overwrite::Overwritten;
+use super::{Describe, GraphProcessor};
 use crate::ctxpath::CtxPath;
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn resolve_handlebars(
-        &self,
+        &mut self,
         action: HandlebarsAction,
-        _path: &CtxPath,
+        path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
         let res = match action.resolve() {
             Ok(res) => res,
@@ -24,14 +28,14 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             }
         };
 
-        handle_res(res)
+        self.handle_template_res(res, &action, action.overwrite, &action.dest, path)
     }
 
     #[inline]
     pub fn resolve_liquid(
-        &self,
+        &mut self,
         action: LiquidAction,
-        _path: &CtxPath,
+        path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
         let res = match action.resolve() {
             Ok(res) => res,
@@ -41,28 +45,46 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
             }
         };
 
-        handle_res(res)
+        self.handle_template_res(res, &action, action.overwrite, &action.dest, path)
     }
-}
 
-#[inline]
-fn handle_res(res: Res) -> Result<Vec<Op<'static>>, ()> {
-    match res {
-        Res::Normal(ops) => {
-            // TODO: Output
-            Ok(map_ops(ops))
-        }
-        Res::OverwriteContents(ops) => {
-            // TODO: Output
-            Ok(map_ops(ops))
-        }
-        Res::OverwriteFile(ops) => {
-            // TODO: Output
-            Ok(map_ops(ops))
-        }
-        Res::Skip(_skip) => {
-            // TODO: Output
-            Ok(vec![])
+    #[inline]
+    fn handle_template_res(
+        &mut self,
+        res: Res,
+        action: &dyn Describe,
+        policy: Option<Overwrite>,
+        target: &Path,
+        path: &CtxPath,
+    ) -> Result<Vec<Op<'static>>, ()> {
+        match res {
+            Res::Normal(ops) => {
+                // TODO: Output
+                Ok(map_ops(ops))
+            }
+            Res::Permissions(ops) => {
+                self.up_to_date.push(target.to_path_buf());
+                Ok(map_ops(ops))
+            }
+            Res::OverwriteContents(ops) | Res::OverwriteFile(ops) => {
+                let overwritten = Overwritten {
+                    action,
+                    policy,
+                    target,
+                    diff: FileDiff::of_overwrite(&ops),
+                    ops: map_ops(ops),
+                    adopt: None,
+                };
+                self.overwrite(overwritten, path)
+            }
+            Res::Skip(template::Skip::DestExists) => {
+                self.up_to_date.push(target.to_path_buf());
+                Ok(vec![])
+            }
+            Res::Skip(_skip) => {
+                // TODO: Output
+                Ok(vec![])
+            }
         }
     }
 }
@@ -75,6 +97,8 @@ fn map_ops(ops: Vec<template::Op>) -> Vec<Op<'static>> {
             template::Op::Create(op) => Op::Create(op),
             template::Op::Write(op) => Op::Write(op),
             template::Op::Mkdir(op) => Op::Mkdir(op),
+            template::Op::Chmod(op) => Op::Chmod(op),
+            template::Op::Chown(op) => Op::Chown(op),
         })
         .collect()
 }
@@ -1,58 +1,97 @@
 use shelflib::{
-    action::{link, tree::Res, Resolve, TreeAction},
+    action::{link, Resolve, TreeAction},
     op::Op,
 };
 
 use super::GraphProcessor;
 use crate::ctxpath::CtxPath;
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn resolve_tree(
-        &self,
+        &mut self,
         action: TreeAction,
-        _path: &CtxPath,
+        path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
-        let res = match action.resolve() {
-            Ok(res) => res,
+        let action = TreeAction {
+            relative: action.relative.or(Some(self.opts.relative)),
+            ..action
+        };
+
+        let actions = match action.link_actions() {
+            Ok(Ok(actions)) => actions,
+            Ok(Err(_skip)) => {
+                // TODO: Output
+                return Ok(vec![]);
+            }
             Err(_err) => {
                 // TODO: Output
                 return Err(());
             }
         };
 
-        match res {
-            Res::Normal(res) => {
-                // TODO: Output
-                let ops = res
-                    .into_iter()
-                    .flat_map(|res| match res {
-                        link::Res::Normal(ops) => super::link::map_ops(ops),
-                        link::Res::Overwrite(ops) => super::link::map_ops(ops),
-                        link::Res::Skip(_skip) => {
-                            // TODO: Output
-                            vec![]
-                        }
-                    })
-                    .collect();
-                Ok(ops)
+        // TODO: Output
+        let mut ops = Vec::new();
+        for action in actions {
+            if let Some(package) = self.overriding(&action.dest, path) {
+                super::output::skipping_overridden(&action, package, path, &self.opts.dest);
+                continue;
             }
-            Res::Skip(_skip) => {
-                // TODO: Output
-                Ok(vec![])
+
+            let res = match action.resolve() {
+                Ok(res) => res,
+                Err(err) => {
+                    super::link::output::error_resolving(&err, &action, path, &self.opts.dest);
+                    return Err(());
+                }
+            };
+
+            match res {
+                link::Res::Normal(lops) => ops.extend(super::link::map_ops(lops)),
+                link::Res::Permissions(lops) => {
+                    self.up_to_date.push(action.dest.clone());
+                    ops.extend(super::link::map_ops(lops))
+                }
+                link::Res::Overwrite(lops) => {
+                    let overwritten = super::link::overwritten(&action, lops);
+                    ops.extend(self.overwrite(overwritten, path)?);
+                }
+                link::Res::Skip(link::Skip::DestExists) => {
+                    self.up_to_date.push(action.dest.clone());
+                }
+                link::Res::Skip(_skip) => {
+                    // TODO: Output
+                }
             }
         }
+
+        let prune_ops = match action.prune_ops() {
+            Ok(prune_ops) => prune_ops,
+            Err(err) => {
+                output::error_pruning(&err, &action, path, &self.opts.dest);
+                return Err(());
+            }
+        };
+        for op in prune_ops {
+            output::pruning(&op.path, &action, path, &self.opts.dest);
+            ops.push(Op::Rm(op));
+        }
+
+        Ok(ops)
     }
 }
 
 mod output {
     use std::path::Path;
 
-    use shelflib::action::TreeAction;
+    use shelflib::action::{tree::Error, TreeAction};
 
     use super::super::{describe, Describe, DescribeMode};
     use crate::ctxpath::CtxPath;
-    use crate::output::{comb::sjoin4, Pretty};
+    use crate::output::{
+        comb::{sjoin2, sjoin4},
+        Pretty, Step,
+    };
 
     impl Describe for TreeAction {
         #[inline]
@@ -67,4 +106,28 @@ mod output {
             )
         }
     }
+
+    #[inline]
+    pub fn pruning(target: &Path, action: &TreeAction, path: &CtxPath, dest: &Path) {
+        Step::warning().message(sjoin2(
+            "removing extra",
+            describe::sdest_relative(target, dest),
+        ));
+        Step::warning().context(action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn error_pruning(err: &Error, action: &TreeAction, path: &CtxPath, dest: &Path) {
+        Step::error().message(sjoin2(
+            "can't find extra files in",
+            describe::sdest_relative(&action.dest, dest),
+        ));
+        Step::error().context(action.describe_error(path, dest));
+        match err {
+            Error::Pattern(err) => Step::error().reason(err),
+            Error::Glob(err) => Step::error().reason(err),
+            Error::IgnoreFile(err) => Step::error().reason(err),
+            Error::SrcMissing | Error::Link(_) => Step::error().reason(err),
+        };
+    }
 }
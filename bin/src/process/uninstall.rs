@@ -0,0 +1,246 @@
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use shelflib::{
+    action::{uninstall::Res, Action, Uninstall},
+    graph::{PackageData, PackageGraph},
+    op::Op,
+};
+
+use super::prune::created_dirs;
+use super::{Describe, GraphProcessor, Processor};
+use crate::ctxpath::CtxPath;
+
+impl<'j> Processor<'j> {
+    /// Remove everything deployed by the packages in `graph` that still matches what they would
+    /// deploy, and prune the directories left empty that were created by shelf.
+    #[inline]
+    pub fn uninstall(
+        &mut self,
+        graph: &PackageGraph,
+        paths: &HashMap<PathBuf, CtxPath>,
+    ) -> Result<(), ()> {
+        let created = created_dirs(self.journal);
+        self.transact(graph, paths, |gp| gp.uninstall(&created))
+    }
+}
+
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
+    #[inline]
+    pub fn uninstall(&mut self, created: &HashSet<PathBuf>) -> Result<(), ()> {
+        match self.graph.order() {
+            Ok(order) => {
+                // Uninstall dependents before their dependencies.
+                let order: Vec<_> = order.collect();
+                order
+                    .into_iter()
+                    .rev()
+                    .map(|pd| self.uninstall_package(pd, created))
+                    .collect::<Result<Vec<_>, _>>()?;
+                Ok(())
+            }
+            Err(err) => {
+                super::output::error_circular(err);
+                Err(())
+            }
+        }
+    }
+
+    #[inline]
+    pub fn uninstall_package(
+        &mut self,
+        pd: &PackageData,
+        created: &HashSet<PathBuf>,
+    ) -> Result<(), ()> {
+        // SAFETY: Path guaranteed to be in it by `load`.
+        let path = self.paths.get(&pd.path).unwrap();
+
+        output::uninstalling(path);
+
+        let aiter = pd.action_iter(&self.opts.dest);
+        aiter
+            .map(|action| self.uninstall_action(action, path, created))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(())
+    }
+
+    #[inline]
+    pub fn uninstall_action(
+        &mut self,
+        action: Action,
+        path: &CtxPath,
+        created: &HashSet<PathBuf>,
+    ) -> Result<(), ()> {
+        let dest = &self.opts.dest;
+
+        let op = match &action {
+            Action::Link(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Tree(a) => {
+                let resvec = match a.uninstall() {
+                    Ok(resvec) => resvec,
+                    Err(_) => {
+                        output::error_resolving(a, path, dest);
+                        return Err(());
+                    }
+                };
+
+                let ops = resvec
+                    .into_iter()
+                    .filter_map(|(la, res)| handle_res(res, &la, &la.dest, path, dest));
+                return self.uninstall_ops(&action, ops, vec![], path, created);
+            }
+            Action::Write(a) => handle_res(a.uninstall(), a, &a.dest, path, dest),
+            Action::Handlebars(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Liquid(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Yaml(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Toml(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Json(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Ini(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Env(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            Action::Block(a) => handle_result(a.uninstall(), a, &a.dest, path, dest)?,
+            // Directories are only pruned if empty.
+            Action::Mkdir(a) => {
+                let dirs = vec![a.path.clone()];
+                return self.uninstall_ops(&action, None, dirs, path, created);
+            }
+            // Commands and functions can't be undone.
+            Action::Command(_) | Action::Function(_) => None,
+        };
+
+        self.uninstall_ops(&action, op, vec![], path, created)
+    }
+
+    /// Perform the removal `ops`, and then prune `dirs` and the parents of removed files.
+    /// Files that blocks are removed from are kept.
+    #[inline]
+    fn uninstall_ops<I>(
+        &mut self,
+        action: &Action,
+        ops: I,
+        mut dirs: Vec<PathBuf>,
+        path: &CtxPath,
+        created: &HashSet<PathBuf>,
+    ) -> Result<(), ()>
+    where
+        I: IntoIterator<Item = Op<'static>>,
+    {
+        let dest = &self.opts.dest;
+
+        for op in ops {
+            match &op {
+                Op::Rm(op) => {
+                    output::removing(&op.path, dest);
+                    dirs.extend(op.path.parent().map(Path::to_path_buf));
+                }
+                Op::Write(op) => output::removing_block(&op.path, dest),
+                _ => {}
+            }
+            self.process_op(action, op, path, dest)?;
+        }
+
+        self.prune_dirs(action, dirs, path, created)
+    }
+}
+
+/// Return the removal op of an uninstallation resolution, or report why it was skipped.
+#[inline]
+fn handle_res<A>(
+    res: Res,
+    action: &A,
+    target: &Path,
+    path: &CtxPath,
+    dest: &Path,
+) -> Option<Op<'static>>
+where
+    A: Describe,
+{
+    match res {
+        Res::Normal(op) => Some(Op::Rm(op)),
+        Res::RemoveBlock(op) => Some(Op::Write(op)),
+        Res::Skip(skip) => {
+            output::skipping(&skip, action, target, path, dest);
+            None
+        }
+    }
+}
+
+/// Like [`handle_res`], but report resolution errors.
+#[inline]
+fn handle_result<A, E>(
+    res: Result<Res, E>,
+    action: &A,
+    target: &Path,
+    path: &CtxPath,
+    dest: &Path,
+) -> Result<Option<Op<'static>>, ()>
+where
+    A: Describe,
+{
+    match res {
+        Ok(res) => Ok(handle_res(res, action, target, path, dest)),
+        Err(_) => {
+            output::error_resolving(action, path, dest);
+            Err(())
+        }
+    }
+}
+
+mod output {
+    use std::path::Path;
+
+    use shelflib::action::uninstall::Skip;
+
+    use super::super::{describe, Describe};
+    use crate::ctxpath::CtxPath;
+    use crate::output::{comb::sjoin2, Section, Step};
+
+    #[inline]
+    pub fn uninstalling(path: &CtxPath) {
+        Section::message("uninstalling", path.rel().display());
+    }
+
+    #[inline]
+    pub fn removing(target: &Path, dest: &Path) {
+        Step::message(sjoin2("removing", describe::sdest_relative(target, dest)));
+    }
+
+    #[inline]
+    pub fn removing_block(target: &Path, dest: &Path) {
+        Step::message(sjoin2(
+            "removing block from",
+            describe::sdest_relative(target, dest),
+        ));
+    }
+
+    #[inline]
+    pub fn skipping<A>(skip: &Skip, action: &A, target: &Path, path: &CtxPath, dest: &Path)
+    where
+        A: Describe,
+    {
+        let message = match skip {
+            Skip::NotDeployed => return,
+            Skip::DestMissing => sjoin2(
+                "missing destination",
+                describe::sdest_relative(target, dest),
+            ),
+            Skip::DestChanged => sjoin2(
+                "modified destination",
+                describe::sdest_relative(target, dest),
+            ),
+            Skip::Merged => sjoin2("merged destination", describe::sdest_relative(target, dest)),
+        };
+
+        Step::skipping().message(message);
+        Step::skipping().context(action.describe_info(path, dest));
+    }
+
+    #[inline]
+    pub fn error_resolving<A>(action: &A, path: &CtxPath, dest: &Path)
+    where
+        A: Describe,
+    {
+        Step::error().message("couldn't resolve action");
+        Step::error().context(action.describe_error(path, dest));
+    }
+}
@@ -1,37 +1,61 @@
+use std::path::Path;
+
 use shelflib::{
     action::{
+        diff::FileDiff,
         write::{self, Res},
-        Resolve, WriteAction,
+        Overwrite, Resolve, WriteAction,
     },
     op::Op,
 };
 
-use super::GraphProcessor;
+use super::overwrite::Overwritten;
+use super::{Describe, GraphProcessor};
 use crate::ctxpath::CtxPath;
 
-impl<'p, 'g> GraphProcessor<'p, 'g> {
+impl<'p, 'g, 't> GraphProcessor<'p, 'g, 't> {
     #[inline]
     pub fn resolve_write(
-        &self,
+        &mut self,
         action: WriteAction,
-        _path: &CtxPath,
+        path: &CtxPath,
     ) -> Result<Vec<Op<'static>>, ()> {
         let res = action.resolve();
+        self.handle_write_res(res, &action, action.overwrite, &action.dest, path)
+    }
+
+    /// Return the operations of the resolution of an action that writes to `target`.
+    #[inline]
+    pub fn handle_write_res(
+        &mut self,
+        res: Res,
+        action: &dyn Describe,
+        policy: Option<Overwrite>,
+        target: &Path,
+        path: &CtxPath,
+    ) -> Result<Vec<Op<'static>>, ()> {
         match res {
             Res::Normal(ops) => {
                 // TODO: Output
                 Ok(map_ops(ops))
             }
-            Res::OverwriteContents(ops) => {
-                // TODO: Output
+            Res::Permissions(ops) => {
+                self.up_to_date.push(target.to_path_buf());
                 Ok(map_ops(ops))
             }
-            Res::OverwriteFile(ops) => {
-                // TODO: Output
-                Ok(map_ops(ops))
+            Res::OverwriteContents(ops) | Res::OverwriteFile(ops) => {
+                let overwritten = Overwritten {
+                    action,
+                    policy,
+                    target,
+                    diff: FileDiff::of_overwrite(&ops),
+                    ops: map_ops(ops),
+                    adopt: None,
+                };
+                self.overwrite(overwritten, path)
             }
-            Res::Skip(_skip) => {
-                // TODO: Output
+            Res::Skip(write::Skip::DestExists) => {
+                self.up_to_date.push(target.to_path_buf());
                 Ok(vec![])
             }
         }
@@ -39,13 +63,15 @@ impl<'p, 'g> GraphProcessor<'p, 'g> {
 }
 
 #[inline]
-fn map_ops(ops: Vec<write::Op>) -> Vec<Op<'static>> {
+pub(super) fn map_ops(ops: Vec<write::Op>) -> Vec<Op<'static>> {
     ops.into_iter()
         .map(|op| match op {
             write::Op::Rm(op) => Op::Rm(op),
             write::Op::Create(op) => Op::Create(op),
             write::Op::Write(op) => Op::Write(op),
             write::Op::Mkdir(op) => Op::Mkdir(op),
+            write::Op::Chmod(op) => Op::Chmod(op),
+            write::Op::Chown(op) => Op::Chown(op),
         })
         .collect()
 }
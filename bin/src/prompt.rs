@@ -0,0 +1,55 @@
+use std::fmt::Display;
+use std::io;
+
+use crossterm::{
+    event::{self, Event, KeyCode, KeyEvent, KeyModifiers},
+    terminal,
+    tty::IsTty,
+};
+
+use crate::output::Prompt;
+
+/// Return true if the user can be prompted for input.
+#[inline]
+pub fn interactive() -> bool {
+    io::stdin().is_tty() && io::stderr().is_tty()
+}
+
+/// Print `message` and wait for one of `keys` to be pressed, which is echoed after it. Returns
+/// `None` if the prompt was aborted with escape or Ctrl-C, or input couldn't be read.
+#[inline]
+pub fn key(message: impl Display, keys: &[char]) -> Option<char> {
+    Prompt::message(message);
+
+    terminal::enable_raw_mode().ok()?;
+    let pressed = read_key(keys);
+    let _ = terminal::disable_raw_mode();
+
+    Prompt::answer(pressed);
+    pressed
+}
+
+#[inline]
+fn read_key(keys: &[char]) -> Option<char> {
+    loop {
+        match event::read().ok()? {
+            Event::Key(KeyEvent {
+                code: KeyCode::Char('c'),
+                modifiers,
+            }) if modifiers.contains(KeyModifiers::CONTROL) => return None,
+            Event::Key(KeyEvent {
+                code: KeyCode::Esc, ..
+            }) => return None,
+            Event::Key(KeyEvent {
+                code: KeyCode::Char(c),
+                ..
+            }) => {
+                let c = c.to_ascii_lowercase();
+                if keys.contains(&c) {
+                    return Some(c);
+                }
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,57 @@
+mod output;
+
+use std::fs::{self, File};
+use std::io;
+use std::path::PathBuf;
+
+use shelflib::state::State;
+
+/// The on-disk copy of the deployment [`State`]. Unlike the journal, the whole state is rewritten
+/// on every save.
+#[derive(Debug)]
+pub struct StateFile {
+    path: PathBuf,
+}
+
+impl StateFile {
+    #[inline]
+    pub fn new<P>(path: P) -> Self
+    where
+        P: Into<PathBuf>,
+    {
+        Self { path: path.into() }
+    }
+
+    /// Load the state from the file. If the file doesn't exist, an empty state is returned.
+    #[inline]
+    pub fn load(&self) -> Result<State, ()> {
+        match File::open(&self.path) {
+            Ok(file) => State::load(file).map_err(|err| output::error_reading(&self.path, err)),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(State::new()),
+            Err(err) => {
+                output::error_opening(&self.path, err);
+                Err(())
+            }
+        }
+    }
+
+    /// Write `state` to the file. The state is first written to a temporary file, which then
+    /// replaces the file, so that the file is never left partially written.
+    #[inline]
+    pub fn save(&self, state: &State) -> Result<(), ()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).map_err(|err| output::error_opening(&self.path, err))?;
+        }
+
+        let tmp_path = self.path.with_extension("tmp");
+        let file = File::create(&tmp_path).map_err(|err| output::error_opening(&tmp_path, err))?;
+        state
+            .write(&file)
+            .map_err(|err| output::error_writing(&self.path, err))?;
+        file.sync_data()
+            .map_err(|err| output::error_writing(&self.path, err.into()))?;
+
+        fs::rename(&tmp_path, &self.path)
+            .map_err(|err| output::error_writing(&self.path, err.into()))
+    }
+}
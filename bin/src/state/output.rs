@@ -0,0 +1,31 @@
+use std::io;
+use std::path::Path;
+
+use shelflib::state::Error;
+
+use crate::output::{comb::sjoin2, spath, Section};
+
+#[inline]
+pub fn error_opening(path: &Path, err: io::Error) {
+    Section::error()
+        .message(sjoin2("couldn't open state", spath(path)))
+        .reason(err);
+}
+
+#[inline]
+pub fn error_reading(path: &Path, err: Error) {
+    let error = Section::error().message(sjoin2("couldn't read state", spath(path)));
+    match err {
+        Error::Io(err) => error.reason(err),
+        Error::Serde(err) => error.reason(err),
+    };
+}
+
+#[inline]
+pub fn error_writing(path: &Path, err: Error) {
+    let error = Section::error().message(sjoin2("couldn't write state", spath(path)));
+    match err {
+        Error::Io(err) => error.reason(err),
+        Error::Serde(err) => error.reason(err),
+    };
+}
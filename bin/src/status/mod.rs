@@ -0,0 +1,123 @@
+mod output;
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use shelflib::{
+    action::{status::Status, Action, Check},
+    graph::{PackageData, PackageGraph},
+};
+
+use crate::ctxpath::CtxPath;
+use crate::process::Describe;
+
+#[derive(Debug, Clone)]
+pub struct StatusOptions {
+    pub dest: PathBuf,
+}
+
+/// Reports the status of the destinations of packages without performing anything.
+#[derive(Debug)]
+pub struct Checker<'g> {
+    opts: StatusOptions,
+
+    graph: &'g PackageGraph,
+    paths: &'g HashMap<PathBuf, CtxPath>,
+}
+
+impl<'g> Checker<'g> {
+    #[inline]
+    pub fn new(
+        opts: StatusOptions,
+        graph: &'g PackageGraph,
+        paths: &'g HashMap<PathBuf, CtxPath>,
+    ) -> Self {
+        Self { opts, graph, paths }
+    }
+
+    /// Report the status of every destination, and return the number of destinations that are
+    /// out of date.
+    #[inline]
+    pub fn check(&self) -> Result<usize, ()> {
+        match self.graph.order() {
+            Ok(order) => order
+                .map(|pd| self.check_package(pd))
+                .sum::<Result<usize, _>>(),
+            Err(err) => {
+                output::error_circular(err);
+                Err(())
+            }
+        }
+    }
+
+    #[inline]
+    fn check_package(&self, pd: &PackageData) -> Result<usize, ()> {
+        // SAFETY: Path guaranteed to be in it by `load`.
+        let path = self.paths.get(&pd.path).unwrap();
+
+        output::checking(path);
+
+        pd.action_iter(&self.opts.dest)
+            .map(|action| self.check_action(action, path))
+            .sum()
+    }
+
+    #[inline]
+    fn check_action(&self, action: Action<'_>, path: &CtxPath) -> Result<usize, ()> {
+        match &action {
+            Action::Link(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Tree(a) => match a.check() {
+                Ok(statuses) => Ok(statuses
+                    .into_iter()
+                    .map(|(la, status)| self.report(status, &la, &la.dest, path))
+                    .sum()),
+                Err(_) => {
+                    output::error_resolving(a, path, &self.opts.dest);
+                    Err(())
+                }
+            },
+            Action::Write(a) => Ok(self.report(a.check(), a, &a.dest, path)),
+            Action::Mkdir(a) => Ok(self.report(a.check(), a, &a.path, path)),
+            Action::Handlebars(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Liquid(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Yaml(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Toml(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Json(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Ini(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Env(a) => self.handle_result(a.check(), a, &a.dest, path),
+            Action::Block(a) => self.handle_result(a.check(), a, &a.dest, path),
+            // Commands and functions don't deploy anything.
+            Action::Command(_) | Action::Function(_) => Ok(0),
+        }
+    }
+
+    #[inline]
+    fn handle_result<A, E>(
+        &self,
+        res: Result<Status, E>,
+        action: &A,
+        target: &Path,
+        path: &CtxPath,
+    ) -> Result<usize, ()>
+    where
+        A: Describe,
+    {
+        match res {
+            Ok(status) => Ok(self.report(status, action, target, path)),
+            Err(_) => {
+                output::error_resolving(action, path, &self.opts.dest);
+                Err(())
+            }
+        }
+    }
+
+    /// Report the status of `target`, and return 1 if it is out of date or 0 otherwise.
+    #[inline]
+    fn report<A>(&self, status: Status, action: &A, target: &Path, path: &CtxPath) -> usize
+    where
+        A: Describe,
+    {
+        output::status(&status, action, target, path, &self.opts.dest);
+        usize::from(status.is_drift())
+    }
+}
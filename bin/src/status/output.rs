@@ -0,0 +1,61 @@
+use std::path::Path;
+
+use shelflib::{action::status::Status, graph::CircularDependencyError};
+
+use crate::ctxpath::CtxPath;
+use crate::output::{
+    comb::{sjoin2, sjoin3},
+    spath, Section, Step,
+};
+use crate::process::{describe, Describe};
+
+#[inline]
+pub fn checking(path: &CtxPath) {
+    Section::message("checking", path.rel().display());
+}
+
+#[inline]
+pub fn status<A>(status: &Status, action: &A, target: &Path, path: &CtxPath, dest: &Path)
+where
+    A: Describe,
+{
+    let target = describe::sdest_relative(target, dest);
+    let warning = match status {
+        Status::NotDeployed => return,
+        Status::UpToDate => {
+            Step::message(sjoin2("up to date:", target));
+            return;
+        }
+        Status::Missing => Step::warning().message(sjoin2("missing destination", target)),
+        Status::LinkElsewhere(link) => {
+            Step::warning().message(sjoin3(target, "links to", spath(link)))
+        }
+        Status::Diverged => Step::warning().message(sjoin2("modified destination", target)),
+        Status::ModeChanged(mode) => Step::warning().message(sjoin3(
+            "changed permissions of",
+            target,
+            format!("({})", mode),
+        )),
+        Status::OwnerChanged(owner) => {
+            Step::warning().message(sjoin3("changed owner of", target, format!("({})", owner)))
+        }
+        Status::Foreign => Step::warning().message(sjoin2("foreign file at", target)),
+    };
+
+    warning.context(action.describe_info(path, dest));
+}
+
+#[inline]
+pub fn error_resolving<A>(action: &A, path: &CtxPath, dest: &Path)
+where
+    A: Describe,
+{
+    Step::error().message("couldn't resolve action");
+    Step::error().context(action.describe_error(path, dest));
+}
+
+#[inline]
+pub fn error_circular(err: CircularDependencyError) {
+    Section::error().message("circular dependency detected");
+    Section::error().context(err.path().display());
+}
@@ -0,0 +1,159 @@
+mod output;
+
+use std::path::{Path, PathBuf};
+
+use shelflib::{
+    journal::Record,
+    op::journal::{JournalOpFinish, OpJournal, RollbackIter},
+    state::State,
+};
+
+use crate::ctxpath::CtxPath;
+use crate::journal::JournalFile;
+
+#[derive(Debug, Clone)]
+pub struct UndoOptions {
+    pub noop: bool,
+    pub dest: PathBuf,
+}
+
+#[derive(Debug)]
+pub struct Undoer<'j> {
+    opts: UndoOptions,
+    journal: &'j mut OpJournal,
+    file: &'j mut JournalFile,
+    state: &'j mut State,
+}
+
+impl<'j> Undoer<'j> {
+    #[inline]
+    pub fn new(
+        opts: UndoOptions,
+        journal: &'j mut OpJournal,
+        file: &'j mut JournalFile,
+        state: &'j mut State,
+    ) -> Self {
+        Self {
+            opts,
+            journal,
+            file,
+            state,
+        }
+    }
+
+    /// Roll back the last committed transaction that hasn't already been undone. Transactions that
+    /// were themselves undos are skipped, so repeated undos go further back in history.
+    #[inline]
+    pub fn undo_last(&mut self) -> Result<(), ()> {
+        output::undoing_last();
+
+        // Source paths are described relative to the current directory.
+        let cwd = CtxPath::from_cwd(".");
+
+        if self.opts.noop {
+            match self.journal.last_committed() {
+                Some(idx) => preview(
+                    self.journal.iter().rev().skip(idx + 1),
+                    &cwd,
+                    &self.opts.dest,
+                ),
+                None => output::nothing_to_undo(),
+            }
+
+            return Ok(());
+        }
+
+        match self.journal.rollback_last() {
+            Some(rollback) => process(rollback, self.file, self.state, &cwd, &self.opts),
+            None => {
+                output::nothing_to_undo();
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'j> Undoer<'j> {
+    /// Return true if the journal ends with ops that were never committed, i.e. the last run was
+    /// interrupted.
+    #[inline]
+    pub fn interrupted(&self) -> bool {
+        matches!(self.journal.latest(), Some(Record::Atom(_)))
+    }
+
+    /// Report the uncommitted ops of an interrupted run without rolling them back.
+    #[inline]
+    pub fn report_interrupted(&self) {
+        output::interrupted();
+
+        let cwd = CtxPath::from_cwd(".");
+        for record in self.journal.iter().rev() {
+            match record {
+                Record::Atom(fin) => output::unfinished(fin, &cwd, &self.opts.dest),
+                Record::Commit | Record::RollbackCommit => break,
+            }
+        }
+
+        output::recover_hint();
+    }
+
+    /// Roll back the uncommitted ops of an interrupted run.
+    #[inline]
+    pub fn recover(&mut self) -> Result<(), ()> {
+        output::recovering();
+
+        let cwd = CtxPath::from_cwd(".");
+        if self.opts.noop {
+            preview(self.journal.iter().rev(), &cwd, &self.opts.dest);
+            return Ok(());
+        }
+
+        let rollback = self.journal.rollback();
+        process(rollback, self.file, self.state, &cwd, &self.opts)
+    }
+}
+
+/// Describe the atoms of `records` up to the first commit without performing anything.
+#[inline]
+fn preview<'a, I>(records: I, cwd: &CtxPath, dest: &Path)
+where
+    I: Iterator<Item = Record<&'a JournalOpFinish>>,
+{
+    for record in records {
+        match record {
+            Record::Atom(fin) => output::would_undo(fin, cwd, dest),
+            Record::Commit | Record::RollbackCommit => break,
+        }
+    }
+}
+
+/// Perform each step of `rollback`, writing each appended record to `file` and updating `state`.
+#[inline]
+fn process(
+    mut rollback: RollbackIter<'_>,
+    file: &mut JournalFile,
+    state: &mut State,
+    cwd: &CtxPath,
+    opts: &UndoOptions,
+) -> Result<(), ()> {
+    loop {
+        match rollback.next() {
+            Some(Ok((fin, entry))) => {
+                state.record_rollback(fin, entry);
+                output::undone(fin, cwd, &opts.dest);
+            }
+            Some(Err(err)) => {
+                output::error_undoing(err);
+                // Persist the steps that were performed before failing.
+                let _ = file.sync(&rollback);
+                return Err(());
+            }
+            None => break,
+        }
+
+        file.sync(&rollback)?;
+    }
+
+    // Write the commit record.
+    file.sync(&rollback)
+}
@@ -0,0 +1,65 @@
+use std::error::Error;
+use std::path::Path;
+
+use shelflib::op::journal::{JournalOpError, JournalOpFinish};
+
+use crate::ctxpath::CtxPath;
+use crate::output::{
+    comb::{sjoin2, tick},
+    Section, Step,
+};
+use crate::process::Describe;
+
+#[inline]
+pub fn undoing_last() {
+    Section::message("undoing", "last transaction");
+}
+
+#[inline]
+pub fn nothing_to_undo() {
+    Step::note().message("no transaction to undo");
+}
+
+#[inline]
+pub fn interrupted() {
+    Section::error().message("the last run was interrupted and left unfinished ops");
+}
+
+#[inline]
+pub fn unfinished(fin: &JournalOpFinish, cwd: &CtxPath, dest: &Path) {
+    Step::message(sjoin2("unfinished:", fin.describe_info(cwd, dest)));
+}
+
+#[inline]
+pub fn recover_hint() {
+    Section::note().message(sjoin2(
+        "rerun with",
+        sjoin2(tick("--recover"), "to roll them back first"),
+    ));
+}
+
+#[inline]
+pub fn recovering() {
+    Section::message("recovering", "interrupted run");
+}
+
+#[inline]
+pub fn undone(fin: &JournalOpFinish, cwd: &CtxPath, dest: &Path) {
+    Step::message(fin.describe_info(cwd, dest));
+}
+
+#[inline]
+pub fn would_undo(fin: &JournalOpFinish, cwd: &CtxPath, dest: &Path) {
+    Step::message(sjoin2("would undo:", fin.describe_info(cwd, dest)));
+}
+
+#[inline]
+pub fn error_undoing(err: JournalOpError) {
+    // Report the most specific cause of the error.
+    let mut reason: &dyn Error = &err;
+    while let Some(source) = reason.source() {
+        reason = source;
+    }
+
+    Step::error().message("couldn't undo op").reason(reason);
+}
@@ -0,0 +1,235 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use crate::op::{CreateOp, WriteOp};
+
+use super::write::{Op, Res, Skip};
+use super::{mkdir, owner, Mode, Owner, Resolve};
+
+/// Action to insert or update a block of `contents` in the file at `dest`, leaving the rest of the
+/// file untouched. The block is delimited by marker lines, so it can be found again to be updated
+/// or removed.
+#[derive(Debug, Clone)]
+pub struct BlockAction {
+    /// Path of the file.
+    pub dest: PathBuf,
+    /// Contents of the block, without the marker lines.
+    pub contents: String,
+    /// Name that identifies the block in its marker lines, e.g. `shelf:bash`.
+    pub marker: String,
+    /// Comment prefix of the marker lines, e.g. `#`.
+    pub comment: String,
+
+    /// Permission bits to set on the destination, if it is created.
+    pub mode: Option<Mode>,
+    /// Owner and group to set on the destination, if it is created.
+    pub owner: Owner,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("destination is not a file")]
+    NotFile,
+    #[error("read error")]
+    Read(#[from] io::Error),
+    #[error("begin marker without a matching end marker")]
+    Unbalanced,
+}
+
+impl Resolve for BlockAction {
+    type Output = Result<Res, Error>;
+
+    #[inline]
+    fn resolve(&self) -> Self::Output {
+        let Self {
+            dest, mode, owner, ..
+        } = self;
+
+        // Symlinks are followed, so that the file they point to is edited. The permissions and
+        // ownership of an existing file are left alone, since it isn't owned by the action.
+        match fs::metadata(dest) {
+            Ok(meta) if meta.is_file() => {
+                let current = fs::read_to_string(dest)?;
+                let contents = self.insert(&current)?;
+
+                if contents == current {
+                    Ok(Res::Skip(Skip::DestExists))
+                } else {
+                    Ok(Res::OverwriteContents(vec![Op::Write(WriteOp {
+                        path: dest.clone(),
+                        contents: contents.into_bytes(),
+                    })]))
+                }
+            }
+            Ok(_) => Err(Error::NotFile),
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                let mut ops: Vec<_> = mkdir::mkdir_parents_ops(dest).map(Op::Mkdir).collect();
+                ops.push(Op::Create(CreateOp { path: dest.clone() }));
+                ops.extend(owner::attr_ops(dest, None, owner, *mode));
+                ops.push(Op::Write(WriteOp {
+                    path: dest.clone(),
+                    contents: self.insert("")?.into_bytes(),
+                }));
+                Ok(Res::Normal(ops))
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+impl BlockAction {
+    /// Return `current` with the block inserted, replacing the existing block if there is one.
+    /// Otherwise, the block is appended.
+    #[inline]
+    pub fn insert(&self, current: &str) -> Result<String, Error> {
+        let lines: Vec<_> = current.split_inclusive('\n').collect();
+        let block = self.block();
+
+        let contents = match self.find(&lines)? {
+            Some((begin, end)) => {
+                let mut contents = lines[..begin].concat();
+                contents.push_str(&block);
+                contents.push_str(&lines[end + 1..].concat());
+                contents
+            }
+            None => {
+                let mut contents = current.to_string();
+                if !contents.is_empty() && !contents.ends_with('\n') {
+                    contents.push('\n');
+                }
+                contents.push_str(&block);
+                contents
+            }
+        };
+        Ok(contents)
+    }
+
+    /// Return `current` with the block removed, or `None` if it doesn't contain the block.
+    #[inline]
+    pub fn remove(&self, current: &str) -> Result<Option<String>, Error> {
+        let lines: Vec<_> = current.split_inclusive('\n').collect();
+        let (begin, end) = match self.find(&lines)? {
+            Some(markers) => markers,
+            None => return Ok(None),
+        };
+
+        let mut contents = lines[..begin].concat();
+        contents.push_str(&lines[end + 1..].concat());
+        Ok(Some(contents))
+    }
+
+    /// Return the line numbers of the begin and end markers of the block in `lines`, or `None`
+    /// if there is no begin marker. A begin marker without an end marker after it is an error,
+    /// since the extent of the block can't be known.
+    #[inline]
+    fn find(&self, lines: &[&str]) -> Result<Option<(usize, usize)>, Error> {
+        let (begin_marker, end_marker) = self.markers();
+        let is = |line: &str, marker: &str| line.trim_end() == marker;
+
+        let begin = match lines.iter().position(|line| is(line, &begin_marker)) {
+            Some(begin) => begin,
+            None => return Ok(None),
+        };
+        let end = lines[begin..]
+            .iter()
+            .position(|line| is(line, &end_marker))
+            .ok_or(Error::Unbalanced)?
+            + begin;
+        Ok(Some((begin, end)))
+    }
+
+    /// Return the block with its markers.
+    #[inline]
+    fn block(&self) -> String {
+        let (begin_marker, end_marker) = self.markers();
+
+        let mut block = format!("{}\n{}", begin_marker, self.contents);
+        if !block.ends_with('\n') {
+            block.push('\n');
+        }
+        block.push_str(&end_marker);
+        block.push('\n');
+        block
+    }
+
+    #[inline]
+    fn markers(&self) -> (String, String) {
+        (
+            format!("{} BEGIN {}", self.comment, self.marker),
+            format!("{} END {}", self.comment, self.marker),
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::PathBuf;
+
+    use super::{BlockAction, Error};
+    use crate::action::Owner;
+
+    fn action(contents: &str) -> BlockAction {
+        BlockAction {
+            dest: PathBuf::new(),
+            contents: contents.to_string(),
+            marker: "shelf:bash".to_string(),
+            comment: "#".to_string(),
+            mode: None,
+            owner: Owner::default(),
+        }
+    }
+
+    #[test]
+    fn test_insert() {
+        let block = "# BEGIN shelf:bash\nalias ll='ls -l'\n# END shelf:bash\n";
+
+        let inserting = action("alias ll='ls -l'");
+        assert_eq!(inserting.insert("").unwrap(), block);
+        assert_eq!(
+            inserting.insert("export A=1").unwrap(),
+            format!("export A=1\n{}", block)
+        );
+
+        // Updating replaces the block in place.
+        let current = format!("export A=1\n{}export B=2\n", block);
+        let updated = action("alias la='ls -a'\n").insert(&current).unwrap();
+        assert_eq!(
+            updated,
+            "export A=1\n# BEGIN shelf:bash\nalias la='ls -a'\n# END shelf:bash\nexport B=2\n"
+        );
+        assert_eq!(
+            action("alias la='ls -a'").insert(&updated).unwrap(),
+            updated
+        );
+    }
+
+    #[test]
+    fn test_remove() {
+        let removing = action("alias ll='ls -l'");
+        let current =
+            "export A=1\n# BEGIN shelf:bash\nalias ll='ls -l'\n# END shelf:bash\nexport B=2\n";
+
+        assert_eq!(
+            removing.remove(current).unwrap().unwrap(),
+            "export A=1\nexport B=2\n"
+        );
+        let inserted = removing.insert("").unwrap();
+        assert_eq!(removing.remove(&inserted).unwrap().unwrap(), "");
+        assert!(removing.remove("export A=1\n").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_unbalanced() {
+        let block = action("alias ll='ls -l'");
+
+        // Without an end marker, the block can't be told apart from the rest of the file.
+        let current = "export A=1\n# BEGIN shelf:bash\nalias ll='ls -l'\nexport B=2\n";
+        assert!(matches!(block.insert(current), Err(Error::Unbalanced)));
+        assert!(matches!(block.remove(current), Err(Error::Unbalanced)));
+
+        // An end marker before the begin marker doesn't close it.
+        let current = "# END shelf:bash\n# BEGIN shelf:bash\n";
+        assert!(matches!(block.remove(current), Err(Error::Unbalanced)));
+    }
+}
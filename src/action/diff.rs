@@ -0,0 +1,206 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use similar::TextDiff;
+
+use crate::op::WriteOp;
+
+use super::{
+    block,
+    generated::{self, env, ini, json, toml, yaml},
+    template, write, BlockAction, EnvAction, HandlebarsAction, IniAction, JsonAction, LiquidAction,
+    Resolve, TomlAction, WriteAction, YamlAction,
+};
+
+/// Trait for computing the changes that an action would make to the contents of the file it
+/// writes, without performing anything.
+pub trait Diff {
+    type Output;
+
+    fn diff(&self) -> Self::Output;
+}
+
+/// Change to the contents of a destination file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileDiff {
+    /// Path of the destination.
+    pub dest: PathBuf,
+    /// Current contents of the destination, or `None` if it isn't an existing file.
+    pub current: Option<Vec<u8>>,
+    /// Contents that would be written.
+    pub contents: Vec<u8>,
+}
+
+impl FileDiff {
+    /// Render a unified diff from the current to the new contents, labelling the destination as
+    /// `name` in the headers.
+    #[inline]
+    pub fn unified(&self, name: &str) -> String {
+        let current = self.current.as_deref().map(String::from_utf8_lossy);
+        let contents = String::from_utf8_lossy(&self.contents);
+
+        let old_header = match current {
+            Some(_) => format!("a/{}", name),
+            None => "/dev/null".to_string(),
+        };
+        let new_header = format!("b/{}", name);
+
+        TextDiff::from_lines(current.as_deref().unwrap_or(""), &contents)
+            .unified_diff()
+            .header(&old_header, &new_header)
+            .to_string()
+    }
+
+    /// Return the change made by `ops`, which overwrite the contents of an existing file.
+    #[inline]
+    pub fn of_overwrite(ops: &[write::Op]) -> Option<Self> {
+        let dest = ops.iter().find_map(|op| match op {
+            write::Op::Write(op) => Some(&op.path),
+            _ => None,
+        })?;
+        Self::from_ops(ops, fs::read(dest).ok())
+    }
+
+    /// Return the change made by replacing the file at `dest` with the file at `src`, if both
+    /// are readable files.
+    #[inline]
+    pub fn of_replace(src: &Path, dest: &Path) -> Option<Self> {
+        Some(Self {
+            dest: dest.to_path_buf(),
+            current: Some(fs::read(dest).ok()?),
+            contents: fs::read(src).ok()?,
+        })
+    }
+
+    #[inline]
+    fn from_write_res(res: write::Res) -> Option<Self> {
+        match res {
+            write::Res::OverwriteContents(ops) => Self::of_overwrite(&ops),
+            // The destination doesn't exist, or isn't a file.
+            write::Res::Normal(ops) | write::Res::OverwriteFile(ops) => Self::from_ops(&ops, None),
+            write::Res::Permissions(_) | write::Res::Skip(_) => None,
+        }
+    }
+
+    #[inline]
+    fn from_template_res(res: template::Res) -> Option<Self> {
+        match res {
+            template::Res::OverwriteContents(ops) => Self::of_overwrite(&ops),
+            template::Res::Normal(ops) | template::Res::OverwriteFile(ops) => {
+                Self::from_ops(&ops, None)
+            }
+            template::Res::Permissions(_) | template::Res::Skip(_) => None,
+        }
+    }
+
+    #[inline]
+    fn from_ops(ops: &[write::Op], current: Option<Vec<u8>>) -> Option<Self> {
+        ops.iter().find_map(|op| match op {
+            write::Op::Write(WriteOp { path, contents }) => Some(Self {
+                dest: path.clone(),
+                current: current.clone(),
+                contents: contents.clone(),
+            }),
+            _ => None,
+        })
+    }
+}
+
+impl Diff for WriteAction {
+    type Output = Option<FileDiff>;
+
+    #[inline]
+    fn diff(&self) -> Self::Output {
+        FileDiff::from_write_res(self.resolve())
+    }
+}
+
+macro_rules! diff_template_impl {
+    ($ty:ty, $err:ty) => {
+        impl Diff for $ty {
+            type Output = Result<Option<FileDiff>, $err>;
+
+            #[inline]
+            fn diff(&self) -> Self::Output {
+                let res = self.resolve()?;
+                Ok(FileDiff::from_template_res(res))
+            }
+        }
+    };
+}
+
+diff_template_impl!(HandlebarsAction, template::hbs::Error);
+diff_template_impl!(LiquidAction, template::liquid::Error);
+
+macro_rules! diff_generated_impl {
+    ($ty:ty, $err:ty) => {
+        impl Diff for $ty {
+            type Output = Result<Option<FileDiff>, $err>;
+
+            #[inline]
+            fn diff(&self) -> Self::Output {
+                let res: generated::Res = self.resolve()?;
+                Ok(FileDiff::from_write_res(res))
+            }
+        }
+    };
+}
+
+diff_generated_impl!(YamlAction, yaml::Error);
+diff_generated_impl!(TomlAction, toml::Error);
+diff_generated_impl!(JsonAction, json::Error);
+diff_generated_impl!(IniAction, ini::Error);
+diff_generated_impl!(EnvAction, env::Error);
+
+impl Diff for BlockAction {
+    type Output = Result<Option<FileDiff>, block::Error>;
+
+    #[inline]
+    fn diff(&self) -> Self::Output {
+        let res = self.resolve()?;
+        Ok(FileDiff::from_write_res(res))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io;
+
+    use super::Diff;
+    use crate::action::WriteAction;
+
+    #[test]
+    fn test_write() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let action = WriteAction {
+            dest: dir.join("file"),
+            contents: b"a\nc\n".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        let diff = action.diff().unwrap();
+        assert_eq!(diff.current, None);
+        assert_eq!(
+            diff.unified("file"),
+            "--- /dev/null\n+++ b/file\n@@ -0,0 +1,2 @@\n+a\n+c\n"
+        );
+
+        fs::write(dir.join("file"), "a\nb\n")?;
+        let diff = action.diff().unwrap();
+        assert_eq!(diff.current, Some(b"a\nb\n".to_vec()));
+        assert_eq!(
+            diff.unified("file"),
+            "--- a/file\n+++ b/file\n@@ -1,2 +1,2 @@\n a\n-b\n+c\n"
+        );
+
+        fs::write(dir.join("file"), "a\nc\n")?;
+        assert_eq!(action.diff(), None);
+
+        Ok(())
+    }
+}
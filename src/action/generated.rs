@@ -1,19 +1,23 @@
 use std::path::Path;
 
 use super::write::WriteAction;
-use super::Resolve;
+use super::{ArrayMerge, Mode, Overwrite, Owner, Resolve};
 
 // Re-export action types.
-pub use self::{json::JsonAction, toml::TomlAction, yaml::YamlAction};
+pub use self::{
+    env::EnvAction, ini::IniAction, json::JsonAction, toml::TomlAction, yaml::YamlAction,
+};
 // Re-export shared Res type.
 pub use super::write::{Op, Res, Skip};
 // Re-export shared Object type.
 pub use super::object::Object;
 
+use super::merge::{self, Merge};
+
 pub mod yaml {
     use std::path::PathBuf;
 
-    use super::{Object, Res, Resolve};
+    use super::{merge, ArrayMerge, Merge, Mode, Object, Overwrite, Owner, Res, Resolve};
 
     #[derive(Debug, Clone)]
     pub struct YamlAction {
@@ -21,12 +25,20 @@ pub mod yaml {
         pub values: Object,
 
         pub header: Option<String>,
+        /// Strategy for merging arrays when merging into the existing destination; if `None`,
+        /// the whole destination is replaced.
+        pub merge: Option<ArrayMerge>,
+        pub overwrite: Option<Overwrite>,
+        pub mode: Option<Mode>,
+        pub owner: Owner,
     }
 
     #[derive(Debug, thiserror::Error)]
     pub enum Error {
         #[error("serde error")]
         Serde(#[from] serde_yaml::Error),
+        #[error("read error")]
+        Read(#[from] std::io::Error),
     }
 
     impl Resolve for YamlAction {
@@ -38,11 +50,27 @@ pub mod yaml {
                 dest,
                 values,
                 header,
+                merge,
+                overwrite,
+                mode,
+                owner,
             } = self;
 
             // Render contents.
-            let contents = serde_yaml::to_string(&values)?;
-            Ok(super::write_resolve(dest, contents, header))
+            let contents = match merge {
+                Some(arrays) => {
+                    let mut merged = match merge::read_existing(dest)? {
+                        Some(existing) => serde_yaml::from_str(&existing)?,
+                        None => serde_yaml::Value::Mapping(Default::default()),
+                    };
+                    merged.merge(serde_yaml::to_value(values)?, *arrays);
+                    serde_yaml::to_string(&merged)?
+                }
+                None => serde_yaml::to_string(&values)?,
+            };
+            Ok(super::write_resolve(
+                dest, contents, header, overwrite, mode, owner,
+            ))
         }
     }
 }
@@ -50,7 +78,7 @@ pub mod yaml {
 pub mod toml {
     use std::path::PathBuf;
 
-    use super::{Object, Res, Resolve};
+    use super::{merge, ArrayMerge, Merge, Mode, Object, Overwrite, Owner, Res, Resolve};
 
     #[derive(Debug, Clone)]
     pub struct TomlAction {
@@ -58,12 +86,22 @@ pub mod toml {
         pub values: Object,
 
         pub header: Option<String>,
+        /// Strategy for merging arrays when merging into the existing destination; if `None`,
+        /// the whole destination is replaced.
+        pub merge: Option<ArrayMerge>,
+        pub overwrite: Option<Overwrite>,
+        pub mode: Option<Mode>,
+        pub owner: Owner,
     }
 
     #[derive(Debug, thiserror::Error)]
     pub enum Error {
         #[error("serde error")]
         Serde(#[from] toml::ser::Error),
+        #[error("parse error")]
+        Parse(#[from] toml::de::Error),
+        #[error("read error")]
+        Read(#[from] std::io::Error),
     }
 
     impl Resolve for TomlAction {
@@ -75,11 +113,27 @@ pub mod toml {
                 dest,
                 values,
                 header,
+                merge,
+                overwrite,
+                mode,
+                owner,
             } = self;
 
             // Render contents.
-            let contents = toml::to_string_pretty(&values)?;
-            Ok(super::write_resolve(dest, contents, header))
+            let contents = match merge {
+                Some(arrays) => {
+                    let mut merged = match merge::read_existing(dest)? {
+                        Some(existing) => toml::from_str(&existing)?,
+                        None => toml::Value::Table(Default::default()),
+                    };
+                    merged.merge(toml::Value::try_from(values)?, *arrays);
+                    toml::to_string_pretty(&merged)?
+                }
+                None => toml::to_string_pretty(&values)?,
+            };
+            Ok(super::write_resolve(
+                dest, contents, header, overwrite, mode, owner,
+            ))
         }
     }
 }
@@ -87,18 +141,26 @@ pub mod toml {
 pub mod json {
     use std::path::PathBuf;
 
-    use super::{Object, Res, Resolve};
+    use super::{merge, ArrayMerge, Merge, Mode, Object, Overwrite, Owner, Res, Resolve};
 
     #[derive(Debug, Clone)]
     pub struct JsonAction {
         pub dest: PathBuf,
         pub values: Object,
+        /// Strategy for merging arrays when merging into the existing destination; if `None`,
+        /// the whole destination is replaced.
+        pub merge: Option<ArrayMerge>,
+        pub overwrite: Option<Overwrite>,
+        pub mode: Option<Mode>,
+        pub owner: Owner,
     }
 
     #[derive(Debug, thiserror::Error)]
     pub enum Error {
         #[error("serde error")]
         Serde(#[from] serde_json::Error),
+        #[error("read error")]
+        Read(#[from] std::io::Error),
     }
 
     impl Resolve for JsonAction {
@@ -106,27 +168,472 @@ pub mod json {
 
         #[inline]
         fn resolve(&self) -> Self::Output {
-            let Self { dest, values } = self;
+            let Self {
+                dest,
+                values,
+                merge,
+                overwrite,
+                mode,
+                owner,
+            } = self;
 
             // Render contents.
-            let contents = serde_json::to_string(&values)?;
-            Ok(super::write_resolve(dest, contents, &None))
+            let contents = match merge {
+                Some(arrays) => {
+                    let mut merged = match merge::read_existing(dest)? {
+                        // Files that are edited by hand often have comments (JSONC).
+                        Some(existing) => serde_json::from_str(&merge::strip_jsonc(&existing))?,
+                        None => serde_json::Value::Object(Default::default()),
+                    };
+                    merged.merge(serde_json::to_value(values)?, *arrays);
+                    serde_json::to_string_pretty(&merged)?
+                }
+                None => serde_json::to_string_pretty(&values)?,
+            };
+            Ok(super::write_resolve(
+                dest, contents, &None, overwrite, mode, owner,
+            ))
         }
     }
 }
 
+pub mod ini {
+    use std::collections::BTreeMap;
+    use std::path::PathBuf;
+
+    use super::{Mode, Object, Overwrite, Owner, Res, Resolve};
+    use crate::action::object::Value;
+
+    /// Action to write `values` to an INI file at `dest`.
+    ///
+    /// Values that are objects become sections, and values nested in sections become subsections
+    /// in the style of git configs, e.g. `[remote "origin"]`. The other top-level values are
+    /// written before any section. Arrays are written as the key repeated for each element, like
+    /// in systemd units. Keys, section names and values are written as is, so they can't contain
+    /// anything that would be read back differently, such as line breaks.
+    #[derive(Debug, Clone)]
+    pub struct IniAction {
+        pub dest: PathBuf,
+        pub values: Object,
+
+        pub header: Option<String>,
+        pub overwrite: Option<Overwrite>,
+        pub mode: Option<Mode>,
+        pub owner: Owner,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum Error {
+        #[error("value of {0:?} can't be written to an ini file")]
+        Unsupported(String),
+        #[error("{0:?} can't be used as a key or section name in an ini file")]
+        InvalidKey(String),
+    }
+
+    impl Resolve for IniAction {
+        type Output = Result<Res, Error>;
+
+        #[inline]
+        fn resolve(&self) -> Self::Output {
+            let Self {
+                dest,
+                values,
+                header,
+                overwrite,
+                mode,
+                owner,
+            } = self;
+
+            // Render contents.
+            let contents = to_string(values)?;
+            Ok(super::write_resolve(
+                dest, contents, header, overwrite, mode, owner,
+            ))
+        }
+    }
+
+    /// Render `values` in the INI format.
+    #[inline]
+    pub fn to_string(values: &Object) -> Result<String, Error> {
+        let mut out = String::new();
+        let mut sections = Vec::new();
+        for (key, value) in &values.0 {
+            match value {
+                Value::Object(entries) => sections.push((key.clone(), entries)),
+                value => entry(&mut out, key, value)?,
+            }
+        }
+
+        for (name, entries) in sections {
+            check_key(&name)?;
+            section(&mut out, &name, entries, true)?;
+        }
+
+        Ok(out)
+    }
+
+    #[inline]
+    fn section(
+        out: &mut String,
+        name: &str,
+        entries: &BTreeMap<String, Value>,
+        nest: bool,
+    ) -> Result<(), Error> {
+        let mut body = String::new();
+        let mut subsections = Vec::new();
+        for (key, value) in entries {
+            match value {
+                Value::Object(entries) if nest => {
+                    check_key(key)?;
+                    subsections.push((key, entries))
+                }
+                value => entry(&mut body, key, value)?,
+            }
+        }
+
+        // A section that only holds subsections needs no header of its own.
+        if !body.is_empty() || subsections.is_empty() {
+            if !out.is_empty() {
+                out.push('\n');
+            }
+            out.push_str(&format!("[{}]\n", name));
+            out.push_str(&body);
+        }
+
+        for (key, entries) in subsections {
+            section(out, &format!("{} \"{}\"", name, key), entries, false)?;
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn entry(out: &mut String, key: &str, value: &Value) -> Result<(), Error> {
+        check_key(key)?;
+        match value {
+            Value::Array(values) => {
+                for value in values {
+                    out.push_str(&format!("{} = {}\n", key, scalar(key, value)?));
+                }
+            }
+            value => out.push_str(&format!("{} = {}\n", key, scalar(key, value)?)),
+        }
+
+        Ok(())
+    }
+
+    #[inline]
+    fn scalar(key: &str, value: &Value) -> Result<String, Error> {
+        match value {
+            Value::Nil => Ok(String::new()),
+            Value::Bool(b) => Ok(b.to_string()),
+            Value::Int(i) => Ok(i.to_string()),
+            Value::Float(f) => Ok(f.to_string()),
+            // Line breaks would end the entry, and the rest could be read as other entries or
+            // sections.
+            Value::Str(s) if s.chars().any(|c| c.is_control() && c != '\t') => {
+                Err(Error::Unsupported(key.to_string()))
+            }
+            Value::Str(s) => Ok(s.clone()),
+            Value::Array(_) | Value::Object(_) => Err(Error::Unsupported(key.to_string())),
+        }
+    }
+
+    /// Check that `key` can be written as a key or (sub)section name as is, without being read
+    /// back as something else, e.g. a comment or the end of a section header.
+    #[inline]
+    fn check_key(key: &str) -> Result<(), Error> {
+        let valid = !key.is_empty()
+            && key.trim() == key
+            && !key.starts_with([';', '#'])
+            && !key
+                .chars()
+                .any(|c| c.is_control() || matches!(c, '[' | ']' | '=' | '"'));
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidKey(key.to_string()))
+        }
+    }
+}
+
+pub mod env {
+    use std::path::PathBuf;
+
+    use super::{Mode, Object, Overwrite, Owner, Res, Resolve};
+    use crate::action::object::Value;
+
+    /// Action to write `values` as `KEY=VALUE` lines to a file at `dest`, e.g. a `.env` file.
+    /// Keys must be valid variable names. Values are quoted for the shell where needed, and must
+    /// not be arrays, objects or strings with line breaks.
+    #[derive(Debug, Clone)]
+    pub struct EnvAction {
+        pub dest: PathBuf,
+        pub values: Object,
+
+        pub header: Option<String>,
+        pub overwrite: Option<Overwrite>,
+        pub mode: Option<Mode>,
+        pub owner: Owner,
+    }
+
+    #[derive(Debug, thiserror::Error)]
+    pub enum Error {
+        #[error("value of {0:?} can't be written to an env file")]
+        Unsupported(String),
+        #[error("{0:?} is not a valid variable name")]
+        InvalidKey(String),
+    }
+
+    impl Resolve for EnvAction {
+        type Output = Result<Res, Error>;
+
+        #[inline]
+        fn resolve(&self) -> Self::Output {
+            let Self {
+                dest,
+                values,
+                header,
+                overwrite,
+                mode,
+                owner,
+            } = self;
+
+            // Render contents.
+            let contents = to_string(values)?;
+            Ok(super::write_resolve(
+                dest, contents, header, overwrite, mode, owner,
+            ))
+        }
+    }
+
+    /// Render `values` as `KEY=VALUE` lines.
+    #[inline]
+    pub fn to_string(values: &Object) -> Result<String, Error> {
+        let mut out = String::new();
+        for (key, value) in &values.0 {
+            if !is_name(key) {
+                return Err(Error::InvalidKey(key.clone()));
+            }
+
+            let value = match value {
+                Value::Nil => String::new(),
+                Value::Bool(b) => b.to_string(),
+                Value::Int(i) => i.to_string(),
+                Value::Float(f) => f.to_string(),
+                // Not every reader of env files handles values that span lines.
+                Value::Str(s) if s.chars().any(|c| c.is_control() && c != '\t') => {
+                    return Err(Error::Unsupported(key.clone()))
+                }
+                Value::Str(s) => quote(s),
+                Value::Array(_) | Value::Object(_) => return Err(Error::Unsupported(key.clone())),
+            };
+            out.push_str(&format!("{}={}\n", key, value));
+        }
+
+        Ok(out)
+    }
+
+    /// Return true if `key` is a valid variable name, i.e. `[A-Za-z_][A-Za-z0-9_]*`.
+    #[inline]
+    fn is_name(key: &str) -> bool {
+        let mut chars = key.chars();
+        chars
+            .next()
+            .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+            && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+    }
+
+    /// Double-quote `s` if it contains anything that the shell would interpret.
+    #[inline]
+    fn quote(s: &str) -> String {
+        let plain = |c: char| c.is_ascii_alphanumeric() || "_-./:,@%+=".contains(c);
+        if s.chars().all(plain) {
+            return s.to_string();
+        }
+
+        let mut quoted = String::from('"');
+        for c in s.chars() {
+            if matches!(c, '"' | '\\' | '$' | '`') {
+                quoted.push('\\');
+            }
+            quoted.push(c);
+        }
+        quoted.push('"');
+        quoted
+    }
+}
+
 #[inline]
-fn write_resolve(dest: &Path, mut contents: String, header: &Option<String>) -> Res {
+fn write_resolve(
+    dest: &Path,
+    mut contents: String,
+    header: &Option<String>,
+    overwrite: &Option<Overwrite>,
+    mode: &Option<Mode>,
+    owner: &Owner,
+) -> Res {
     if let Some(header) = header.as_ref() {
-        contents.insert_str(0, header);
         contents.insert(0, '\n');
+        contents.insert_str(0, header.trim_end_matches('\n'));
     }
 
     // Write contents.
     let wa = WriteAction {
         dest: dest.to_path_buf(),
         contents: contents.into_bytes(),
+        overwrite: *overwrite,
+        mode: *mode,
+        owner: *owner,
     };
 
     wa.resolve()
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use super::{env, ini, json::JsonAction, Op, Res};
+    use crate::action::{
+        object::{Object, Value},
+        ArrayMerge, Resolve,
+    };
+
+    fn object<const N: usize>(entries: [(&str, Value); N]) -> BTreeMap<String, Value> {
+        IntoIterator::into_iter(entries)
+            .map(|(key, value)| (key.to_string(), value))
+            .collect()
+    }
+
+    fn str(s: &str) -> Value {
+        Value::Str(s.to_string())
+    }
+
+    #[test]
+    fn test_json_merge_jsonc() {
+        let dir = tempfile::tempdir().unwrap();
+        let dest = dir.path().join("settings.json");
+        fs::write(
+            &dest,
+            "{\n    // Appearance\n    \"editor.fontSize\": 12,\n    \"telemetry\": false,\n}\n",
+        )
+        .unwrap();
+
+        let action = JsonAction {
+            dest,
+            values: Object(object([("editor.fontSize", Value::Int(14))])),
+            merge: Some(ArrayMerge::Replace),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+        let contents = match action.resolve().unwrap() {
+            Res::OverwriteContents(ops) => ops.into_iter().find_map(|op| match op {
+                Op::Write(op) => Some(op.contents),
+                _ => None,
+            }),
+            res => panic!("unexpected resolution: {:?}", res),
+        };
+        assert_eq!(
+            String::from_utf8(contents.unwrap()).unwrap(),
+            "{\n  \"editor.fontSize\": 14,\n  \"telemetry\": false\n}"
+        );
+    }
+
+    #[test]
+    fn test_ini() {
+        let values = Object(object([
+            ("root", Value::Bool(true)),
+            (
+                "remote",
+                Value::Object(object([(
+                    "origin",
+                    Value::Object(object([("url", str("git@example.com:a.git"))])),
+                )])),
+            ),
+            (
+                "Service",
+                Value::Object(object([
+                    ("ExecStart", str("/usr/bin/a")),
+                    ("Environment", Value::Array(vec![str("A=1"), str("B=2")])),
+                ])),
+            ),
+        ]));
+
+        assert_eq!(
+            ini::to_string(&values).unwrap(),
+            "root = true\n\
+             \n\
+             [Service]\n\
+             Environment = A=1\n\
+             Environment = B=2\n\
+             ExecStart = /usr/bin/a\n\
+             \n\
+             [remote \"origin\"]\n\
+             url = git@example.com:a.git\n"
+        );
+
+        let nested = Object(object([(
+            "a",
+            Value::Object(object([(
+                "b",
+                Value::Object(object([("c", Value::Object(object([])))])),
+            )])),
+        )]));
+        assert!(ini::to_string(&nested).is_err());
+
+        // Anything that would be read back as other entries or sections is rejected.
+        let single = |key: &str, value| Object(object([(key, value)]));
+        assert!(matches!(
+            ini::to_string(&single("a", str("b\n[evil]\nc = d"))),
+            Err(ini::Error::Unsupported(_))
+        ));
+        for key in ["[evil]", "a=b", "; a", " a", ""] {
+            assert!(matches!(
+                ini::to_string(&single(key, str("b"))),
+                Err(ini::Error::InvalidKey(_))
+            ));
+        }
+        let subsection = single(
+            "remote",
+            Value::Object(object([(
+                "a\"]",
+                Value::Object(object([("url", str("b"))])),
+            )])),
+        );
+        assert!(matches!(
+            ini::to_string(&subsection),
+            Err(ini::Error::InvalidKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_env() {
+        let values = Object(object([
+            ("EDITOR", str("nvim")),
+            ("GREETING", str("hello \"world\" $USER")),
+            ("JOBS", Value::Int(4)),
+        ]));
+
+        assert_eq!(
+            env::to_string(&values).unwrap(),
+            "EDITOR=nvim\nGREETING=\"hello \\\"world\\\" \\$USER\"\nJOBS=4\n"
+        );
+        assert!(env::to_string(&Object(object([("PATH", Value::Array(vec![]))]))).is_err());
+
+        let single = |key: &str, value| Object(object([(key, value)]));
+        assert!(matches!(
+            env::to_string(&single("A", str("b\nEVIL=1"))),
+            Err(env::Error::Unsupported(_))
+        ));
+        for key in ["1A", "A-B", "A B", "A=B", ""] {
+            assert!(matches!(
+                env::to_string(&single(key, str("b"))),
+                Err(env::Error::InvalidKey(_))
+            ));
+        }
+        assert!(env::to_string(&single("_a1", str("b"))).is_ok());
+    }
+}
@@ -1,12 +1,13 @@
 use std::fs;
-use std::path::PathBuf;
+use std::io;
+use std::path::{Path, PathBuf};
 
 use crate::fse;
-use crate::op::{CopyOp, LinkOp, MkdirOp, RmOp};
+use crate::op::{ChmodOp, ChownOp, CopyOp, HardlinkOp, LinkOp, MkdirOp, RmOp};
 
-use super::{mkdir, Resolve};
+use super::{mkdir, owner, Mode, Overwrite, Owner, Resolve};
 
-/// Action to symlink or copy from `src` to `dest`.
+/// Action to symlink, hard link, or copy from `src` to `dest`.
 #[derive(Debug, Clone)]
 pub struct LinkAction {
     /// Path of file to symlink/copy.
@@ -16,8 +17,23 @@ pub struct LinkAction {
 
     /// Perform a copy instead of a symlink.
     pub copy: bool,
+    /// Perform a hard link instead of a symlink. Ignored if `copy` is true.
+    pub hard: bool,
+    /// Symlink by a path relative to the directory of `dest` instead of an absolute path; if
+    /// `None`, absolute paths are used. Ignored for copies.
+    pub relative: Option<bool>,
+    /// When copying a directory over an existing one, remove the files in the destination that
+    /// aren't in `src`. Ignored for anything else.
+    pub prune: bool,
     /// If the `src` does not exist, emit no operations.
     pub optional: bool,
+    /// Policy for an existing destination; if `None`, the default policy is used.
+    pub overwrite: Option<Overwrite>,
+    /// Permission bits to set on the destination. Only copies can have them, since symlinks and
+    /// hard links share them with `src`, which would be changed too.
+    pub mode: Option<Mode>,
+    /// Owner and group to set on the destination. Only copies can have them, like `mode`.
+    pub owner: Owner,
 }
 
 /// Error that occurs when resolving [`LinkAction`].
@@ -26,6 +42,15 @@ pub enum Error {
     /// `src` was not found, and `optional` was false.
     #[error("src missing")]
     SrcMissing,
+    /// `src` is a directory, which can't be hard linked.
+    #[error("src is a directory")]
+    SrcDir,
+    /// `src` and `dest` are on different filesystems, so `src` can't be hard linked.
+    #[error("src and dest on different filesystems")]
+    CrossDevice,
+    /// `mode` or `owner` was set for a symlink or hard link, which would change `src`.
+    #[error("mode or owner set for a link")]
+    LinkAttrs,
 }
 
 // Resolution of [`LinkAction`].
@@ -35,6 +60,8 @@ pub enum Res {
     Normal(Vec<Op>),
     /// The destination file or directory will be overwritten.
     Overwrite(Vec<Op>),
+    /// The destination is up to date, but its permissions or ownership will be changed.
+    Permissions(Vec<Op>),
     /// The action is skipped.
     Skip(Skip),
 }
@@ -46,10 +73,30 @@ pub enum Op {
     Rm(RmOp),
     /// Link operation.
     Link(LinkOp),
+    /// Hard link operation.
+    Hardlink(HardlinkOp),
     /// Copy operation.
     Copy(CopyOp),
     /// Mkdir operation.
     Mkdir(MkdirOp),
+    /// Chmod operation.
+    Chmod(ChmodOp),
+    /// Chown operation.
+    Chown(ChownOp),
+}
+
+impl From<ChmodOp> for Op {
+    #[inline]
+    fn from(op: ChmodOp) -> Self {
+        Self::Chmod(op)
+    }
+}
+
+impl From<ChownOp> for Op {
+    #[inline]
+    fn from(op: ChownOp) -> Self {
+        Self::Chown(op)
+    }
 }
 
 /// Reason for skipping [`LinkAction`].
@@ -72,9 +119,18 @@ impl Resolve for LinkAction {
             src,
             dest,
             copy,
+            hard,
             optional,
+            mode,
+            owner,
+            ..
         } = self;
 
+        // Changing the attributes of a link would change those of `src` in the package.
+        if !*copy && (mode.is_some() || !owner.is_unset()) {
+            return Err(Error::LinkAttrs);
+        }
+
         // If src and dest are the same, skip.
         if src == dest {
             return Ok(Res::Skip(Skip::SameSrcDest));
@@ -92,11 +148,80 @@ impl Resolve for LinkAction {
             _ => {}
         };
 
-        if *copy {
-            self.resolve_copy()
+        let res = if *copy {
+            self.resolve_copy()?
+        } else if *hard {
+            self.resolve_hardlink()?
         } else {
-            self.resolve_link()
+            self.resolve_link()?
+        };
+
+        Ok(self.resolve_attrs(res))
+    }
+}
+
+impl LinkAction {
+    /// Return the operations that adopt the existing destination into the package instead of
+    /// overwriting it. The destination is copied in place of `src`, whose old contents are kept
+    /// in the file safe, and is then replaced by a link to (or copy of) it.
+    ///
+    /// Returns `None` if the destination doesn't exist, or if it is a directory that would have
+    /// to be hard linked.
+    #[inline]
+    pub fn adopt_ops(&self) -> Option<Vec<Op>> {
+        let Self {
+            src,
+            dest,
+            copy,
+            hard,
+            relative,
+            ..
+        } = self;
+
+        let dest_is_dir = fs::symlink_metadata(dest).ok()?.is_dir();
+        let hard = *hard && !*copy;
+        if hard && dest_is_dir {
+            return None;
+        }
+
+        let mut ops = Vec::new();
+        if let Ok(meta) = fs::symlink_metadata(src) {
+            ops.push(Op::Rm(RmOp {
+                path: src.clone(),
+                dir: meta.is_dir(),
+            }));
         }
+
+        ops.push(Op::Copy(CopyOp {
+            src: dest.clone(),
+            dest: src.clone(),
+            dir: dest_is_dir,
+        }));
+        ops.push(Op::Rm(RmOp {
+            path: dest.clone(),
+            dir: dest_is_dir,
+        }));
+        ops.push(if *copy {
+            Op::Copy(CopyOp {
+                src: src.clone(),
+                dest: dest.clone(),
+                dir: dest_is_dir,
+            })
+        } else if hard {
+            Op::Hardlink(HardlinkOp {
+                src: src.clone(),
+                dest: dest.clone(),
+            })
+        } else {
+            Op::Link(LinkOp {
+                src: src.clone(),
+                dest: dest.clone(),
+                relative: relative.unwrap_or(false),
+            })
+        });
+        ops.extend(owner::attr_ops(dest, None, &self.owner, self.mode));
+
+        Some(ops)
     }
 }
 
@@ -107,18 +232,16 @@ impl LinkAction {
         let Self {
             src,
             dest,
-            copy: _,
-            optional: _,
+            relative,
+            ..
         } = self;
 
         // Check the filetype and determine if overwrite is necessary.
         let (overwrite, is_dir) = match fs::symlink_metadata(dest) {
             // For symlinks, check the target.
-            // If it's the same as src, skip.
+            // If it's the same as src, whether absolute or relative, skip.
             Ok(meta) if meta.is_symlink() => {
-                // SAFETY: Already determined it exists and is a symlink.
-                let target = fs::read_link(dest).unwrap();
-                if target == *src {
+                if fse::links_to(dest, src) {
                     return Ok(Res::Skip(Skip::DestExists));
                 } else {
                     (true, false)
@@ -138,6 +261,7 @@ impl LinkAction {
         let link_op = Op::Link(LinkOp {
             src: src.clone(),
             dest: dest.clone(),
+            relative: relative.unwrap_or(false),
         });
         if overwrite {
             // Add op to remove existing file if exist.
@@ -159,34 +283,101 @@ impl LinkAction {
     }
 
     #[inline]
-    fn resolve_copy(&self) -> Result<Res, Error> {
+    fn resolve_hardlink(&self) -> Result<Res, Error> {
         let Self { src, dest, .. } = self;
 
+        let src_meta = fs::metadata(src).map_err(|_| Error::SrcMissing)?;
+        if src_meta.is_dir() {
+            return Err(Error::SrcDir);
+        }
+
+        // Hard links can't cross filesystems, so check the nearest existing parent of `dest`.
+        let dest_parent = dest.ancestors().skip(1).find(|path| path.exists());
+        if dest_parent.is_some_and(|parent| !fse::same_device(parent, src)) {
+            return Err(Error::CrossDevice);
+        }
+
+        // Check the filetype and determine if overwrite is necessary.
+        let (overwrite, is_dir) = match fs::symlink_metadata(dest) {
+            // For files, check if it's the same file as src. If so, skip.
+            Ok(meta) if meta.is_file() => {
+                if fse::same_file(dest, src) {
+                    return Ok(Res::Skip(Skip::DestExists));
+                }
+
+                (true, false)
+            }
+
+            // For existing directories and symlinks, warn about an overwrite.
+            // Remove the file, and then link.
+            Ok(meta) if meta.is_dir() => (true, true),
+            Ok(meta) if meta.is_symlink() => (true, false),
+
+            // File doesn't exist, or insufficient permissions.
+            // Treat as nonexistent.
+            Ok(_) | Err(_) => (false, false),
+        };
+
+        let link_op = Op::Hardlink(HardlinkOp {
+            src: src.clone(),
+            dest: dest.clone(),
+        });
+        if overwrite {
+            // Add op to remove existing file if exist.
+            let rm_op = Op::Rm(RmOp {
+                path: dest.clone(),
+                dir: is_dir,
+            });
+
+            Ok(Res::Overwrite(vec![rm_op, link_op]))
+        } else {
+            // Check for existence of parent directories and add op to make parent directories if
+            // they don't exist.
+            let mut ops: Vec<_> = mkdir::mkdir_parents_ops(dest).map(Op::Mkdir).collect();
+
+            ops.push(link_op);
+
+            Ok(Res::Normal(ops))
+        }
+    }
+
+    #[inline]
+    fn resolve_copy(&self) -> Result<Res, Error> {
+        let Self {
+            src, dest, prune, ..
+        } = self;
+
         let src_is_dir = match fs::symlink_metadata(src) {
             Ok(meta) if meta.is_dir() => true,
             Ok(_) | Err(_) => false,
         };
 
         let (overwrite_dest, dest_is_dir) = match fs::symlink_metadata(dest) {
+            // For directories copied over directories, update the destination file by file, so
+            // that unchanged files are left alone. If either can't be read, replace the whole
+            // destination instead.
+            Ok(meta) if meta.is_dir() && src_is_dir => match sync_ops(src, dest, *prune) {
+                Ok(ops) if ops.is_empty() => return Ok(Res::Skip(Skip::DestExists)),
+                Ok(ops) => return Ok(Res::Overwrite(ops)),
+                Err(_) => (true, true),
+            },
+
             // For files, check the contents. If they match, we should do nothing.
             // If not, proceed with overwrite.
             Ok(meta) if meta.is_file() => {
-                let content_same = fs::read_to_string(src)
-                    .map(|src_contents| match fs::read_to_string(dest) {
-                        Ok(dest_contents) => src_contents == dest_contents,
-                        Err(_) => false,
-                    })
-                    .unwrap_or(false);
-                if content_same {
+                if fse::same_contents(src, dest) {
                     return Ok(Res::Skip(Skip::DestExists));
                 }
 
                 (true, false)
             }
 
-            // For directories and symlinks, warn about an overwrite.
-            // Remove the directory, and then link.
+            // For other directories, warn about an overwrite.
+            // Remove the directory, and then copy.
             Ok(meta) if meta.is_dir() => (true, true),
+
+            // For symlinks, warn about an overwrite.
+            // Remove the symlink, and then copy.
             Ok(meta) if meta.is_symlink() => (true, false),
 
             // File doesn't exist, or insufficient permissions; treat as nonexistent.
@@ -216,4 +407,336 @@ impl LinkAction {
             Ok(Res::Normal(ops))
         }
     }
+
+    /// Add the operations that set the ownership and permissions of the destination to `res`,
+    /// if necessary.
+    #[inline]
+    fn resolve_attrs(&self, res: Res) -> Res {
+        let Self {
+            dest, mode, owner, ..
+        } = self;
+
+        // Only copies have attributes to set (see `resolve`). New copies always need them, since
+        // they may be created with others.
+        match res {
+            Res::Normal(mut ops) => {
+                ops.extend(owner::attr_ops(dest, None, owner, *mode));
+                Res::Normal(ops)
+            }
+            Res::Overwrite(mut ops) => {
+                ops.extend(owner::attr_ops(dest, None, owner, *mode));
+                Res::Overwrite(ops)
+            }
+            Res::Skip(Skip::DestExists) => {
+                let ops: Vec<_> = owner::attr_ops(dest, Some(dest), owner, *mode);
+                if ops.is_empty() {
+                    Res::Skip(Skip::DestExists)
+                } else {
+                    Res::Permissions(ops)
+                }
+            }
+            res => res,
+        }
+    }
+}
+
+/// Return the operations that update the existing directory `dest` to be a copy of the directory
+/// `src`, file by file. Files that are already the same are left alone, and files in `dest` that
+/// aren't in `src` are removed only if `prune` is true.
+#[inline]
+fn sync_ops(src: &Path, dest: &Path, prune: bool) -> io::Result<Vec<Op>> {
+    let names = fse::dir_entries(src)?;
+
+    let mut ops = Vec::new();
+    for name in &names {
+        let (fsrc, fdest) = (src.join(name), dest.join(name));
+        let src_is_dir = fs::metadata(&fsrc)?.is_dir();
+        let copy_op = Op::Copy(CopyOp {
+            src: fsrc.clone(),
+            dest: fdest.clone(),
+            dir: src_is_dir,
+        });
+
+        match fs::symlink_metadata(&fdest) {
+            // Recurse into directories that exist on both sides.
+            Ok(meta) if meta.is_dir() && src_is_dir => ops.extend(sync_ops(&fsrc, &fdest, prune)?),
+            // Files with the same contents are up to date.
+            Ok(meta) if meta.is_file() && !src_is_dir && fse::same_contents(&fsrc, &fdest) => {}
+            // Anything else is replaced.
+            Ok(meta) => {
+                ops.push(Op::Rm(RmOp {
+                    path: fdest,
+                    dir: meta.is_dir(),
+                }));
+                ops.push(copy_op);
+            }
+            Err(_) => ops.push(copy_op),
+        }
+    }
+
+    if prune {
+        for name in fse::dir_entries(dest)? {
+            if names.binary_search(&name).is_err() {
+                let path = dest.join(name);
+                let dir = fs::symlink_metadata(&path)?.is_dir();
+                ops.push(Op::Rm(RmOp { path, dir }));
+            }
+        }
+    }
+
+    Ok(ops)
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::symlink;
+
+    use super::{Error, LinkAction, Op, Res, Skip};
+    use crate::action::{Owner, Resolve};
+    use crate::op::{CopyOp, LinkOp, RmOp};
+
+    #[test]
+    fn test_adopt_ops() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        let dest = dir.join("dest");
+        fs::write(&src, "package")?;
+
+        let action = LinkAction {
+            src: src.clone(),
+            dest: dest.clone(),
+            copy: false,
+            hard: false,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+        assert!(action.adopt_ops().is_none());
+
+        fs::write(&dest, "machine")?;
+        let ops: Vec<_> = action.adopt_ops().unwrap();
+        assert!(matches!(&ops[..], [
+            Op::Rm(RmOp { path: rm_src, dir: false }),
+            Op::Copy(CopyOp { src: copy_src, dest: copy_dest, dir: false }),
+            Op::Rm(RmOp { path: rm_dest, dir: false }),
+            Op::Link(LinkOp { src: link_src, dest: link_dest, relative: false }),
+        ] if *rm_src == src
+            && *copy_src == dest
+            && *copy_dest == src
+            && *rm_dest == dest
+            && *link_src == src
+            && *link_dest == dest));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_relative() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let src = dir.join("pkg/src");
+        fs::create_dir(dir.join("pkg"))?;
+        fs::create_dir(dir.join("home"))?;
+        fs::write(&src, "package")?;
+
+        let action = |dest: &str, relative| LinkAction {
+            src: src.clone(),
+            dest: dir.join(dest),
+            copy: false,
+            hard: false,
+            relative,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        let ops = match action("home/new", Some(true)).resolve().unwrap() {
+            Res::Normal(ops) => ops,
+            res => panic!("unexpected resolution {:?}", res),
+        };
+        assert!(matches!(
+            &ops[..],
+            [Op::Link(LinkOp { relative: true, .. })]
+        ));
+
+        // Both relative and absolute links to `src` are up to date.
+        symlink("../pkg/src", dir.join("home/relative"))?;
+        symlink(&src, dir.join("home/absolute"))?;
+        for dest in ["home/relative", "home/absolute"] {
+            for relative in [Some(true), None] {
+                assert!(matches!(
+                    action(dest, relative).resolve().unwrap(),
+                    Res::Skip(Skip::DestExists)
+                ));
+            }
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_hardlink() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        fs::write(&src, "package")?;
+        fs::create_dir(dir.join("srcdir"))?;
+
+        let action = |src, dest: &str| LinkAction {
+            src,
+            dest: dir.join(dest),
+            copy: false,
+            hard: true,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        let ops = match action(src.clone(), "new").resolve().unwrap() {
+            Res::Normal(ops) => ops,
+            res => panic!("unexpected resolution {:?}", res),
+        };
+        assert!(matches!(&ops[..], [Op::Hardlink(_)]));
+
+        fs::hard_link(&src, dir.join("linked"))?;
+        assert!(matches!(
+            action(src.clone(), "linked").resolve().unwrap(),
+            Res::Skip(Skip::DestExists)
+        ));
+
+        fs::write(dir.join("other"), "package")?;
+        assert!(matches!(
+            action(src, "other").resolve().unwrap(),
+            Res::Overwrite(_)
+        ));
+
+        assert!(matches!(
+            action(dir.join("srcdir"), "dir").resolve(),
+            Err(Error::SrcDir)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_copy() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        // Binary contents that aren't valid UTF-8 must still be detected as up to date.
+        let binary = [0xff, 0xfe, 0x00, 0x80];
+        for name in ["src", "dest"] {
+            fs::create_dir(dir.join(name))?;
+            fs::write(dir.join(name).join("font.bin"), binary)?;
+        }
+
+        let action = |src: &str, dest: &str, prune| LinkAction {
+            src: dir.join(src),
+            dest: dir.join(dest),
+            copy: true,
+            hard: false,
+            relative: None,
+            prune,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        assert!(matches!(
+            action("src/font.bin", "dest/font.bin", false)
+                .resolve()
+                .unwrap(),
+            Res::Skip(Skip::DestExists)
+        ));
+        assert!(matches!(
+            action("src", "dest", false).resolve().unwrap(),
+            Res::Skip(Skip::DestExists)
+        ));
+
+        fs::write(dir.join("dest/font.bin"), &binary[1..])?;
+        assert!(matches!(
+            action("src/font.bin", "dest/font.bin", false)
+                .resolve()
+                .unwrap(),
+            Res::Overwrite(_)
+        ));
+
+        // Directories are updated file by file.
+        fs::write(dir.join("src/new.txt"), "new")?;
+        fs::write(dir.join("dest/extra.txt"), "extra")?;
+        let ops = match action("src", "dest", false).resolve().unwrap() {
+            Res::Overwrite(ops) => ops,
+            res => panic!("unexpected resolution {:?}", res),
+        };
+        assert!(matches!(
+            &ops[..],
+            [Op::Rm(rm), Op::Copy(font), Op::Copy(new)]
+                if rm.path == dir.join("dest/font.bin")
+                    && font.dest == dir.join("dest/font.bin")
+                    && new.dest == dir.join("dest/new.txt")
+        ));
+
+        // Extra files are only removed if pruning.
+        let ops = match action("src", "dest", true).resolve().unwrap() {
+            Res::Overwrite(ops) => ops,
+            res => panic!("unexpected resolution {:?}", res),
+        };
+        assert!(matches!(
+            ops.last(),
+            Some(Op::Rm(rm)) if rm.path == dir.join("dest/extra.txt")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_link_attrs() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+        fs::write(dir.join("src"), "package")?;
+
+        let action = |copy, hard, mode: Option<&str>, uid| LinkAction {
+            src: dir.join("src"),
+            dest: dir.join("dest"),
+            copy,
+            hard,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: mode.map(|mode| mode.parse().unwrap()),
+            owner: Owner { uid, gid: None },
+        };
+
+        // Setting the attributes of a link would change those of the package's source.
+        for (copy, hard, mode, uid) in [
+            (false, false, Some("0600"), None),
+            (false, false, None, Some(0)),
+            (false, true, Some("0600"), None),
+        ] {
+            assert!(matches!(
+                action(copy, hard, mode, uid).resolve(),
+                Err(Error::LinkAttrs)
+            ));
+        }
+        assert!(action(false, false, None, None).resolve().is_ok());
+        assert!(action(true, false, Some("0600"), Some(0)).resolve().is_ok());
+
+        Ok(())
+    }
 }
@@ -0,0 +1,269 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+/// Strategy for merging an array into an existing one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ArrayMerge {
+    /// Replace the existing array.
+    Replace,
+    /// Append the elements to the existing array.
+    Append,
+    /// Append the elements that the existing array doesn't already contain.
+    Union,
+}
+
+impl Default for ArrayMerge {
+    #[inline]
+    fn default() -> Self {
+        Self::Replace
+    }
+}
+
+/// Error that occurs when parsing an [`ArrayMerge`] strategy.
+#[derive(Debug, thiserror::Error)]
+#[error(r#"invalid array merge strategy {0:?} (must be "replace", "append", or "union")"#)]
+pub struct ParseError(String);
+
+impl FromStr for ArrayMerge {
+    type Err = ParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "replace" => Ok(Self::Replace),
+            "append" => Ok(Self::Append),
+            "union" => Ok(Self::Union),
+            _ => Err(ParseError(s.to_string())),
+        }
+    }
+}
+
+impl ArrayMerge {
+    /// Merge `other` into the array `base`.
+    #[inline]
+    fn apply<T: PartialEq>(&self, base: &mut Vec<T>, other: Vec<T>) {
+        match self {
+            Self::Replace => *base = other,
+            Self::Append => base.extend(other),
+            Self::Union => {
+                for value in other {
+                    if !base.contains(&value) {
+                        base.push(value);
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Trait for deep-merging a document value into another of the same format.
+///
+/// Maps are merged key by key, so keys that only `self` has are kept. Arrays are merged according
+/// to the strategy, and any other value is replaced.
+pub trait Merge {
+    fn merge(&mut self, other: Self, arrays: ArrayMerge);
+}
+
+macro_rules! merge_impl {
+    ($ty:ty, $map:path, $array:path) => {
+        impl Merge for $ty {
+            #[inline]
+            fn merge(&mut self, other: Self, arrays: ArrayMerge) {
+                match (&mut *self, other) {
+                    ($map(base), $map(other)) => {
+                        for (key, value) in other {
+                            match base.get_mut(&key) {
+                                Some(existing) => existing.merge(value, arrays),
+                                None => {
+                                    base.insert(key, value);
+                                }
+                            }
+                        }
+                    }
+                    ($array(base), $array(other)) => arrays.apply(base, other),
+                    (base, other) => *base = other,
+                }
+            }
+        }
+    };
+}
+
+merge_impl!(
+    serde_json::Value,
+    serde_json::Value::Object,
+    serde_json::Value::Array
+);
+merge_impl!(
+    serde_yaml::Value,
+    serde_yaml::Value::Mapping,
+    serde_yaml::Value::Sequence
+);
+merge_impl!(toml::Value, toml::Value::Table, toml::Value::Array);
+
+/// Return the contents of the existing file at `dest` to merge into, or `None` if there is no
+/// such file or it is blank. Anything that isn't a regular file is treated as nonexistent, and
+/// will be replaced.
+#[inline]
+pub fn read_existing(dest: &Path) -> io::Result<Option<String>> {
+    match fs::metadata(dest) {
+        Ok(meta) if meta.is_file() => {
+            let contents = fs::read_to_string(dest)?;
+            Ok(Some(contents).filter(|contents| !contents.trim().is_empty()))
+        }
+        Ok(_) => Ok(None),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+/// Return the JSONC document `s`, i.e. JSON with comments and trailing commas like VS Code's
+/// settings.json, as plain JSON. Comments and trailing commas are blanked out, so that the lines
+/// and columns of parse errors still point into `s`.
+#[inline]
+pub fn strip_jsonc(s: &str) -> String {
+    let mut out = String::with_capacity(s.len());
+    // Index in `out` of a comma that may be trailing.
+    let mut comma = None;
+
+    let mut chars = s.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                comma = None;
+                out.push(c);
+                while let Some(c) = chars.next() {
+                    out.push(c);
+                    match c {
+                        '\\' => out.extend(chars.next()),
+                        '"' => break,
+                        _ => {}
+                    }
+                }
+            }
+            '/' if chars.peek() == Some(&'/') => {
+                out.push(' ');
+                while chars.next_if(|&c| c != '\n').is_some() {
+                    out.push(' ');
+                }
+            }
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                out.push_str("  ");
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    out.push(if c == '\n' { c } else { ' ' });
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+            }
+            ',' => {
+                comma = Some(out.len());
+                out.push(c);
+            }
+            '}' | ']' => {
+                if let Some(i) = comma.take() {
+                    out.replace_range(i..i + 1, " ");
+                }
+                out.push(c);
+            }
+            c if c.is_whitespace() => out.push(c),
+            c => {
+                comma = None;
+                out.push(c);
+            }
+        }
+    }
+
+    out
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::json;
+
+    use super::{strip_jsonc, ArrayMerge, Merge};
+
+    #[test]
+    fn test_merge() {
+        let existing = json!({
+            "editor.fontSize": 12,
+            "editor.rulers": [80],
+            "files": {"autoSave": "off", "eol": "\n"},
+            "telemetry": false,
+        });
+        let values = json!({
+            "editor.fontSize": 14,
+            "editor.rulers": [80, 100],
+            "files": {"autoSave": "onFocusChange"},
+        });
+
+        let merged = |arrays| {
+            let mut merged = existing.clone();
+            merged.merge(values.clone(), arrays);
+            merged
+        };
+
+        assert_eq!(
+            merged(ArrayMerge::Replace),
+            json!({
+                "editor.fontSize": 14,
+                "editor.rulers": [80, 100],
+                "files": {"autoSave": "onFocusChange", "eol": "\n"},
+                "telemetry": false,
+            })
+        );
+        assert_eq!(
+            merged(ArrayMerge::Append)["editor.rulers"],
+            json!([80, 80, 100])
+        );
+        assert_eq!(merged(ArrayMerge::Union)["editor.rulers"], json!([80, 100]));
+    }
+
+    #[test]
+    fn test_merge_toml() {
+        let mut existing: toml::Value =
+            toml::from_str("[user]\nname = \"a\"\nsince = 1979-05-27T07:32:00Z\n").unwrap();
+        let values: toml::Value = toml::from_str("[user]\nname = \"b\"\n").unwrap();
+        existing.merge(values, ArrayMerge::Replace);
+
+        assert_eq!(
+            toml::to_string(&existing).unwrap(),
+            "[user]\nname = \"b\"\nsince = 1979-05-27T07:32:00Z\n"
+        );
+    }
+
+    #[test]
+    fn test_strip_jsonc() {
+        let settings = r#"// Place your settings in this file to overwrite the default settings
+{
+    /* Appearance */
+    "editor.fontSize": 12, // points
+    "editor.rulers": [80, 100,],
+    "files.exclude": {
+        "**/.git": true, /* hidden */
+        "http://a/*b": false,
+    },
+}
+"#;
+        let value: serde_json::Value = serde_json::from_str(&strip_jsonc(settings)).unwrap();
+        assert_eq!(
+            value,
+            json!({
+                "editor.fontSize": 12,
+                "editor.rulers": [80, 100],
+                "files.exclude": {"**/.git": true, "http://a/*b": false},
+            })
+        );
+
+        // Errors still point into the original document.
+        let err = serde_json::from_str::<serde_json::Value>(&strip_jsonc("{\n  /* a */ b\n}"))
+            .unwrap_err();
+        assert_eq!((err.line(), err.column()), (2, 11));
+    }
+}
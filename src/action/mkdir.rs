@@ -2,9 +2,9 @@ use std::fs;
 use std::path::{Path, PathBuf};
 
 use crate::fse;
-use crate::op::{MkdirOp, RmOp};
+use crate::op::{ChmodOp, ChownOp, MkdirOp, RmOp};
 
-use super::Resolve;
+use super::{owner, Mode, Owner, Resolve};
 
 #[derive(Debug, Clone)]
 pub struct MkdirAction {
@@ -12,6 +12,10 @@ pub struct MkdirAction {
     pub path: PathBuf,
     /// Missing parrents should also be created.
     pub parents: bool,
+    /// Permission bits to set on the directory.
+    pub mode: Option<Mode>,
+    /// Owner and group to set on the directory.
+    pub owner: Owner,
 }
 
 #[derive(Debug, Clone)]
@@ -19,6 +23,8 @@ pub enum Res {
     Normal(Vec<Op>),
     /// The destination file or directory will be overwritten.
     Overwrite(Vec<Op>),
+    /// The directory exists, but its permissions or ownership will be changed.
+    Permissions(Vec<Op>),
     /// The action is skipped.
     Skip(Skip),
 }
@@ -29,6 +35,24 @@ pub enum Op {
     Rm(RmOp),
     /// Mkdir operation.
     Mkdir(MkdirOp),
+    /// Chmod operation.
+    Chmod(ChmodOp),
+    /// Chown operation.
+    Chown(ChownOp),
+}
+
+impl From<ChmodOp> for Op {
+    #[inline]
+    fn from(op: ChmodOp) -> Self {
+        Self::Chmod(op)
+    }
+}
+
+impl From<ChownOp> for Op {
+    #[inline]
+    fn from(op: ChownOp) -> Self {
+        Self::Chown(op)
+    }
 }
 
 /// Reason for skipping [`MkdirAction`].
@@ -43,12 +67,23 @@ impl Resolve for MkdirAction {
 
     #[inline]
     fn resolve(&self) -> Self::Output {
-        let Self { path, parents } = self;
+        let Self {
+            path,
+            parents,
+            mode,
+            owner,
+        } = self;
 
         let (overwrite, is_dir) = match fs::symlink_metadata(path) {
-            // For directories, we should do nothing, as it already exists.
+            // For directories, we should do nothing, as it already exists, unless its ownership
+            // or permissions differ.
             Ok(meta) if meta.is_dir() => {
-                return Res::Skip(Skip::DestExists);
+                let ops: Vec<_> = owner::attr_ops(path, Some(path), owner, *mode);
+                return if ops.is_empty() {
+                    Res::Skip(Skip::DestExists)
+                } else {
+                    Res::Permissions(ops)
+                };
             }
 
             // For files and symlinks, warn about an overwrite, remove the file, and then link.
@@ -71,6 +106,7 @@ impl Resolve for MkdirAction {
             };
 
             ops.push(Op::Mkdir(MkdirOp { path: path.clone() }));
+            ops.extend(owner::attr_ops(path, None, owner, *mode));
 
             Res::Normal(ops)
         }
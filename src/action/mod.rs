@@ -1,5 +1,10 @@
+pub mod merge;
+pub mod mode;
 pub mod object;
+pub mod overwrite;
+pub mod owner;
 
+pub mod block;
 pub mod command;
 pub mod function;
 pub mod generated;
@@ -9,16 +14,32 @@ pub mod template;
 pub mod tree;
 pub mod write;
 
+pub mod diff;
+pub mod status;
+pub mod uninstall;
+
 // Re-export action types.
+pub use self::block::BlockAction;
 pub use self::command::CommandAction;
 pub use self::function::FunctionAction;
-pub use self::generated::{JsonAction, TomlAction, YamlAction};
+pub use self::generated::{EnvAction, IniAction, JsonAction, TomlAction, YamlAction};
 pub use self::link::LinkAction;
 pub use self::mkdir::MkdirAction;
 pub use self::template::{HandlebarsAction, LiquidAction};
 pub use self::tree::TreeAction;
 pub use self::write::WriteAction;
 
+pub use self::merge::ArrayMerge;
+pub use self::mode::Mode;
+pub use self::overwrite::Overwrite;
+pub use self::owner::Owner;
+
+pub use self::diff::Diff;
+pub use self::status::Check;
+pub use self::uninstall::Uninstall;
+
+use std::path::PathBuf;
+
 pub trait Resolve {
     type Output;
 
@@ -35,11 +56,52 @@ pub enum Action<'lua> {
     Yaml(YamlAction),
     Toml(TomlAction),
     Json(JsonAction),
+    Ini(IniAction),
+    Env(EnvAction),
+    Block(BlockAction),
     Mkdir(MkdirAction),
     Command(CommandAction),
     Function(FunctionAction<'lua>),
 }
 
+impl<'lua> Action<'lua> {
+    /// Return the destination paths that the action deploys to.
+    #[inline]
+    pub fn dests(&self) -> Vec<PathBuf> {
+        match self {
+            Action::Link(action) => vec![action.dest.clone()],
+            Action::Tree(action) => match action.link_actions() {
+                Ok(Ok(actions)) => actions.into_iter().map(|action| action.dest).collect(),
+                Ok(Err(_)) | Err(_) => vec![],
+            },
+            Action::Write(action) => vec![action.dest.clone()],
+            Action::Handlebars(action) => vec![action.dest.clone()],
+            Action::Liquid(action) => vec![action.dest.clone()],
+            Action::Yaml(action) => vec![action.dest.clone()],
+            Action::Toml(action) => vec![action.dest.clone()],
+            Action::Json(action) => vec![action.dest.clone()],
+            Action::Ini(action) => vec![action.dest.clone()],
+            Action::Env(action) => vec![action.dest.clone()],
+            Action::Block(action) => vec![action.dest.clone()],
+            Action::Mkdir(action) => vec![action.path.clone()],
+            Action::Command(_) | Action::Function(_) => vec![],
+        }
+    }
+
+    /// Return true if the action merges into its destinations instead of owning them, so they may
+    /// be shared with other actions.
+    #[inline]
+    pub fn merges(&self) -> bool {
+        match self {
+            Action::Yaml(action) => action.merge.is_some(),
+            Action::Toml(action) => action.merge.is_some(),
+            Action::Json(action) => action.merge.is_some(),
+            Action::Block(_) => true,
+            _ => false,
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ResolutionError {
     #[error("link action resolution error")]
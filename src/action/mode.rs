@@ -0,0 +1,114 @@
+use std::fmt;
+use std::path::Path;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::op::ChmodOp;
+
+/// Permission bits of a deployed file, e.g. `0o600`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Mode(u32);
+
+/// Error that occurs when parsing a [`Mode`].
+#[derive(Debug, thiserror::Error)]
+#[error("invalid mode {0:?} (must be an octal number no greater than 7777, e.g. \"0600\")")]
+pub struct ParseError(String);
+
+impl Mode {
+    /// Return the permission bits.
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.0
+    }
+
+    /// Return the permission bits of the file at `path`, following symlinks.
+    #[cfg(unix)]
+    #[inline]
+    pub fn of<P: AsRef<Path>>(path: P) -> Option<Self> {
+        use std::fs;
+        use std::os::unix::fs::PermissionsExt;
+
+        let meta = fs::metadata(path).ok()?;
+        Some(Self(meta.permissions().mode() & 0o7777))
+    }
+
+    /// Return the permission bits of the file at `path`, following symlinks. There are none on
+    /// this platform.
+    #[cfg(not(unix))]
+    #[inline]
+    pub fn of<P: AsRef<Path>>(_path: P) -> Option<Self> {
+        None
+    }
+
+    /// Return true if the file at `path` has these permission bits, following symlinks.
+    #[inline]
+    pub fn matches<P: AsRef<Path>>(&self, path: P) -> bool {
+        Self::of(path) == Some(*self)
+    }
+
+    /// Return the operation that sets the permission bits of `path` to these.
+    #[inline]
+    pub fn op<P: AsRef<Path>>(&self, path: P) -> ChmodOp {
+        ChmodOp {
+            path: path.as_ref().to_path_buf(),
+            mode: self.0,
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = ParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let digits = s.strip_prefix("0o").unwrap_or(s);
+        match u32::from_str_radix(digits, 8) {
+            Ok(bits) if !digits.is_empty() && bits <= 0o7777 => Ok(Self(bits)),
+            _ => Err(ParseError(s.to_string())),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:04o}", self.0)
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::Mode;
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("0600".parse::<Mode>().unwrap().bits(), 0o600);
+        assert_eq!("755".parse::<Mode>().unwrap().bits(), 0o755);
+        assert_eq!("0o644".parse::<Mode>().unwrap().to_string(), "0644");
+        assert!("0800".parse::<Mode>().is_err());
+        assert!("17777".parse::<Mode>().is_err());
+        assert!("".parse::<Mode>().is_err());
+    }
+
+    #[test]
+    fn test_matches() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let path = tempdir.path().join("file");
+        fs::write(&path, "")?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o640))?;
+
+        assert!("0640".parse::<Mode>().unwrap().matches(&path));
+        assert!(!"0600".parse::<Mode>().unwrap().matches(&path));
+        assert!(!"0640"
+            .parse::<Mode>()
+            .unwrap()
+            .matches(tempdir.path().join("none")));
+
+        Ok(())
+    }
+}
@@ -1,10 +1,14 @@
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 
 use serde::{Deserialize, Serialize};
 
+/// Map of values, e.g. template variables or the contents of a generated file.
+///
+/// Lua tables don't keep the order in which their keys were declared, so keys are kept sorted
+/// instead. This makes generated files byte-identical between runs.
 #[derive(Debug, Clone, Deserialize, Serialize)]
 #[serde(transparent)]
-pub struct Object(pub HashMap<String, Value>);
+pub struct Object(pub BTreeMap<String, Value>);
 
 // FIXME Custom serialization/deserialization to handle Nil?
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -15,13 +19,14 @@ pub enum Value {
     Int(i64),
     Float(f64),
     Str(String),
-    Object(HashMap<String, Value>),
+    Array(Vec<Value>),
+    Object(BTreeMap<String, Value>),
 }
 
 impl Object {
     #[inline]
     pub fn new() -> Self {
-        Self(HashMap::new())
+        Self(BTreeMap::new())
     }
 }
 
@@ -30,3 +35,51 @@ impl Default for Object {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+
+    use super::{Object, Value};
+
+    #[test]
+    fn test_serialize_array() {
+        let plugins = Value::Array(vec![
+            Value::Str("telescope".to_string()),
+            Value::Int(2),
+            Value::Array(vec![Value::Bool(true)]),
+            Value::Object(BTreeMap::from([(
+                "name".to_string(),
+                Value::Str("lsp".to_string()),
+            )])),
+        ]);
+        let object = Object(BTreeMap::from([("plugins".to_string(), plugins)]));
+
+        assert_eq!(
+            serde_json::to_string(&object).unwrap(),
+            r#"{"plugins":["telescope",2,[true],{"name":"lsp"}]}"#
+        );
+        assert_eq!(
+            serde_yaml::to_string(&object).unwrap(),
+            "---\nplugins:\n  - telescope\n  - 2\n  - - true\n  - name: lsp\n"
+        );
+        assert_eq!(
+            toml::to_string_pretty(&object).unwrap(),
+            "plugins = [\n    \"telescope\",\n    2,\n    [true],\n    { name = \"lsp\" },\n]\n"
+        );
+    }
+
+    #[test]
+    fn test_serialize_sorted() {
+        let object = Object(BTreeMap::from([
+            ("zsh".to_string(), Value::Int(1)),
+            ("bash".to_string(), Value::Int(2)),
+            ("fish".to_string(), Value::Int(3)),
+        ]));
+
+        assert_eq!(
+            serde_json::to_string(&object).unwrap(),
+            r#"{"bash":2,"fish":3,"zsh":1}"#
+        );
+    }
+}
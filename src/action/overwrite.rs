@@ -0,0 +1,122 @@
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::fse;
+use crate::op::CopyOp;
+
+/// Policy for handling an existing destination that an action would overwrite.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Overwrite {
+    /// Replace the destination. The replaced file is kept in the file safe, so it can be restored
+    /// by undoing.
+    Replace,
+    /// Leave the destination untouched.
+    Skip,
+    /// Copy the destination to a backup path next to it before replacing it.
+    Backup,
+    /// Move the destination into the package in place of the source, and then replace it. Only
+    /// files and trees can adopt their destinations.
+    Adopt,
+    /// Ask interactively what to do.
+    Ask,
+    /// Refuse to overwrite the destination.
+    Error,
+}
+
+impl Default for Overwrite {
+    #[inline]
+    fn default() -> Self {
+        Self::Replace
+    }
+}
+
+/// Error that occurs when parsing an [`Overwrite`] policy.
+#[derive(Debug, thiserror::Error)]
+#[error(
+    r#"invalid overwrite policy {0:?} (must be "replace", "skip", "backup", "adopt", "ask", or "error")"#
+)]
+pub struct ParseError(String);
+
+impl FromStr for Overwrite {
+    type Err = ParseError;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "replace" => Ok(Self::Replace),
+            "skip" => Ok(Self::Skip),
+            "backup" => Ok(Self::Backup),
+            "adopt" => Ok(Self::Adopt),
+            "ask" => Ok(Self::Ask),
+            "error" => Ok(Self::Error),
+            _ => Err(ParseError(s.to_string())),
+        }
+    }
+}
+
+/// Return the operation that copies `dest` to an unused backup path next to it.
+#[inline]
+pub fn backup_op(dest: &Path) -> CopyOp {
+    let dir = matches!(fs::symlink_metadata(dest), Ok(meta) if meta.is_dir());
+
+    CopyOp {
+        src: dest.to_path_buf(),
+        dest: backup_path(dest),
+        dir,
+    }
+}
+
+/// Return the first of `<dest>.bak`, `<dest>.bak.1`, `<dest>.bak.2`, ... that doesn't exist.
+#[inline]
+fn backup_path(dest: &Path) -> PathBuf {
+    let with_suffix = |suffix: String| {
+        let mut name: OsString = dest.file_name().unwrap_or_default().to_os_string();
+        name.push(suffix);
+        dest.with_file_name(name)
+    };
+
+    (0..)
+        .map(|i| match i {
+            0 => with_suffix(".bak".to_string()),
+            i => with_suffix(format!(".bak.{}", i)),
+        })
+        .find(|path| !fse::symlink_exists(path))
+        .unwrap()
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io;
+
+    use super::{backup_op, Overwrite};
+
+    #[test]
+    fn test_from_str() {
+        assert_eq!("ask".parse::<Overwrite>().unwrap(), Overwrite::Ask);
+        assert!("nope".parse::<Overwrite>().is_err());
+    }
+
+    #[test]
+    fn test_backup_op() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let dest = dir.join("file");
+        fs::write(&dest, "")?;
+
+        let op = backup_op(&dest);
+        assert_eq!(op.src, dest);
+        assert_eq!(op.dest, dir.join("file.bak"));
+        assert!(!op.dir);
+
+        fs::write(dir.join("file.bak"), "")?;
+        assert_eq!(backup_op(&dest).dest, dir.join("file.bak.1"));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,238 @@
+use std::fmt;
+use std::path::Path;
+
+use crate::op::{ChmodOp, ChownOp};
+
+use super::Mode;
+
+/// Owner and group of a deployed file, as numeric ids. Ids that are `None` are left unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Owner {
+    /// Id of the owning user.
+    pub uid: Option<u32>,
+    /// Id of the owning group.
+    pub gid: Option<u32>,
+}
+
+/// Error that occurs when looking up a user or group by name.
+#[derive(Debug, thiserror::Error)]
+#[error("unknown {kind} {name:?}")]
+pub struct LookupError {
+    kind: &'static str,
+    name: String,
+}
+
+impl Owner {
+    /// Return the owner and group of the file at `path`, following symlinks.
+    #[cfg(unix)]
+    #[inline]
+    pub fn of<P: AsRef<Path>>(path: P) -> Option<Self> {
+        use std::fs;
+        use std::os::unix::fs::MetadataExt;
+
+        let meta = fs::metadata(path).ok()?;
+        Some(Self {
+            uid: Some(meta.uid()),
+            gid: Some(meta.gid()),
+        })
+    }
+
+    /// Return the owner and group of the file at `path`, following symlinks. There are none on
+    /// this platform.
+    #[cfg(not(unix))]
+    #[inline]
+    pub fn of<P: AsRef<Path>>(_path: P) -> Option<Self> {
+        None
+    }
+
+    /// Return true if neither the owner nor the group is set.
+    #[inline]
+    pub fn is_unset(&self) -> bool {
+        self.uid.is_none() && self.gid.is_none()
+    }
+
+    /// Return true if the file at `path` has the set owner and group, following symlinks.
+    #[inline]
+    pub fn matches<P: AsRef<Path>>(&self, path: P) -> bool {
+        if self.is_unset() {
+            return true;
+        }
+
+        match Self::of(path) {
+            Some(Self { uid, gid }) => {
+                self.uid.is_none_or(|id| uid == Some(id))
+                    && self.gid.is_none_or(|id| gid == Some(id))
+            }
+            None => false,
+        }
+    }
+
+    /// Return the operation that sets the owner and group of `path` to these.
+    #[inline]
+    pub fn op<P: AsRef<Path>>(&self, path: P) -> ChownOp {
+        ChownOp {
+            path: path.as_ref().to_path_buf(),
+            uid: self.uid,
+            gid: self.gid,
+        }
+    }
+}
+
+impl fmt::Display for Owner {
+    /// Format as `user:group`, using names where they can be found and ids otherwise. Ids that
+    /// are `None` are omitted.
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if let Some(uid) = self.uid {
+            match lookup::user_name(uid) {
+                Some(name) => write!(f, "{}", name)?,
+                None => write!(f, "{}", uid)?,
+            }
+        }
+        if let Some(gid) = self.gid {
+            match lookup::group_name(gid) {
+                Some(name) => write!(f, ":{}", name)?,
+                None => write!(f, ":{}", gid)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Return the id of the user given by `name`, which is either a user name or a numeric id.
+#[inline]
+pub fn user_id(name: &str) -> Result<u32, LookupError> {
+    name.parse()
+        .ok()
+        .or_else(|| lookup::user_id(name))
+        .ok_or_else(|| LookupError {
+            kind: "user",
+            name: name.to_string(),
+        })
+}
+
+/// Return the id of the group given by `name`, which is either a group name or a numeric id.
+#[inline]
+pub fn group_id(name: &str) -> Result<u32, LookupError> {
+    name.parse()
+        .ok()
+        .or_else(|| lookup::group_id(name))
+        .ok_or_else(|| LookupError {
+            kind: "group",
+            name: name.to_string(),
+        })
+}
+
+/// Return the operations that give `path` the ownership `owner` and permissions `mode`, in the
+/// order they should be performed. If `check` is given, only the attributes that the file at
+/// `check` doesn't already have are included.
+#[inline]
+pub(super) fn attr_ops<O>(
+    path: &Path,
+    check: Option<&Path>,
+    owner: &Owner,
+    mode: Option<Mode>,
+) -> Vec<O>
+where
+    O: From<ChownOp> + From<ChmodOp>,
+{
+    let mut ops = Vec::new();
+
+    // Ownership and permission bits are only supported on Unix; elsewhere, they're left alone.
+    if cfg!(not(unix)) {
+        return ops;
+    }
+
+    // Changing the owner may clear setuid and setgid bits, so it goes first.
+    if !owner.is_unset() && !check.is_some_and(|check| owner.matches(check)) {
+        ops.push(owner.op(path).into());
+    }
+    if let Some(mode) = mode {
+        if !check.is_some_and(|check| mode.matches(check)) {
+            ops.push(mode.op(path).into());
+        }
+    }
+
+    ops
+}
+
+/// Lookup of user and group names in the system databases, which only exist on Unix.
+#[cfg(unix)]
+mod lookup {
+    #[inline]
+    pub fn user_name(uid: u32) -> Option<String> {
+        users::get_user_by_uid(uid).map(|user| user.name().to_string_lossy().into_owned())
+    }
+
+    #[inline]
+    pub fn group_name(gid: u32) -> Option<String> {
+        users::get_group_by_gid(gid).map(|group| group.name().to_string_lossy().into_owned())
+    }
+
+    #[inline]
+    pub fn user_id(name: &str) -> Option<u32> {
+        users::get_user_by_name(name).map(|user| user.uid())
+    }
+
+    #[inline]
+    pub fn group_id(name: &str) -> Option<u32> {
+        users::get_group_by_name(name).map(|group| group.gid())
+    }
+}
+
+#[cfg(not(unix))]
+mod lookup {
+    #[inline]
+    pub fn user_name(_uid: u32) -> Option<String> {
+        None
+    }
+
+    #[inline]
+    pub fn group_name(_gid: u32) -> Option<String> {
+        None
+    }
+
+    #[inline]
+    pub fn user_id(_name: &str) -> Option<u32> {
+        None
+    }
+
+    #[inline]
+    pub fn group_id(_name: &str) -> Option<u32> {
+        None
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::MetadataExt;
+
+    use super::{group_id, user_id, Owner};
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(user_id("0").unwrap(), 0);
+        assert_eq!(user_id("root").unwrap(), 0);
+        assert_eq!(group_id("0").unwrap(), 0);
+        assert!(user_id("no-such-user-shelf").is_err());
+    }
+
+    #[test]
+    fn test_matches() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let path = tempdir.path().join("file");
+        fs::write(&path, "")?;
+        let meta = fs::metadata(&path)?;
+
+        let owner = |uid, gid| Owner { uid, gid };
+        assert!(owner(None, None).matches(&path));
+        assert!(owner(Some(meta.uid()), None).matches(&path));
+        assert!(owner(Some(meta.uid()), Some(meta.gid())).matches(&path));
+        assert!(!owner(Some(meta.uid() + 1), None).matches(&path));
+        assert!(!owner(None, Some(0)).matches(tempdir.path().join("none")));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,356 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use super::{
+    block,
+    generated::{self, env, ini, json, toml, yaml},
+    link, mkdir, template, tree, write, BlockAction, EnvAction, HandlebarsAction, IniAction,
+    JsonAction, LinkAction, LiquidAction, MkdirAction, Mode, Owner, Resolve, TomlAction,
+    TreeAction, WriteAction, YamlAction,
+};
+
+/// Trait for checking whether the files that an action deploys are up to date, without
+/// performing anything.
+pub trait Check {
+    type Output;
+
+    fn check(&self) -> Self::Output;
+}
+
+/// Status of a destination of an action.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Status {
+    /// The destination matches what the action would deploy.
+    UpToDate,
+    /// The action would not deploy anything.
+    NotDeployed,
+    /// The destination does not exist.
+    Missing,
+    /// The destination is a symlink that points to the contained path instead of the source.
+    LinkElsewhere(PathBuf),
+    /// The destination is a file of the expected type, but its contents differ.
+    Diverged,
+    /// The destination matches what the action would deploy, but its permissions differ; contains
+    /// its current permissions.
+    ModeChanged(Mode),
+    /// The destination matches what the action would deploy, but its owner or group differ;
+    /// contains its current owner and group.
+    OwnerChanged(Owner),
+    /// The destination is a file of an unexpected type.
+    Foreign,
+}
+
+impl Status {
+    /// Return true if the destination is not as the action would deploy it.
+    #[inline]
+    pub fn is_drift(&self) -> bool {
+        !matches!(self, Self::UpToDate | Self::NotDeployed)
+    }
+
+    /// Status of `dest`, which matches what the action would deploy except for its permissions
+    /// or ownership. `mode` is the permissions that the action would set.
+    #[inline]
+    fn of_attrs(dest: &Path, mode: Option<Mode>) -> Self {
+        match (Mode::of(dest), Owner::of(dest)) {
+            (Some(current), _) if mode.is_some_and(|mode| mode != current) => {
+                Self::ModeChanged(current)
+            }
+            (_, Some(owner)) => Self::OwnerChanged(owner),
+            _ => Self::Foreign,
+        }
+    }
+
+    #[inline]
+    fn from_write_res(res: write::Res, dest: &Path, mode: Option<Mode>) -> Self {
+        match res {
+            write::Res::Skip(write::Skip::DestExists) => Self::UpToDate,
+            write::Res::Permissions(_) => Self::of_attrs(dest, mode),
+            write::Res::Normal(_) => Self::Missing,
+            write::Res::OverwriteContents(_) => Self::Diverged,
+            write::Res::OverwriteFile(_) => Self::Foreign,
+        }
+    }
+
+    #[inline]
+    fn from_template_res(res: template::Res, dest: &Path, mode: Option<Mode>) -> Self {
+        match res {
+            template::Res::Skip(template::Skip::DestExists) => Self::UpToDate,
+            template::Res::Permissions(_) => Self::of_attrs(dest, mode),
+            template::Res::Skip(template::Skip::SameSrcDest | template::Skip::OptMissing) => {
+                Self::NotDeployed
+            }
+            template::Res::Normal(_) => Self::Missing,
+            template::Res::OverwriteContents(_) => Self::Diverged,
+            template::Res::OverwriteFile(_) => Self::Foreign,
+        }
+    }
+}
+
+impl Check for LinkAction {
+    type Output = Result<Status, link::Error>;
+
+    #[inline]
+    fn check(&self) -> Self::Output {
+        let status = match self.resolve()? {
+            link::Res::Skip(link::Skip::DestExists) => Status::UpToDate,
+            link::Res::Permissions(_) => Status::of_attrs(&self.dest, self.mode),
+            link::Res::Skip(link::Skip::SameSrcDest | link::Skip::OptMissing) => {
+                Status::NotDeployed
+            }
+            link::Res::Normal(_) => Status::Missing,
+            link::Res::Overwrite(_) => self.check_overwrite(),
+        };
+
+        Ok(status)
+    }
+}
+
+impl LinkAction {
+    /// Determine the status of a destination that would be overwritten.
+    #[inline]
+    fn check_overwrite(&self) -> Status {
+        let (dest_meta, src_meta) = match (
+            fs::symlink_metadata(&self.dest),
+            fs::symlink_metadata(&self.src),
+        ) {
+            (Ok(dest_meta), Ok(src_meta)) => (dest_meta, src_meta),
+            _ => return Status::Foreign,
+        };
+
+        if self.copy || self.hard {
+            // A copy or hard link of the same file type has diverged contents.
+            if dest_meta.file_type() == src_meta.file_type() {
+                Status::Diverged
+            } else {
+                Status::Foreign
+            }
+        } else if dest_meta.is_symlink() {
+            match fs::read_link(&self.dest) {
+                Ok(target) => Status::LinkElsewhere(target),
+                Err(_) => Status::Foreign,
+            }
+        } else {
+            Status::Foreign
+        }
+    }
+}
+
+impl Check for TreeAction {
+    type Output = Result<Vec<(LinkAction, Status)>, tree::Error>;
+
+    #[inline]
+    fn check(&self) -> Self::Output {
+        let actions = match self.link_actions()? {
+            Ok(actions) => actions,
+            Err(_) => return Ok(vec![]),
+        };
+
+        actions
+            .into_iter()
+            .map(|action| {
+                let status = action.check()?;
+                Ok((action, status))
+            })
+            .collect()
+    }
+}
+
+impl Check for WriteAction {
+    type Output = Status;
+
+    #[inline]
+    fn check(&self) -> Self::Output {
+        Status::from_write_res(self.resolve(), &self.dest, self.mode)
+    }
+}
+
+impl Check for MkdirAction {
+    type Output = Status;
+
+    #[inline]
+    fn check(&self) -> Self::Output {
+        match self.resolve() {
+            mkdir::Res::Skip(mkdir::Skip::DestExists) => Status::UpToDate,
+            mkdir::Res::Permissions(_) => Status::of_attrs(&self.path, self.mode),
+            mkdir::Res::Normal(_) => Status::Missing,
+            mkdir::Res::Overwrite(_) => Status::Foreign,
+        }
+    }
+}
+
+macro_rules! check_template_impl {
+    ($ty:ty, $err:ty) => {
+        impl Check for $ty {
+            type Output = Result<Status, $err>;
+
+            #[inline]
+            fn check(&self) -> Self::Output {
+                let res = self.resolve()?;
+                Ok(Status::from_template_res(res, &self.dest, self.mode))
+            }
+        }
+    };
+}
+
+check_template_impl!(HandlebarsAction, template::hbs::Error);
+check_template_impl!(LiquidAction, template::liquid::Error);
+
+macro_rules! check_generated_impl {
+    ($ty:ty, $err:ty) => {
+        impl Check for $ty {
+            type Output = Result<Status, $err>;
+
+            #[inline]
+            fn check(&self) -> Self::Output {
+                let res: generated::Res = self.resolve()?;
+                Ok(Status::from_write_res(res, &self.dest, self.mode))
+            }
+        }
+    };
+}
+
+check_generated_impl!(YamlAction, yaml::Error);
+check_generated_impl!(TomlAction, toml::Error);
+check_generated_impl!(JsonAction, json::Error);
+check_generated_impl!(IniAction, ini::Error);
+check_generated_impl!(EnvAction, env::Error);
+
+impl Check for BlockAction {
+    type Output = Result<Status, block::Error>;
+
+    #[inline]
+    fn check(&self) -> Self::Output {
+        let res = self.resolve()?;
+        Ok(Status::from_write_res(res, &self.dest, self.mode))
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::{symlink, MetadataExt, PermissionsExt};
+
+    use super::{Check, Status};
+    use crate::action::{LinkAction, Mode, Owner, WriteAction};
+
+    #[test]
+    fn test_link() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        let other = dir.join("other");
+        fs::write(&src, "src")?;
+        fs::write(&other, "other")?;
+
+        let action = |dest: &str, copy| LinkAction {
+            src: src.clone(),
+            dest: dir.join(dest),
+            copy,
+            hard: false,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        assert_eq!(action("missing", false).check().unwrap(), Status::Missing);
+
+        symlink(&src, dir.join("link"))?;
+        assert_eq!(action("link", false).check().unwrap(), Status::UpToDate);
+
+        symlink(&other, dir.join("elsewhere"))?;
+        assert_eq!(
+            action("elsewhere", false).check().unwrap(),
+            Status::LinkElsewhere(other.clone())
+        );
+        assert_eq!(action("other", false).check().unwrap(), Status::Foreign);
+        assert_eq!(action("other", true).check().unwrap(), Status::Diverged);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let action = |dest: &str| WriteAction {
+            dest: dir.join(dest),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        assert_eq!(action("file").check(), Status::Missing);
+
+        fs::write(dir.join("file"), "contents")?;
+        assert_eq!(action("file").check(), Status::UpToDate);
+
+        fs::write(dir.join("file"), "changed")?;
+        assert_eq!(action("file").check(), Status::Diverged);
+
+        fs::create_dir(dir.join("dir"))?;
+        assert_eq!(action("dir").check(), Status::Foreign);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mode() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dest = tempdir.path().join("file");
+
+        let mode: Mode = "0600".parse().unwrap();
+        let action = WriteAction {
+            dest: dest.clone(),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: Some(mode),
+            owner: Default::default(),
+        };
+
+        fs::write(&dest, "contents")?;
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o644))?;
+        assert_eq!(action.check(), Status::ModeChanged("0644".parse().unwrap()));
+
+        fs::set_permissions(&dest, fs::Permissions::from_mode(0o600))?;
+        assert_eq!(action.check(), Status::UpToDate);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_owner() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dest = tempdir.path().join("file");
+        fs::write(&dest, "contents")?;
+        let meta = fs::metadata(&dest)?;
+
+        let action = |uid| WriteAction {
+            dest: dest.clone(),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Owner {
+                uid: Some(uid),
+                gid: None,
+            },
+        };
+
+        assert_eq!(action(meta.uid()).check(), Status::UpToDate);
+        assert_eq!(
+            action(meta.uid() + 1).check(),
+            Status::OwnerChanged(Owner {
+                uid: Some(meta.uid()),
+                gid: Some(meta.gid()),
+            })
+        );
+
+        Ok(())
+    }
+}
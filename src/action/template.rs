@@ -5,7 +5,7 @@ use std::path::Path;
 use crate::fse;
 
 use super::write::{Res as WriteActionRes, WriteAction};
-use super::Resolve;
+use super::{Mode, Overwrite, Owner, Resolve};
 
 // Re-export action types.
 pub use self::{hbs::HandlebarsAction, liquid::LiquidAction};
@@ -21,6 +21,8 @@ pub enum Res {
     OverwriteContents(Vec<Op>),
     /// The existing destination file will be replaced.
     OverwriteFile(Vec<Op>),
+    /// The destination is up to date, but its permissions or ownership will be changed.
+    Permissions(Vec<Op>),
     /// The action is skipped.
     Skip(Skip),
 }
@@ -34,6 +36,7 @@ impl Res {
             WriteActionRes::Normal(ops) => Self::Normal(ops),
             WriteActionRes::OverwriteContents(ops) => Self::OverwriteContents(ops),
             WriteActionRes::OverwriteFile(ops) => Self::OverwriteFile(ops),
+            WriteActionRes::Permissions(ops) => Self::Permissions(ops),
             WriteActionRes::Skip(skip) => Self::Skip(match skip {
                 WriteActionSkip::DestExists => Skip::DestExists,
             }),
@@ -60,7 +63,7 @@ pub mod hbs {
     use handlebars::Handlebars;
     use serde::Serialize;
 
-    use super::{Object, Res, Resolve};
+    use super::{Mode, Object, Overwrite, Owner, Res, Resolve};
 
     // Re-export handlebars error types.
     pub use handlebars::{RenderError, TemplateError};
@@ -75,6 +78,9 @@ pub mod hbs {
 
         pub optional: bool,
         pub partials: HandlebarsPartials,
+        pub overwrite: Option<Overwrite>,
+        pub mode: Option<Mode>,
+        pub owner: Owner,
     }
 
     #[derive(Debug, thiserror::Error)]
@@ -100,11 +106,21 @@ pub mod hbs {
                 vars,
                 optional,
                 partials,
+                overwrite,
+                mode,
+                owner,
             } = self;
 
-            super::resolve_impl(src, dest, vars, optional, |src, _dest, vars| {
-                render(src, vars, partials)
-            })
+            super::resolve_impl(
+                src,
+                dest,
+                vars,
+                optional,
+                overwrite,
+                mode,
+                owner,
+                |src, _dest, vars| render(src, vars, partials),
+            )
             .and_then(|res| res.ok_or(Error::SrcMissing))
         }
     }
@@ -120,7 +136,7 @@ pub mod hbs {
         let mut reg = Handlebars::new();
         partials
             .iter()
-            .map(|(name, path)| reg.register_template_file(name, &path))
+            .map(|(name, path)| reg.register_template_file(name, path))
             .collect::<Result<Vec<_>, _>>()?;
 
         let res = reg.render_template(&template_str, ctx)?;
@@ -135,7 +151,7 @@ pub mod liquid {
     use liquid::ParserBuilder;
     use serde::Serialize;
 
-    use super::{Object, Res, Resolve};
+    use super::{Mode, Object, Overwrite, Owner, Res, Resolve};
 
     // Re-export liquid error type.
     pub use liquid::Error as LiquidError;
@@ -147,6 +163,9 @@ pub mod liquid {
         pub vars: Object,
 
         pub optional: bool,
+        pub overwrite: Option<Overwrite>,
+        pub mode: Option<Mode>,
+        pub owner: Owner,
     }
 
     #[derive(Debug, thiserror::Error)]
@@ -169,11 +188,21 @@ pub mod liquid {
                 dest,
                 vars,
                 optional,
+                overwrite,
+                mode,
+                owner,
             } = self;
 
-            super::resolve_impl(src, dest, vars, optional, |src, _dest, vars| {
-                render(src, vars)
-            })
+            super::resolve_impl(
+                src,
+                dest,
+                vars,
+                optional,
+                overwrite,
+                mode,
+                owner,
+                |src, _dest, vars| render(src, vars),
+            )
             .and_then(|res| res.ok_or(Error::SrcMissing))
         }
     }
@@ -191,12 +220,16 @@ pub mod liquid {
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 #[inline]
 fn resolve_impl<E, RF>(
     src: &Path,
     dest: &Path,
     vars: &Object,
     optional: &bool,
+    overwrite: &Option<Overwrite>,
+    mode: &Option<Mode>,
+    owner: &Owner,
     render: RF,
 ) -> Result<Option<Res>, E>
 where
@@ -220,6 +253,9 @@ where
             let wa = WriteAction {
                 dest: dest.to_path_buf(),
                 contents: contents.into_bytes(),
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             };
             let res = wa.resolve();
 
@@ -232,3 +268,67 @@ where
 fn read_template<P: AsRef<Path>>(path: P) -> io::Result<String> {
     fs::read_to_string(path)
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::BTreeMap;
+    use std::fs;
+
+    use super::{HandlebarsAction, LiquidAction, Object, Op, Res, Resolve};
+    use crate::action::object::Value;
+
+    fn contents(res: Res) -> String {
+        match res {
+            Res::Normal(ops) => ops
+                .into_iter()
+                .find_map(|op| match op {
+                    Op::Write(op) => Some(String::from_utf8(op.contents).unwrap()),
+                    _ => None,
+                })
+                .unwrap(),
+            res => panic!("unexpected resolution {:?}", res),
+        }
+    }
+
+    #[test]
+    fn test_array() -> std::io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let plugins = Value::Array(vec![
+            Value::Str("telescope".to_string()),
+            Value::Str("lsp".to_string()),
+        ]);
+        let vars = Object(BTreeMap::from([("plugins".to_string(), plugins)]));
+
+        fs::write(dir.join("a.hbs"), "{{#each plugins}}{{this}}\n{{/each}}")?;
+        let action = HandlebarsAction {
+            src: dir.join("a.hbs"),
+            dest: dir.join("a"),
+            vars: vars.clone(),
+            optional: false,
+            partials: Default::default(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+        assert_eq!(contents(action.resolve().unwrap()), "telescope\nlsp\n");
+
+        fs::write(
+            dir.join("b.liquid"),
+            "{% for plugin in plugins %}{{ plugin }}\n{% endfor %}",
+        )?;
+        let action = LiquidAction {
+            src: dir.join("b.liquid"),
+            dest: dir.join("b"),
+            vars,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+        assert_eq!(contents(action.resolve().unwrap()), "telescope\nlsp\n");
+
+        Ok(())
+    }
+}
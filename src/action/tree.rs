@@ -1,13 +1,15 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::path::{Path, PathBuf};
-use std::{env, fs};
+use std::{fs, io, iter};
 
 use glob::{GlobError, PatternError};
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
 
 use crate::fse;
+use crate::op::{LinkOp, MkdirOp, RmOp};
 
-use super::link::Res as LinkActionRes;
-use super::{LinkAction, Resolve};
+use super::link::{Error as LinkActionError, Op as LinkActionOp, Res as LinkActionRes};
+use super::{LinkAction, Mode, Overwrite, Owner, Resolve};
 
 pub type Patterns = Vec<Pattern>;
 pub type Pattern = String;
@@ -18,15 +20,28 @@ pub struct TreeAction {
     pub dest: PathBuf,
     pub globs: Patterns,
     pub ignore: Patterns,
+    /// Also honour `.gitignore` files in `src`, in addition to `.shelfignore` files.
+    pub gitignore: bool,
 
     pub copy: bool,
+    pub hard: bool,
+    pub relative: Option<bool>,
+    pub prune: bool,
+    /// Symlink whole directories whose destinations don't exist yet, instead of each file in
+    /// them. Ignored for copies and hard links.
+    pub fold: bool,
     pub optional: bool,
+    pub overwrite: Option<Overwrite>,
+    pub mode: Option<Mode>,
+    pub owner: Owner,
 }
 
 #[derive(Debug, Clone)]
 pub enum Res {
     // TODO: Better API than this?
-    Normal(Vec<LinkActionRes>),
+    /// Resolutions of the files in the tree, and removals of the extra files in `dest` if
+    /// pruning.
+    Normal(Vec<LinkActionRes>, Vec<RmOp>),
     /// The action is skipped.
     Skip(Skip),
 }
@@ -50,6 +65,10 @@ pub enum Error {
     Glob(#[from] GlobError),
     #[error("pattern error")]
     Pattern(#[from] PatternError),
+    #[error("ignore file error")]
+    IgnoreFile(#[from] ignore::Error),
+    #[error("link error")]
+    Link(#[from] LinkActionError),
 }
 
 impl Resolve for TreeAction {
@@ -57,19 +76,42 @@ impl Resolve for TreeAction {
 
     #[inline]
     fn resolve(&self) -> Self::Output {
+        let actions = match self.link_actions()? {
+            Ok(actions) => actions,
+            Err(skip) => return Ok(Res::Skip(skip)),
+        };
+
+        let resvec = actions
+            .into_iter()
+            .map(|action| action.resolve())
+            .collect::<Result<_, _>>()?;
+        Ok(Res::Normal(resvec, self.prune_ops()?))
+    }
+}
+
+impl TreeAction {
+    /// Return the [`LinkAction`] for each file in the tree, or the reason for skipping the whole
+    /// tree.
+    #[inline]
+    pub fn link_actions(&self) -> Result<Result<Vec<LinkAction>, Skip>, Error> {
         let Self {
             src,
             dest,
-            globs,
-            ignore,
             copy,
+            hard,
+            relative,
+            prune,
             optional,
+            overwrite,
+            mode,
+            owner,
+            ..
         } = self;
 
         match (optional, fse::symlink_exists(src)) {
             // `src` is optional and does not exist; skip.
             (true, false) => {
-                return Ok(Res::Skip(Skip::OptMissing));
+                return Ok(Err(Skip::OptMissing));
             }
             // `src` is not optional but does not exist; skip.
             (false, false) => {
@@ -79,33 +121,283 @@ impl Resolve for TreeAction {
             _ => {}
         };
 
-        // Glob to get file paths.
-        let mut paths = glob_tree(&src, globs)?;
-        // Glob to get ignored paths.
-        let ignore_paths = glob_tree(&src, ignore)?;
-
-        // Remove all the ignored paths from the globbed paths.
-        for path in ignore_paths {
-            paths.remove(&path);
+        let mut paths = self.paths(src)?;
+        if self.fold && !*copy && !*hard {
+            paths = self.fold(paths);
         }
 
         // Join these back into full paths for src and dest.
-        let src_paths = paths.iter().map(|path| src.join(path));
-        let dest_paths = paths.iter().map(|path| dest.join(path));
+        let src_paths = paths.iter().map(|path| join(src, path));
+        let dest_paths = paths.iter().map(|path| join(dest, path));
 
         // Map paths and dest paths into linking actions.
-        let it = src_paths
+        let actions = src_paths
             .zip(dest_paths)
             .map(move |(fsrc, fdest)| LinkAction {
                 src: fsrc,
                 dest: fdest,
                 copy: *copy,
+                hard: *hard,
+                relative: *relative,
+                prune: *prune,
                 optional: false,
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
+            })
+            .collect();
+
+        Ok(Ok(actions))
+    }
+
+    /// Return the operations that remove the files in `dest` matched by the globs that aren't in
+    /// `src`. These are only returned for copies with `prune` set.
+    #[inline]
+    pub fn prune_ops(&self) -> Result<Vec<RmOp>, Error> {
+        let Self {
+            src,
+            dest,
+            copy,
+            prune,
+            ..
+        } = self;
+
+        if !(*copy && *prune && src.is_dir() && dest.is_dir()) {
+            return Ok(vec![]);
+        }
+
+        let src_paths = self.paths(src)?;
+        let dest_paths = self.paths(dest)?;
+
+        let mut extra: Vec<_> = dest_paths.difference(&src_paths).collect();
+        extra.sort();
+
+        let ops = extra
+            .into_iter()
+            .map(|path| RmOp {
+                path: dest.join(path),
+                dir: false,
+            })
+            .collect();
+        Ok(ops)
+    }
+}
+
+impl TreeAction {
+    /// Return the paths of the files under `root` that match `globs` but not `ignore`, relative
+    /// to `root`. Paths excluded by the ignore files in `src` are also removed.
+    #[inline]
+    fn paths(&self, root: &Path) -> Result<HashSet<PathBuf>, Error> {
+        // Glob to get file paths.
+        let mut paths = glob_tree(root, &self.globs)?;
+        // Glob to get ignored paths.
+        let ignore_paths = glob_tree(root, &self.ignore)?;
+
+        // Remove all the ignored paths from the globbed paths.
+        for path in ignore_paths {
+            paths.remove(&path);
+        }
+
+        // Remove the paths excluded by ignore files, and the ignore files themselves.
+        let mut ignore_files = IgnoreFiles::new(&self.src, self.gitignore);
+        let mut kept = HashSet::with_capacity(paths.len());
+        for path in paths {
+            if path.file_name().is_some_and(|name| name == SHELFIGNORE)
+                || ignore_files.is_ignored(&path, false)?
+            {
+                continue;
+            }
+            kept.insert(path);
+        }
+
+        Ok(kept)
+    }
+}
+
+impl TreeAction {
+    /// Replace the files in `paths` with their topmost parent directory that can be symlinked
+    /// as a whole. A directory can be if its destination doesn't exist (or already links to it)
+    /// and every file in it is included in the tree. If the root itself can be, `paths` is
+    /// replaced with the empty path.
+    #[inline]
+    fn fold(&self, paths: HashSet<PathBuf>) -> HashSet<PathBuf> {
+        let root = Path::new("");
+        if !paths.is_empty() && self.is_foldable(root, &paths) {
+            return iter::once(root.to_path_buf()).collect();
+        }
+
+        let mut foldable = HashMap::new();
+
+        let mut folded = HashSet::new();
+        for path in &paths {
+            let parents: Vec<_> = path
+                .ancestors()
+                .skip(1)
+                .filter(|parent| !parent.as_os_str().is_empty())
+                .collect();
+            let dir = parents.into_iter().rev().find(|dir| {
+                *foldable
+                    .entry(dir.to_path_buf())
+                    .or_insert_with(|| self.is_foldable(dir, &paths))
             });
 
-        // SAFETY: Should be fine since all these files should exist?
-        let resvec: Vec<_> = it.map(|action| action.resolve().unwrap()).collect();
-        Ok(Res::Normal(resvec))
+            folded.insert(dir.unwrap_or(path).to_path_buf());
+        }
+
+        folded
+    }
+
+    /// Return true if the directory `dir`, relative to `src`, can be symlinked as a whole.
+    #[inline]
+    fn is_foldable(&self, dir: &Path, paths: &HashSet<PathBuf>) -> bool {
+        let dest = join(&self.dest, dir);
+        (!fse::symlink_exists(&dest) || fse::links_to(&dest, join(&self.src, dir)))
+            && is_included(&self.src, dir, paths)
+    }
+}
+
+/// Join `path` onto `base`. Unlike [`Path::join`], an empty `path` doesn't add a trailing
+/// separator, which would make the result refer to what a symlink at `base` points to.
+#[inline]
+fn join(base: &Path, path: &Path) -> PathBuf {
+    if path.as_os_str().is_empty() {
+        base.to_path_buf()
+    } else {
+        base.join(path)
+    }
+}
+
+/// Return true if every file in the directory `dir` under `root`, recursively, is in `paths`.
+#[inline]
+fn is_included(root: &Path, dir: &Path, paths: &HashSet<PathBuf>) -> bool {
+    let names = match fse::dir_entries(&join(root, dir)) {
+        Ok(names) => names,
+        Err(_) => return false,
+    };
+
+    names.into_iter().all(|name| {
+        let path = dir.join(name);
+        match fs::symlink_metadata(root.join(&path)) {
+            Ok(meta) if meta.is_dir() => is_included(root, &path, paths),
+            Ok(_) => paths.contains(&path),
+            Err(_) => false,
+        }
+    })
+}
+
+/// Return the operations that unfold `link`, a symlink to the directory `target` that was
+/// deployed by a tree in fold mode, into a real directory with a symlink to each entry of
+/// `target`. This lets other actions deploy into the directory without writing into `target`.
+#[inline]
+pub fn unfold_ops(link: &Path, target: &Path) -> io::Result<Vec<LinkActionOp>> {
+    let relative = fs::read_link(link)?.is_relative();
+
+    let mut ops = vec![
+        LinkActionOp::Rm(RmOp {
+            path: link.to_path_buf(),
+            dir: false,
+        }),
+        LinkActionOp::Mkdir(MkdirOp {
+            path: link.to_path_buf(),
+        }),
+    ];
+    ops.extend(fse::dir_entries(target)?.into_iter().map(|name| {
+        LinkActionOp::Link(LinkOp {
+            src: target.join(&name),
+            dest: link.join(name),
+            relative,
+        })
+    }));
+
+    Ok(ops)
+}
+
+/// Name of the ignore files that are always honoured.
+const SHELFIGNORE: &str = ".shelfignore";
+/// Name of the ignore files that are honoured if requested.
+const GITIGNORE: &str = ".gitignore";
+
+/// Matcher for the gitignore-syntax ignore files found in a tree. As in git, the patterns in a
+/// file apply to paths under the directory that contains it, and deeper files take precedence.
+struct IgnoreFiles<'a> {
+    root: &'a Path,
+    names: &'static [&'static str],
+    /// Matchers for the ignore files of each directory, relative to `root`.
+    dirs: HashMap<PathBuf, Gitignore>,
+}
+
+impl<'a> IgnoreFiles<'a> {
+    #[inline]
+    fn new(root: &'a Path, gitignore: bool) -> Self {
+        // The .shelfignore comes last, so it takes precedence over .gitignore in the same
+        // directory.
+        let names: &'static [&'static str] = if gitignore {
+            &[GITIGNORE, SHELFIGNORE]
+        } else {
+            &[SHELFIGNORE]
+        };
+
+        Self {
+            root,
+            names,
+            dirs: HashMap::new(),
+        }
+    }
+
+    /// Return true if `path`, relative to the root, is ignored, either itself or because one of
+    /// its parent directories is.
+    #[inline]
+    fn is_ignored(&mut self, path: &Path, is_dir: bool) -> Result<bool, Error> {
+        // Files in an ignored directory can't be re-included, so check the parents first.
+        let parents: Vec<_> = path
+            .ancestors()
+            .skip(1)
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .collect();
+        for parent in parents.into_iter().rev() {
+            if self.matched(parent, true)? {
+                return Ok(true);
+            }
+        }
+
+        self.matched(path, is_dir)
+    }
+
+    /// Return true if the last matching pattern for `path` ignores it.
+    #[inline]
+    fn matched(&mut self, path: &Path, is_dir: bool) -> Result<bool, Error> {
+        let full = self.root.join(path);
+        for dir in path.ancestors().skip(1) {
+            let matched = self.dir(dir)?.matched(&full, is_dir);
+            if matched.is_ignore() {
+                return Ok(true);
+            } else if matched.is_whitelist() {
+                return Ok(false);
+            }
+        }
+
+        Ok(false)
+    }
+
+    /// Return the matcher for the ignore files in `dir`, relative to the root.
+    #[inline]
+    fn dir(&mut self, dir: &Path) -> Result<&Gitignore, Error> {
+        if !self.dirs.contains_key(dir) {
+            let full = self.root.join(dir);
+            let mut builder = GitignoreBuilder::new(&full);
+            for name in self.names {
+                let file = full.join(name);
+                if file.is_file() {
+                    if let Some(err) = builder.add(file) {
+                        return Err(err.into());
+                    }
+                }
+            }
+            self.dirs.insert(dir.to_path_buf(), builder.build()?);
+        }
+
+        // SAFETY: The matcher was just inserted if it was missing.
+        Ok(self.dirs.get(dir).unwrap())
     }
 }
 
@@ -114,26 +406,29 @@ fn glob_tree<P>(src: P, pats: &[String]) -> Result<HashSet<PathBuf>, Error>
 where
     P: AsRef<Path>,
 {
-    // TODO: Better way to do this than chdir?
-    let cwd = env::current_dir().unwrap();
-    env::set_current_dir(&src).unwrap();
+    // Glob with patterns rooted at `src`, and then make the matches relative to it again.
+    let src = src.as_ref();
+    let root = PathBuf::from(glob::Pattern::escape(&src.to_string_lossy()));
 
     let matches: Vec<glob::Paths> = pats
         .iter()
-        .map(|pat| glob::glob(pat))
+        .map(|pat| glob::glob(&root.join(pat).to_string_lossy()))
         .collect::<Result<_, _>>()?;
 
     let res = matches
         .into_iter()
         .flatten()
         .filter_map(|r| match r {
-            Ok(path) => Some(path).filter(|path| keep_globbed(path)).map(Ok),
+            Ok(path) => Some(path).filter(|path| keep_globbed(path)).map(|path| {
+                Ok(path
+                    .strip_prefix(src)
+                    .map(Path::to_path_buf)
+                    .unwrap_or(path))
+            }),
             Err(err) => Some(Err(err)),
         })
         .collect::<Result<_, _>>()?;
 
-    env::set_current_dir(&cwd).unwrap();
-
     Ok(res)
 }
 
@@ -148,3 +443,173 @@ where
         Err(_) => false,
     }
 }
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::os::unix::fs as unix_fs;
+    use std::path::{Path, PathBuf};
+
+    use super::{glob_tree, unfold_ops, Error, IgnoreFiles, LinkActionOp, TreeAction};
+
+    #[test]
+    fn test_ignore_files() -> Result<(), Error> {
+        let tempdir = tempfile::tempdir().unwrap();
+        let root = tempdir.path();
+        fs::create_dir_all(root.join("lua/build")).unwrap();
+        fs::write(root.join(".shelfignore"), "*.log\n!keep.log\n/build/\n").unwrap();
+        fs::write(root.join("lua/.shelfignore"), "!debug.log\n").unwrap();
+        fs::write(root.join(".gitignore"), "*.tmp\n").unwrap();
+
+        let mut ignore_files = IgnoreFiles::new(root, false);
+        let mut ignored = |path: &str| ignore_files.is_ignored(Path::new(path), false);
+        assert!(ignored("a.log")?);
+        assert!(ignored("lua/a.log")?);
+        // Negation, including from a deeper ignore file.
+        assert!(!ignored("keep.log")?);
+        assert!(!ignored("lua/debug.log")?);
+        // Anchored directory patterns only match at the directory of the ignore file.
+        assert!(ignored("build/init.lua")?);
+        assert!(!ignored("lua/build/init.lua")?);
+        // .gitignore files are only honoured if requested.
+        assert!(!ignored("a.tmp")?);
+        assert!(IgnoreFiles::new(root, true).is_ignored(Path::new("a.tmp"), false)?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_glob_tree() -> Result<(), Error> {
+        let tempdir = tempfile::tempdir().unwrap();
+        // Glob metacharacters in the root itself are matched literally.
+        let root = tempdir.path().join("[pkg]");
+        fs::create_dir_all(root.join("lua")).unwrap();
+        fs::write(root.join("init.lua"), "").unwrap();
+        fs::write(root.join("lua/a.lua"), "").unwrap();
+
+        let cwd = std::env::current_dir().unwrap();
+        let paths = glob_tree(&root, &["**/*".to_string()])?;
+        let expected: HashSet<_> = ["init.lua", "lua/a.lua"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(paths, expected);
+
+        assert!(glob_tree(&root, &["[".to_string()]).is_err());
+        assert_eq!(std::env::current_dir().unwrap(), cwd);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fold() -> std::io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let (src, dest) = (tempdir.path().join("src"), tempdir.path().join("dest"));
+        for dir in ["nvim/lua", "zsh", "git"] {
+            fs::create_dir_all(src.join(dir))?;
+        }
+        for file in [
+            "nvim/init.lua",
+            "nvim/lua/a.lua",
+            "zsh/zshrc",
+            "git/config",
+            "git/a.log",
+        ] {
+            fs::write(src.join(file), "")?;
+        }
+        fs::create_dir_all(dest.join("zsh"))?;
+
+        let action = TreeAction {
+            src: src.clone(),
+            dest: dest.clone(),
+            globs: vec![],
+            ignore: vec![],
+            gitignore: false,
+            copy: false,
+            hard: false,
+            relative: None,
+            prune: false,
+            fold: true,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        // git/a.log is excluded, so git can't be folded; zsh already exists at the destination.
+        let paths = ["nvim/init.lua", "nvim/lua/a.lua", "zsh/zshrc", "git/config"];
+        let folded = action.fold(paths.iter().map(PathBuf::from).collect());
+        let expected: HashSet<_> = ["nvim", "zsh/zshrc", "git/config"]
+            .iter()
+            .map(PathBuf::from)
+            .collect();
+        assert_eq!(folded, expected);
+
+        // Unfolding replaces the directory link with links to each entry.
+        unix_fs::symlink(src.join("nvim"), dest.join("nvim"))?;
+        let ops = unfold_ops(&dest.join("nvim"), &src.join("nvim"))?;
+        assert!(matches!(
+            &ops[..],
+            [LinkActionOp::Rm(_), LinkActionOp::Mkdir(_), LinkActionOp::Link(a), LinkActionOp::Link(b)]
+                if a.src == src.join("nvim/init.lua") && b.dest == dest.join("nvim/lua")
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_fold_root() -> std::io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let src = tempdir.path().join("nvim");
+        let dest = tempdir.path().join("home/.config/nvim");
+        fs::create_dir_all(src.join("lua"))?;
+        fs::write(src.join("init.lua"), "")?;
+        fs::write(src.join("lua/a.lua"), "")?;
+
+        let action = TreeAction {
+            src: src.clone(),
+            dest: dest.clone(),
+            globs: vec!["**/*".to_string()],
+            ignore: vec![],
+            gitignore: false,
+            copy: false,
+            hard: false,
+            relative: None,
+            prune: false,
+            fold: true,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        // The destination doesn't exist, so the whole tree is linked at once.
+        let links = action.link_actions().unwrap().unwrap();
+        assert!(matches!(
+            &links[..],
+            [link] if link.src.as_os_str() == src.as_os_str()
+                && link.dest.as_os_str() == dest.as_os_str()
+        ));
+
+        // It stays folded once linked.
+        fs::create_dir_all(dest.parent().unwrap())?;
+        unix_fs::symlink(&src, &dest)?;
+        assert_eq!(action.link_actions().unwrap().unwrap().len(), 1);
+        fs::remove_file(&dest)?;
+
+        // Ignored files can't be linked, so the root isn't folded.
+        let ignoring = TreeAction {
+            ignore: vec!["lua/a.lua".to_string()],
+            ..action.clone()
+        };
+        let folded = ignoring.fold(["init.lua"].iter().map(PathBuf::from).collect());
+        assert_eq!(folded, ["init.lua"].iter().map(PathBuf::from).collect());
+
+        // Neither is it if the destination already exists.
+        fs::create_dir(&dest)?;
+        assert_eq!(action.link_actions().unwrap().unwrap().len(), 2);
+
+        Ok(())
+    }
+}
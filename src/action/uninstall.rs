@@ -0,0 +1,489 @@
+use std::fs;
+use std::path::Path;
+
+use crate::fse;
+use crate::op::{RmOp, WriteOp};
+
+use super::{
+    block,
+    generated::{self, env, ini, json, toml, yaml},
+    link, template, tree, write, BlockAction, EnvAction, HandlebarsAction, IniAction, JsonAction,
+    LinkAction, LiquidAction, Resolve, TomlAction, TreeAction, WriteAction, YamlAction,
+};
+
+/// Trait for computing the removal of the files that an action deploys.
+///
+/// A destination is only removed if it still matches what the action would deploy, i.e. if
+/// resolving the action would skip it because the destination already exists.
+pub trait Uninstall {
+    type Output;
+
+    fn uninstall(&self) -> Self::Output;
+}
+
+/// Resolution of the uninstallation of an action.
+#[derive(Debug, Clone)]
+pub enum Res {
+    /// Normal procedure.
+    Normal(RmOp),
+    /// The destination is kept, and only the block that was inserted into it is removed.
+    RemoveBlock(WriteOp),
+    /// The action is skipped.
+    Skip(Skip),
+}
+
+/// Reason for skipping the uninstallation of an action.
+#[derive(Debug, Clone)]
+pub enum Skip {
+    /// The action would not deploy anything.
+    NotDeployed,
+    /// The destination does not exist.
+    DestMissing,
+    /// The destination exists but differs from what the action would deploy.
+    DestChanged,
+    /// The destination was merged into, so it also holds contents that weren't deployed.
+    Merged,
+}
+
+impl Res {
+    /// Removal of `dest`, which matches what the action deploys.
+    #[inline]
+    fn remove(dest: &Path) -> Self {
+        Self::Normal(RmOp {
+            path: dest.to_path_buf(),
+            dir: fs::symlink_metadata(dest).is_ok_and(|meta| meta.is_dir()),
+        })
+    }
+
+    /// Skip of `dest`, which doesn't match what the action deploys.
+    #[inline]
+    fn unmatched(dest: &Path) -> Self {
+        if fse::symlink_exists(dest) {
+            Self::Skip(Skip::DestChanged)
+        } else {
+            Self::Skip(Skip::DestMissing)
+        }
+    }
+
+    #[inline]
+    fn from_write_res(res: write::Res, dest: &Path) -> Self {
+        match res {
+            write::Res::Skip(write::Skip::DestExists) | write::Res::Permissions(_) => {
+                Self::remove(dest)
+            }
+            write::Res::Normal(_)
+            | write::Res::OverwriteContents(_)
+            | write::Res::OverwriteFile(_) => Self::unmatched(dest),
+        }
+    }
+
+    #[inline]
+    fn from_template_res(res: template::Res, dest: &Path) -> Self {
+        match res {
+            template::Res::Skip(template::Skip::DestExists) | template::Res::Permissions(_) => {
+                Self::remove(dest)
+            }
+            template::Res::Skip(template::Skip::SameSrcDest | template::Skip::OptMissing) => {
+                Self::Skip(Skip::NotDeployed)
+            }
+            template::Res::Normal(_)
+            | template::Res::OverwriteContents(_)
+            | template::Res::OverwriteFile(_) => Self::unmatched(dest),
+        }
+    }
+}
+
+impl Uninstall for LinkAction {
+    type Output = Result<Res, link::Error>;
+
+    #[inline]
+    fn uninstall(&self) -> Self::Output {
+        let res = match self.resolve()? {
+            // A copied directory without pruning is up to date even if it has extra files, but
+            // those weren't deployed and mustn't be removed with it.
+            link::Res::Skip(link::Skip::DestExists) | link::Res::Permissions(_)
+                if self.copy
+                    && self.dest.is_dir()
+                    && !fse::same_contents(&self.src, &self.dest) =>
+            {
+                Res::unmatched(&self.dest)
+            }
+            link::Res::Skip(link::Skip::DestExists) | link::Res::Permissions(_) => {
+                Res::remove(&self.dest)
+            }
+            link::Res::Skip(link::Skip::SameSrcDest | link::Skip::OptMissing) => {
+                Res::Skip(Skip::NotDeployed)
+            }
+            link::Res::Normal(_) | link::Res::Overwrite(_) => Res::unmatched(&self.dest),
+        };
+
+        Ok(res)
+    }
+}
+
+impl Uninstall for TreeAction {
+    type Output = Result<Vec<(LinkAction, Res)>, tree::Error>;
+
+    #[inline]
+    fn uninstall(&self) -> Self::Output {
+        let actions = match self.link_actions()? {
+            Ok(actions) => actions,
+            Err(_) => return Ok(vec![]),
+        };
+
+        actions
+            .into_iter()
+            .map(|action| {
+                let res = action.uninstall()?;
+                Ok((action, res))
+            })
+            .collect()
+    }
+}
+
+impl Uninstall for WriteAction {
+    type Output = Res;
+
+    #[inline]
+    fn uninstall(&self) -> Self::Output {
+        Res::from_write_res(self.resolve(), &self.dest)
+    }
+}
+
+macro_rules! uninstall_template_impl {
+    ($ty:ty, $err:ty) => {
+        impl Uninstall for $ty {
+            type Output = Result<Res, $err>;
+
+            #[inline]
+            fn uninstall(&self) -> Self::Output {
+                let res = self.resolve()?;
+                Ok(Res::from_template_res(res, &self.dest))
+            }
+        }
+    };
+}
+
+uninstall_template_impl!(HandlebarsAction, template::hbs::Error);
+uninstall_template_impl!(LiquidAction, template::liquid::Error);
+
+macro_rules! uninstall_generated_impl {
+    ($ty:ty, $err:ty $(, $merge:ident)?) => {
+        impl Uninstall for $ty {
+            type Output = Result<Res, $err>;
+
+            #[inline]
+            fn uninstall(&self) -> Self::Output {
+                // Removing a merged destination would also remove what was already there.
+                $(if self.$merge.is_some() {
+                    return Ok(if fse::symlink_exists(&self.dest) {
+                        Res::Skip(Skip::Merged)
+                    } else {
+                        Res::Skip(Skip::DestMissing)
+                    });
+                })?
+
+                let res: generated::Res = self.resolve()?;
+                Ok(Res::from_write_res(res, &self.dest))
+            }
+        }
+    };
+}
+
+uninstall_generated_impl!(YamlAction, yaml::Error, merge);
+uninstall_generated_impl!(TomlAction, toml::Error, merge);
+uninstall_generated_impl!(JsonAction, json::Error, merge);
+uninstall_generated_impl!(IniAction, ini::Error);
+uninstall_generated_impl!(EnvAction, env::Error);
+
+impl Uninstall for BlockAction {
+    type Output = Result<Res, block::Error>;
+
+    #[inline]
+    fn uninstall(&self) -> Self::Output {
+        let current = match fs::metadata(&self.dest) {
+            Ok(meta) if meta.is_file() => fs::read_to_string(&self.dest)?,
+            Ok(_) => return Ok(Res::Skip(Skip::DestChanged)),
+            Err(_) => return Ok(Res::Skip(Skip::DestMissing)),
+        };
+
+        // The file is kept even if it only held the block, since it may not have been created
+        // for it.
+        let res = match self.remove(&current)? {
+            Some(contents) => Res::RemoveBlock(WriteOp {
+                path: self.dest.clone(),
+                contents: contents.into_bytes(),
+            }),
+            None => Res::Skip(Skip::NotDeployed),
+        };
+
+        Ok(res)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io;
+    use std::os::unix::fs::symlink;
+    use std::path::Path;
+
+    use super::{Res, Skip, Uninstall};
+    use crate::action::{
+        block, object::Object, BlockAction, LinkAction, TreeAction, WriteAction, YamlAction,
+    };
+    use crate::op::RmOp;
+
+    fn link_action(src: &Path, dest: &Path, copy: bool) -> LinkAction {
+        LinkAction {
+            src: src.to_path_buf(),
+            dest: dest.to_path_buf(),
+            copy,
+            hard: false,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        }
+    }
+
+    fn is_remove(res: &Res, path: &Path, dir: bool) -> bool {
+        matches!(res, Res::Normal(RmOp { path: p, dir: d }) if p == path && *d == dir)
+    }
+
+    #[test]
+    fn test_link() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        let other = dir.join("other");
+        fs::write(&src, "src")?;
+        fs::write(&other, "other")?;
+        symlink(&src, dir.join("link"))?;
+        symlink(&other, dir.join("elsewhere"))?;
+
+        let uninstall = |dest: &str| {
+            link_action(&src, &dir.join(dest), false)
+                .uninstall()
+                .unwrap()
+        };
+
+        assert!(is_remove(&uninstall("link"), &dir.join("link"), false));
+        assert!(matches!(
+            uninstall("elsewhere"),
+            Res::Skip(Skip::DestChanged)
+        ));
+        assert!(matches!(uninstall("other"), Res::Skip(Skip::DestChanged)));
+        assert!(matches!(uninstall("missing"), Res::Skip(Skip::DestMissing)));
+        assert!(matches!(uninstall("src"), Res::Skip(Skip::NotDeployed)));
+
+        let mut optional = link_action(&dir.join("nothing"), &dir.join("link"), false);
+        optional.optional = true;
+        assert!(matches!(
+            optional.uninstall().unwrap(),
+            Res::Skip(Skip::NotDeployed)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_copy() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let (src, dest) = (dir.join("src"), dir.join("dest"));
+        for root in [&src, &dest] {
+            fs::create_dir(root)?;
+            fs::write(root.join("a"), "a")?;
+        }
+
+        let action = link_action(&src, &dest, true);
+        assert!(is_remove(&action.uninstall().unwrap(), &dest, true));
+
+        // Files that weren't copied mustn't be removed with the directory.
+        fs::write(dest.join("b"), "b")?;
+        assert!(matches!(
+            action.uninstall().unwrap(),
+            Res::Skip(Skip::DestChanged)
+        ));
+
+        let file = link_action(&src.join("a"), &dest.join("a"), true);
+        assert!(is_remove(
+            &file.uninstall().unwrap(),
+            &dest.join("a"),
+            false
+        ));
+        fs::write(dest.join("a"), "changed")?;
+        assert!(matches!(
+            file.uninstall().unwrap(),
+            Res::Skip(Skip::DestChanged)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_tree() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let (src, dest) = (dir.join("src"), dir.join("dest"));
+        fs::create_dir(&src)?;
+        fs::create_dir(&dest)?;
+        fs::write(src.join("a"), "a")?;
+        fs::write(src.join("b"), "b")?;
+        symlink(src.join("a"), dest.join("a"))?;
+
+        let action = TreeAction {
+            src: src.clone(),
+            dest: dest.clone(),
+            globs: vec!["**/*".to_string()],
+            ignore: vec![],
+            gitignore: false,
+            copy: false,
+            hard: false,
+            relative: None,
+            prune: false,
+            fold: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        let mut res = action.uninstall().unwrap();
+        res.sort_by(|(a, _), (b, _)| a.dest.cmp(&b.dest));
+        assert!(
+            matches!(&res[..], [(a, ra), (b, Res::Skip(Skip::DestMissing))]
+            if a.dest == dest.join("a")
+                && is_remove(ra, &dest.join("a"), false)
+                && b.dest == dest.join("b"))
+        );
+
+        // A missing optional source deploys nothing.
+        let missing = TreeAction {
+            src: dir.join("missing"),
+            optional: true,
+            ..action
+        };
+        assert!(missing.uninstall().unwrap().is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_write() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let action = |dest: &str| WriteAction {
+            dest: dir.join(dest),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+
+        fs::write(dir.join("file"), "contents")?;
+        assert!(is_remove(
+            &action("file").uninstall(),
+            &dir.join("file"),
+            false
+        ));
+
+        fs::write(dir.join("file"), "changed")?;
+        assert!(matches!(
+            action("file").uninstall(),
+            Res::Skip(Skip::DestChanged)
+        ));
+        assert!(matches!(
+            action("missing").uninstall(),
+            Res::Skip(Skip::DestMissing)
+        ));
+
+        fs::create_dir(dir.join("dir"))?;
+        assert!(matches!(
+            action("dir").uninstall(),
+            Res::Skip(Skip::DestChanged)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_generated_merged() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dest = tempdir.path().join("config.yaml");
+
+        let action = YamlAction {
+            dest: dest.clone(),
+            values: Object(Default::default()),
+            header: None,
+            merge: Some(Default::default()),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        };
+        assert!(matches!(
+            action.uninstall().unwrap(),
+            Res::Skip(Skip::DestMissing)
+        ));
+
+        // The destination also holds keys that weren't deployed, so it's never removed.
+        fs::write(&dest, "a: 1\n")?;
+        assert!(matches!(
+            action.uninstall().unwrap(),
+            Res::Skip(Skip::Merged)
+        ));
+
+        Ok(())
+    }
+    #[test]
+    fn test_block() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dest = tempdir.path().join("bashrc");
+
+        let action = BlockAction {
+            dest: dest.clone(),
+            contents: "alias ll='ls -l'".to_string(),
+            marker: "shelf:bash".to_string(),
+            comment: "#".to_string(),
+            mode: None,
+            owner: Default::default(),
+        };
+        let contents = |res| match res {
+            Res::RemoveBlock(op) => Some(String::from_utf8(op.contents).unwrap()),
+            _ => None,
+        };
+
+        assert!(matches!(
+            action.uninstall().unwrap(),
+            Res::Skip(Skip::DestMissing)
+        ));
+
+        fs::write(&dest, action.insert("export A=1\n").unwrap())?;
+        assert_eq!(
+            contents(action.uninstall().unwrap()).as_deref(),
+            Some("export A=1\n")
+        );
+
+        // A file that only holds the block is emptied rather than removed.
+        fs::write(&dest, action.insert("").unwrap())?;
+        assert_eq!(contents(action.uninstall().unwrap()).as_deref(), Some(""));
+
+        fs::write(&dest, "export A=1\n")?;
+        assert!(matches!(
+            action.uninstall().unwrap(),
+            Res::Skip(Skip::NotDeployed)
+        ));
+
+        fs::write(&dest, "# BEGIN shelf:bash\nexport A=1\n")?;
+        assert!(matches!(action.uninstall(), Err(block::Error::Unbalanced)));
+
+        Ok(())
+    }
+}
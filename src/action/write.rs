@@ -1,9 +1,10 @@
 use std::fs;
 use std::path::PathBuf;
 
-use crate::op::{CreateOp, MkdirOp, RmOp, WriteOp};
+use crate::fse;
+use crate::op::{ChmodOp, ChownOp, CreateOp, MkdirOp, RmOp, WriteOp};
 
-use super::{mkdir, Resolve};
+use super::{mkdir, owner, Mode, Overwrite, Owner, Resolve};
 
 /// Action to write `contents` to a file at `dest`.
 #[derive(Debug, Clone)]
@@ -13,6 +14,13 @@ pub struct WriteAction {
     /// Contents to be written.
     // TODO: AsRef<[u8]> instead?
     pub contents: Vec<u8>,
+
+    /// Policy for an existing destination; if `None`, the default policy is used.
+    pub overwrite: Option<Overwrite>,
+    /// Permission bits to set on the destination.
+    pub mode: Option<Mode>,
+    /// Owner and group to set on the destination.
+    pub owner: Owner,
 }
 
 #[derive(Debug, Clone)]
@@ -22,6 +30,8 @@ pub enum Res {
     OverwriteContents(Vec<Op>),
     /// The existing destination file will be replaced.
     OverwriteFile(Vec<Op>),
+    /// The destination is up to date, but its permissions or ownership will be changed.
+    Permissions(Vec<Op>),
     /// The action is skipped.
     Skip(Skip),
 }
@@ -36,6 +46,24 @@ pub enum Op {
     Write(WriteOp),
     /// Mkdir operation.
     Mkdir(MkdirOp),
+    /// Chmod operation.
+    Chmod(ChmodOp),
+    /// Chown operation.
+    Chown(ChownOp),
+}
+
+impl From<ChmodOp> for Op {
+    #[inline]
+    fn from(op: ChmodOp) -> Self {
+        Self::Chmod(op)
+    }
+}
+
+impl From<ChownOp> for Op {
+    #[inline]
+    fn from(op: ChownOp) -> Self {
+        Self::Chown(op)
+    }
 }
 
 /// Reason for skipping [`WriteAction`].
@@ -50,34 +78,50 @@ impl Resolve for WriteAction {
 
     #[inline]
     fn resolve(&self) -> Self::Output {
-        let Self { dest, contents } = self;
+        let Self {
+            dest,
+            contents,
+            mode,
+            owner,
+            ..
+        } = self;
 
         // If the destination file already exists, check the filetype.
         match fs::symlink_metadata(dest) {
             // For files, check the contents. If they match, we should do nothing.
             // Otherwise, warn about an overwrite and write.
-            Ok(meta) if meta.is_file() => match fs::read(dest) {
-                // Check for content same.
-                Ok(dest_contents) if dest_contents == *contents => Res::Skip(Skip::DestExists),
-                // If error, just assume content is different.
-                Ok(_) | Err(_) => {
-                    let ops = vec![self.as_op()];
-                    Res::OverwriteContents(ops)
+            // The ownership and permissions of an existing file only need to be changed if
+            // they differ.
+            Ok(meta) if meta.is_file() && fse::has_contents(dest, contents) => {
+                let ops: Vec<_> = owner::attr_ops(dest, Some(dest), owner, *mode);
+                if ops.is_empty() {
+                    Res::Skip(Skip::DestExists)
+                } else {
+                    Res::Permissions(ops)
                 }
-            },
+            }
+            // If error, just assume content is different.
+            Ok(meta) if meta.is_file() => {
+                // Change the permissions before writing, so the new contents are never
+                // exposed with the old permissions.
+                let mut ops: Vec<_> = owner::attr_ops(dest, Some(dest), owner, *mode);
+                ops.push(self.as_op());
+                Res::OverwriteContents(ops)
+            }
 
             // For other kinds of files, warn about an overwrite, remove the directory, create a
             // file, and then write.
             Ok(meta) if meta.is_dir() | meta.is_symlink() => {
                 let dir = meta.is_dir();
-                let ops = vec![
+                let mut ops = vec![
                     Op::Rm(RmOp {
                         path: dest.clone(),
                         dir,
                     }),
                     Op::Create(CreateOp { path: dest.clone() }),
-                    self.as_op(),
                 ];
+                ops.extend(self.create_attr_ops());
+                ops.push(self.as_op());
                 Res::OverwriteFile(ops)
             }
 
@@ -87,6 +131,7 @@ impl Resolve for WriteAction {
 
                 // We need to first create a file before writing to it.
                 ops.push(Op::Create(CreateOp { path: dest.clone() }));
+                ops.extend(self.create_attr_ops());
                 // Add write operation.
                 ops.push(self.as_op());
 
@@ -99,11 +144,18 @@ impl Resolve for WriteAction {
 impl WriteAction {
     #[inline]
     fn as_op(&self) -> Op {
-        let Self { dest, contents } = self;
+        let Self { dest, contents, .. } = self;
 
         Op::Write(WriteOp {
             path: dest.clone(),
             contents: contents.clone(),
         })
     }
+
+    /// Return the operations that set the ownership and permissions of a newly created
+    /// destination.
+    #[inline]
+    fn create_attr_ops(&self) -> Vec<Op> {
+        owner::attr_ops(&self.dest, None, &self.owner, self.mode)
+    }
 }
@@ -1,6 +1,9 @@
-use std::fs;
+use std::fs::{self, File};
+use std::io;
 use std::path::{Component, Path, PathBuf};
 
+use sha2::{digest::Output, Digest, Sha256};
+
 #[inline]
 pub fn symlink_exists<P>(path: P) -> bool
 where
@@ -40,3 +43,231 @@ where
 
     components.into_iter().collect()
 }
+
+/// Return `path` relative to the directory `base`, e.g. `../a/b` for `/x/a/b` relative to `/x/y`.
+/// The paths are compared lexically, so they should both be absolute and not involve symlinks.
+#[inline]
+pub fn relative<P, Q>(path: P, base: Q) -> PathBuf
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let path = clean(path);
+    let base = clean(base);
+
+    let mut path_components = path.components().peekable();
+    let mut base_components = base.components().peekable();
+
+    // Skip the common prefix.
+    while let (Some(a), Some(b)) = (path_components.peek(), base_components.peek()) {
+        if a != b {
+            break;
+        }
+        path_components.next();
+        base_components.next();
+    }
+
+    let relative: PathBuf = base_components
+        .map(|_| Component::ParentDir)
+        .chain(path_components)
+        .collect();
+    if relative.as_os_str().is_empty() {
+        PathBuf::from(".")
+    } else {
+        relative
+    }
+}
+
+/// Return true if `link` is a symlink that points to `target`, either by an absolute path or by
+/// a path relative to the directory of `link`.
+#[inline]
+pub fn links_to<P, Q>(link: P, target: Q) -> bool
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (link, target) = (link.as_ref(), target.as_ref());
+    match fs::read_link(link) {
+        Ok(dest) if dest.is_absolute() => clean(dest) == clean(target),
+        Ok(dest) => {
+            let dir = link.parent().unwrap_or_else(|| Path::new(""));
+            clean(dir.join(dest)) == clean(target)
+        }
+        Err(_) => false,
+    }
+}
+
+/// Return true if `a` and `b` are the same file, e.g. hard links to each other, following
+/// symlinks.
+#[inline]
+pub fn same_file<P, Q>(a: P, b: Q) -> bool
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+            _ => false,
+        }
+    }
+
+    // Without inode numbers, only the paths themselves can be compared.
+    #[cfg(not(unix))]
+    {
+        match (fs::canonicalize(a), fs::canonicalize(b)) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => false,
+        }
+    }
+}
+
+/// Return true if the files at `a` and `b` are on the same filesystem, following symlinks. If
+/// either can't be read, or the platform can't tell, they're assumed to be.
+#[inline]
+pub fn same_device<P, Q>(a: P, b: Q) -> bool
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::MetadataExt;
+
+        match (fs::metadata(a), fs::metadata(b)) {
+            (Ok(a), Ok(b)) => a.dev() == b.dev(),
+            _ => true,
+        }
+    }
+
+    #[cfg(not(unix))]
+    {
+        let _ = (a, b);
+        true
+    }
+}
+
+/// Hash the contents of the file at `path` without reading it into memory all at once.
+#[inline]
+pub fn hash_file<P>(path: P) -> io::Result<Output<Sha256>>
+where
+    P: AsRef<Path>,
+{
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hasher.finalize())
+}
+
+/// Return true if the file at `path` contains exactly `contents`, following symlinks. The sizes
+/// are compared first, so differing files are usually detected without reading them.
+#[inline]
+pub fn has_contents<P>(path: P, contents: &[u8]) -> bool
+where
+    P: AsRef<Path>,
+{
+    let path = path.as_ref();
+    match fs::metadata(path) {
+        Ok(meta) if meta.is_file() && meta.len() == contents.len() as u64 => {
+            hash_file(path).is_ok_and(|hash| hash == Sha256::digest(contents))
+        }
+        Ok(_) | Err(_) => false,
+    }
+}
+
+/// Return true if `a` and `b` have the same contents, following symlinks. Files are compared by
+/// size and then by hash; directories are compared recursively and must contain the same entries.
+/// Any error reading either path is treated as a difference.
+#[inline]
+pub fn same_contents<P, Q>(a: P, b: Q) -> bool
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let (a, b) = (a.as_ref(), b.as_ref());
+    match (fs::metadata(a), fs::metadata(b)) {
+        (Ok(a_meta), Ok(b_meta)) if a_meta.is_file() && b_meta.is_file() => {
+            a_meta.len() == b_meta.len()
+                && match (hash_file(a), hash_file(b)) {
+                    (Ok(a_hash), Ok(b_hash)) => a_hash == b_hash,
+                    _ => false,
+                }
+        }
+        (Ok(a_meta), Ok(b_meta)) if a_meta.is_dir() && b_meta.is_dir() => {
+            match (dir_entries(a), dir_entries(b)) {
+                (Ok(a_entries), Ok(b_entries)) => {
+                    a_entries == b_entries
+                        && a_entries
+                            .iter()
+                            .all(|name| same_contents(a.join(name), b.join(name)))
+                }
+                _ => false,
+            }
+        }
+        _ => false,
+    }
+}
+
+/// Return the sorted names of the entries of the directory at `path`.
+#[inline]
+pub fn dir_entries(path: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut names = fs::read_dir(path)?
+        .map(|entry| entry.map(|entry| PathBuf::from(entry.file_name())))
+        .collect::<io::Result<Vec<_>>>()?;
+    names.sort();
+    Ok(names)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+    use std::io;
+    use std::path::Path;
+
+    use super::{has_contents, relative, same_contents};
+
+    #[test]
+    fn test_relative() {
+        let rel = |path, base| relative(path, base);
+        assert_eq!(rel("/x/a/b", "/x/y"), Path::new("../a/b"));
+        assert_eq!(rel("/x/a", "/x"), Path::new("a"));
+        assert_eq!(rel("/x", "/x/y/z"), Path::new("../.."));
+        assert_eq!(rel("/x/./a/../b", "/x"), Path::new("b"));
+        assert_eq!(rel("/x", "/x"), Path::new("."));
+    }
+
+    #[test]
+    fn test_same_contents() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        // Binary contents that aren't valid UTF-8.
+        let binary = [0xff, 0xfe, 0x00, 0x80];
+        for name in ["a", "b"] {
+            fs::create_dir_all(dir.join(name).join("sub"))?;
+            fs::write(dir.join(name).join("font.bin"), binary)?;
+            fs::write(dir.join(name).join("sub/c.txt"), "c")?;
+        }
+
+        assert!(same_contents(
+            dir.join("a/font.bin"),
+            dir.join("b/font.bin")
+        ));
+        assert!(same_contents(dir.join("a"), dir.join("b")));
+        assert!(has_contents(dir.join("a/font.bin"), &binary));
+        assert!(!has_contents(dir.join("a/font.bin"), &binary[1..]));
+        assert!(!same_contents(dir.join("a/font.bin"), dir.join("a")));
+
+        fs::write(dir.join("b/sub/c.txt"), "d")?;
+        assert!(!same_contents(dir.join("a"), dir.join("b")));
+        fs::write(dir.join("b/sub/c.txt"), "c")?;
+        fs::write(dir.join("b/sub/extra"), "")?;
+        assert!(!same_contents(dir.join("a"), dir.join("b")));
+        assert!(!same_contents(dir.join("a"), dir.join("none")));
+
+        Ok(())
+    }
+}
@@ -5,14 +5,15 @@ use std::slice;
 use mlua::{Function, Lua};
 
 use crate::action::{
-    Action, CommandAction, FunctionAction, HandlebarsAction, JsonAction, LinkAction, LiquidAction,
-    MkdirAction, TomlAction, TreeAction, WriteAction, YamlAction,
+    Action, BlockAction, CommandAction, EnvAction, FunctionAction, HandlebarsAction, IniAction,
+    JsonAction, LinkAction, LiquidAction, MkdirAction, TomlAction, TreeAction, WriteAction,
+    YamlAction,
 };
 use crate::fse;
 use crate::graph::PackageData;
 use crate::spec::{
-    CmdHook, DirFile, Directive, File, FunHook, GeneratedFile, GeneratedFileTyp, Hook, LinkType,
-    RegularFile, TemplatedFile, TemplatedFileType, TreeFile,
+    BlockFile, CmdHook, DirFile, Directive, File, FunHook, GeneratedFile, GeneratedFileTyp, Hook,
+    LinkType, RegularFile, TemplatedFile, TemplatedFileType, TreeFile,
 };
 
 impl PackageData {
@@ -24,6 +25,7 @@ impl PackageData {
         ActionIter {
             dest: dest.as_ref().to_path_buf(),
             path: &self.path,
+            name: &self.spec.name,
             lua: &self.lua,
             directives: self.spec.directives.iter(),
         }
@@ -33,6 +35,7 @@ impl PackageData {
 pub struct ActionIter<'g> {
     dest: PathBuf,
     path: &'g Path,
+    name: &'g str,
     lua: &'g Lua,
 
     directives: slice::Iter<'g, Directive>,
@@ -43,6 +46,7 @@ impl<'p> fmt::Debug for ActionIter<'p> {
         f.debug_struct("ActionIter")
             .field("dest", &self.dest)
             .field("path", &self.path)
+            .field("name", &self.name)
             .field("lua", &"<lua>")
             .field("directives", &self.directives)
             .finish()
@@ -75,6 +79,7 @@ impl<'g> ActionIter<'g> {
             File::Templated(tf) => self.get_file_template(tf),
             File::Tree(tf) => self.get_file_tree(tf),
             File::Generated(gf) => self.get_file_generated(gf),
+            File::Block(bf) => self.get_file_block(bf),
             File::Dir(df) => self.get_file_dir(df),
         }
     }
@@ -85,7 +90,12 @@ impl<'g> ActionIter<'g> {
             src,
             dest,
             link_type,
+            relative,
+            prune,
             optional,
+            overwrite,
+            mode,
+            owner,
         } = rf;
 
         // Normalize src.
@@ -93,17 +103,24 @@ impl<'g> ActionIter<'g> {
         // Normalize dest (or use src if absent).
         let dest_w = self.join_dest(dest.as_ref().unwrap_or(src));
 
-        // Determine copy flag.
-        let copy = match link_type {
-            LinkType::Link => false,
-            LinkType::Copy => true,
+        // Determine copy and hard link flags.
+        let (copy, hard) = match link_type {
+            LinkType::Link => (false, false),
+            LinkType::Copy => (true, false),
+            LinkType::Hardlink => (false, true),
         };
 
         Action::Link(LinkAction {
             src: src_w,
             dest: dest_w,
             copy,
+            hard,
+            relative: *relative,
+            prune: *prune,
             optional: *optional,
+            overwrite: *overwrite,
+            mode: *mode,
+            owner: *owner,
         })
     }
 
@@ -115,6 +132,9 @@ impl<'g> ActionIter<'g> {
             vars,
             typ,
             optional,
+            overwrite,
+            mode,
+            owner,
         } = tf;
 
         // Normalize src.
@@ -129,12 +149,18 @@ impl<'g> ActionIter<'g> {
                 vars: vars.clone(),
                 optional: *optional,
                 partials: hbs.partials.clone(),
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             }),
             TemplatedFileType::Liquid(_) => Action::Liquid(LiquidAction {
                 src: src_w,
                 dest: dest_w,
                 vars: vars.clone(),
                 optional: *optional,
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             }),
         }
     }
@@ -146,8 +172,15 @@ impl<'g> ActionIter<'g> {
             dest,
             globs,
             ignore,
+            gitignore,
+            fold,
             link_type,
+            relative,
+            prune,
             optional,
+            overwrite,
+            mode,
+            owner,
         } = tf;
 
         // Normalize src.
@@ -162,10 +195,11 @@ impl<'g> ActionIter<'g> {
         let globs = globs.clone().unwrap_or_else(|| vec!["**/*".to_string()]);
         let ignore = ignore.clone().unwrap_or_default();
 
-        // Determine copy flag.
-        let copy = match link_type {
-            LinkType::Link => false,
-            LinkType::Copy => true,
+        // Determine copy and hard link flags.
+        let (copy, hard) = match link_type {
+            LinkType::Link => (false, false),
+            LinkType::Copy => (true, false),
+            LinkType::Hardlink => (false, true),
         };
 
         Action::Tree(TreeAction {
@@ -173,14 +207,28 @@ impl<'g> ActionIter<'g> {
             dest: dest_w,
             globs,
             ignore,
+            gitignore: *gitignore,
+            fold: *fold,
             copy,
+            hard,
+            relative: *relative,
+            prune: *prune,
             optional: *optional,
+            overwrite: *overwrite,
+            mode: *mode,
+            owner: *owner,
         })
     }
 
     #[inline]
     fn get_file_generated(&self, gf: &GeneratedFile) -> Action<'g> {
-        let GeneratedFile { dest, typ } = gf;
+        let GeneratedFile {
+            dest,
+            typ,
+            overwrite,
+            mode,
+            owner,
+        } = gf;
 
         // Normalize dest.
         let dest_w = self.join_dest(dest);
@@ -189,37 +237,101 @@ impl<'g> ActionIter<'g> {
             GeneratedFileTyp::Empty(_) => Action::Write(WriteAction {
                 dest: dest_w,
                 contents: "".to_string().into_bytes(),
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             }),
             GeneratedFileTyp::String(s) => Action::Write(WriteAction {
                 dest: dest_w,
                 contents: s.contents.clone().into_bytes(),
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             }),
             // FIXME error context
             GeneratedFileTyp::Yaml(y) => Action::Yaml(YamlAction {
                 dest: dest_w,
                 values: y.values.clone(),
                 header: y.header.clone(),
+                merge: y.merge,
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             }),
             GeneratedFileTyp::Toml(t) => Action::Toml(TomlAction {
                 dest: dest_w,
                 values: t.values.clone(),
                 header: t.header.clone(),
+                merge: t.merge,
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             }),
             GeneratedFileTyp::Json(j) => Action::Json(JsonAction {
                 dest: dest_w,
                 values: j.values.clone(),
+                merge: j.merge,
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
+            }),
+            GeneratedFileTyp::Ini(i) => Action::Ini(IniAction {
+                dest: dest_w,
+                values: i.values.clone(),
+                header: i.header.clone(),
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
+            }),
+            GeneratedFileTyp::Env(e) => Action::Env(EnvAction {
+                dest: dest_w,
+                values: e.values.clone(),
+                header: e.header.clone(),
+                overwrite: *overwrite,
+                mode: *mode,
+                owner: *owner,
             }),
         }
     }
 
+    #[inline]
+    fn get_file_block(&self, bf: &BlockFile) -> Action<'g> {
+        let BlockFile {
+            dest,
+            contents,
+            marker,
+            comment,
+            mode,
+            owner,
+        } = bf;
+
+        Action::Block(BlockAction {
+            dest: self.join_dest(dest),
+            contents: contents.clone(),
+            marker: marker
+                .clone()
+                .unwrap_or_else(|| format!("shelf:{}", self.name)),
+            comment: comment.clone().unwrap_or_else(|| "#".to_string()),
+            mode: *mode,
+            owner: *owner,
+        })
+    }
+
     #[inline]
     fn get_file_dir(&self, df: &DirFile) -> Action<'g> {
-        let DirFile { dest, parents } = df;
+        let DirFile {
+            dest,
+            parents,
+            mode,
+            owner,
+        } = df;
 
         let path = self.join_dest(dest);
         Action::Mkdir(MkdirAction {
             path,
             parents: *parents,
+            mode: *mode,
+            owner: *owner,
         })
     }
 
@@ -290,7 +402,7 @@ impl<'g> ActionIter<'g> {
     where
         P: AsRef<Path>,
     {
-        self.normalize_path(path, &self.path)
+        self.normalize_path(path, self.path)
     }
 
     #[inline]
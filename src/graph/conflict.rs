@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+use crate::action::Action;
+
+use super::PackageGraph;
+
+/// Declaration of a destination by an action of a package.
+#[derive(Debug)]
+pub struct Declaration<'g> {
+    /// Path of the package.
+    pub package: &'g Path,
+    /// Action that deploys the destination.
+    pub action: Action<'g>,
+}
+
+/// Destination declared by more than one package.
+#[derive(Debug)]
+pub struct Conflict<'g> {
+    /// Path of the destination.
+    pub dest: PathBuf,
+    /// Declarations of the destination, in package order.
+    pub declarations: Vec<Declaration<'g>>,
+}
+
+/// Destinations declared by more than one package.
+#[derive(Debug, Default)]
+pub struct Conflicts<'g> {
+    /// Conflicts that no package overrides.
+    pub unresolved: Vec<Conflict<'g>>,
+    /// Map of each overridden destination to the package with the highest priority, which is
+    /// the only one that should deploy it.
+    pub overrides: HashMap<PathBuf, PathBuf>,
+}
+
+impl PackageGraph {
+    /// Resolve the actions of every package with `dest` as the destination directory, and return
+    /// the destinations declared by more than one package.
+    ///
+    /// A conflict is resolved if a single one of the packages sets a priority higher than the
+    /// others. Directories created by mkdir actions and files that are merged into can be shared,
+    /// so they never conflict.
+    #[inline]
+    pub fn conflicts<P>(&self, dest: P) -> Conflicts<'_>
+    where
+        P: AsRef<Path>,
+    {
+        let dest = dest.as_ref();
+
+        let mut declared: BTreeMap<PathBuf, Vec<Declaration<'_>>> = BTreeMap::new();
+        for pd in self.iter() {
+            for action in pd.action_iter(dest) {
+                if matches!(action, Action::Mkdir(_)) || action.merges() {
+                    continue;
+                }
+
+                // A package may deploy the same destination more than once; only the first
+                // declaration is kept.
+                for adest in action.dests() {
+                    let decls = declared.entry(adest).or_default();
+                    if decls.iter().all(|decl| decl.package != pd.path) {
+                        decls.push(Declaration {
+                            package: &pd.path,
+                            action: action.clone(),
+                        });
+                    }
+                }
+            }
+        }
+
+        let mut conflicts = Conflicts::default();
+        for (dest, declarations) in declared {
+            if declarations.len() < 2 {
+                continue;
+            }
+
+            match self.winner(&declarations) {
+                Some(package) => {
+                    conflicts.overrides.insert(dest, package.to_path_buf());
+                }
+                None => conflicts.unresolved.push(Conflict { dest, declarations }),
+            }
+        }
+
+        conflicts
+    }
+
+    /// Return the package with the highest priority among `declarations`, if there is exactly
+    /// one.
+    #[inline]
+    fn winner<'d>(&self, declarations: &'d [Declaration<'_>]) -> Option<&'d Path> {
+        let priority =
+            |decl: &Declaration<'_>| self.get(decl.package).and_then(|pd| pd.spec.priority);
+
+        let max = declarations.iter().filter_map(priority).max()?;
+        let winners: HashSet<_> = declarations
+            .iter()
+            .filter(|decl| priority(decl) == Some(max))
+            .map(|decl| decl.package)
+            .collect();
+
+        if winners.len() == 1 {
+            winners.into_iter().next()
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::path::{Path, PathBuf};
+
+    use mlua::Lua;
+
+    use super::super::{PackageData, PackageGraph};
+    use super::Conflicts;
+    use crate::spec::{BlockFile, DirFile, Directive, File, LinkType, RegularFile, Spec};
+
+    fn link(src: &str, dest: &str) -> Directive {
+        Directive::File(File::Regular(RegularFile {
+            src: PathBuf::from(src),
+            dest: Some(PathBuf::from(dest)),
+            link_type: LinkType::Link,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        }))
+    }
+
+    fn graph(packages: Vec<(&str, Option<u32>, Vec<Directive>)>) -> PackageGraph {
+        let mut graph = PackageGraph::new();
+        for (path, priority, directives) in packages {
+            graph.add_package(PackageData {
+                path: PathBuf::from(path),
+                spec: Spec {
+                    name: path.trim_start_matches('/').to_string(),
+                    deps: vec![],
+                    priority,
+                    directives,
+                },
+                lua: Lua::new(),
+            });
+        }
+        graph
+    }
+
+    fn packages<'g>(conflicts: &'g Conflicts<'_>) -> Vec<(&'g Path, Vec<&'g Path>)> {
+        conflicts
+            .unresolved
+            .iter()
+            .map(|conflict| {
+                let packages = conflict
+                    .declarations
+                    .iter()
+                    .map(|decl| decl.package)
+                    .collect();
+                (conflict.dest.as_path(), packages)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_conflict() {
+        let graph = graph(vec![
+            ("/a", None, vec![link("vimrc", ".vimrc")]),
+            (
+                "/b",
+                None,
+                vec![link("vimrc", ".vimrc"), link("zshrc", ".zshrc")],
+            ),
+        ]);
+
+        let conflicts = graph.conflicts("/home");
+        assert_eq!(
+            packages(&conflicts),
+            vec![(
+                Path::new("/home/.vimrc"),
+                vec![Path::new("/a"), Path::new("/b")]
+            )]
+        );
+        assert!(conflicts.overrides.is_empty());
+    }
+
+    #[test]
+    fn test_override() {
+        let graph = graph(vec![
+            ("/a", None, vec![link("vimrc", ".vimrc")]),
+            ("/b", Some(1), vec![link("vimrc", ".vimrc")]),
+            ("/c", Some(0), vec![link("vimrc", ".vimrc")]),
+        ]);
+
+        let conflicts = graph.conflicts("/home");
+        assert!(conflicts.unresolved.is_empty());
+        assert_eq!(conflicts.overrides.len(), 1);
+        assert_eq!(
+            conflicts.overrides.get(Path::new("/home/.vimrc")),
+            Some(&PathBuf::from("/b"))
+        );
+    }
+
+    #[test]
+    fn test_equal_priority() {
+        let graph = graph(vec![
+            ("/a", Some(1), vec![link("vimrc", ".vimrc")]),
+            ("/b", Some(1), vec![link("vimrc", ".vimrc")]),
+            ("/c", None, vec![link("vimrc", ".vimrc")]),
+        ]);
+
+        // Neither of the highest priority packages wins, so the conflict is reported with every
+        // declaration.
+        let conflicts = graph.conflicts("/home");
+        assert_eq!(
+            packages(&conflicts),
+            vec![(
+                Path::new("/home/.vimrc"),
+                vec![Path::new("/a"), Path::new("/b"), Path::new("/c")]
+            )]
+        );
+        assert!(conflicts.overrides.is_empty());
+    }
+
+    #[test]
+    fn test_shared() {
+        let mkdir = || {
+            Directive::File(File::Dir(DirFile {
+                dest: PathBuf::from(".config"),
+                parents: false,
+                mode: None,
+                owner: Default::default(),
+            }))
+        };
+        let block = || {
+            Directive::File(File::Block(BlockFile {
+                dest: PathBuf::from(".bashrc"),
+                contents: String::new(),
+                marker: None,
+                comment: None,
+                mode: None,
+                owner: Default::default(),
+            }))
+        };
+
+        // Directories and merged files can be shared, and a package doesn't conflict with
+        // itself.
+        let graph = graph(vec![
+            (
+                "/a",
+                None,
+                vec![
+                    mkdir(),
+                    block(),
+                    link("vimrc", ".vimrc"),
+                    link("vimrc", ".vimrc"),
+                ],
+            ),
+            ("/b", None, vec![mkdir(), block()]),
+        ]);
+
+        let conflicts = graph.conflicts("/home");
+        assert!(conflicts.unresolved.is_empty());
+        assert!(conflicts.overrides.is_empty());
+    }
+}
@@ -1,4 +1,5 @@
 mod action;
+mod conflict;
 
 use std::collections::{
     hash_map::{self, DefaultHasher},
@@ -19,6 +20,7 @@ use crate::fse;
 use crate::spec::{Dep, Spec};
 
 pub use self::action::ActionIter;
+pub use self::conflict::{Conflict, Conflicts, Declaration};
 
 pub struct PackageData {
     /// Absolute path of the package.
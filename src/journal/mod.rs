@@ -13,6 +13,8 @@ use serde::{Deserialize, Serialize};
 pub enum Record<T> {
     Atom(T),
     Commit,
+    /// Commit of a transaction that was appended by a [`RollbackIter`].
+    RollbackCommit,
 }
 
 /// Write-ahead logging.
@@ -101,6 +103,7 @@ mod test {
     pub const FORWARD: Record<Datum> = Record::Atom(Datum::Forward);
     pub const BACKWARD: Record<Datum> = Record::Atom(Datum::Backward);
     pub const COMMIT: Record<Datum> = Record::Commit;
+    pub const ROLLBACK_COMMIT: Record<Datum> = Record::RollbackCommit;
 
     #[test]
     fn test_size() {
@@ -29,26 +29,61 @@ where
 {
     /// Return a [`RollbackIter`] that rolls-back until the last commit.
     ///
-    /// If the latest record is a commit or there are no records, the iterator will do nothing.
+    /// The uncommitted records are first committed as a transaction of their own, so that the
+    /// rollback is recorded like any other (see [`Journal::rollback_last`]). If the latest record
+    /// is a commit or there are no records, the iterator will do nothing.
     ///
     /// See [`RollbackIter`].
     #[inline]
     pub fn rollback(&mut self) -> RollbackIter<'_, T> {
-        RollbackIter::new(self)
+        match self.latest() {
+            Some(Record::Atom(_)) => {
+                self.append(Record::Commit);
+                RollbackIter::new_idx(self, 1)
+            }
+            _ => RollbackIter::new(self),
+        }
     }
 
-    /// Return a [`RollbackIter`] that rolls-back the last transaction.
+    /// Return a [`RollbackIter`] that rolls-back the last transaction that hasn't already been
+    /// rolled back.
     ///
-    /// If the latest record is a commit, the iterator will begin rolling back from the
-    /// second-to-last commit; otherwise, this returns nothing.
+    /// Transactions appended by a [`RollbackIter`] are skipped along with the transactions they
+    /// rolled back, so that repeated calls go further back in the journal instead of rolling back
+    /// the previous rollback. If the latest record isn't a commit or there is no such
+    /// transaction, this returns nothing.
     ///
     /// See [`RollbackIter`].
     #[inline]
     pub fn rollback_last(&mut self) -> Option<RollbackIter<'_, T>> {
+        let idx = self.last_committed()?;
+        Some(RollbackIter::new_idx(self, idx + 1))
+    }
+}
+
+impl<T> Journal<T> {
+    /// Return the reverse index of the commit record of the transaction that
+    /// [`Journal::rollback_last`] would roll back.
+    #[inline]
+    pub fn last_committed(&self) -> Option<usize> {
         match self.latest()? {
-            Record::Commit => Some(RollbackIter::new_idx(self, 1)),
-            _ => None,
+            Record::Commit | Record::RollbackCommit => {}
+            Record::Atom(_) => return None,
+        }
+
+        // Each rollback transaction cancels the latest transaction before it that hasn't already
+        // been cancelled.
+        let mut rolled_back = 0;
+        for (idx, record) in self.records.iter().rev().enumerate() {
+            match record {
+                Record::Atom(_) => {}
+                Record::RollbackCommit => rolled_back += 1,
+                Record::Commit if rolled_back == 0 => return Some(idx),
+                Record::Commit => rolled_back -= 1,
+            }
         }
+
+        None
     }
 }
 
@@ -80,18 +115,18 @@ where
 {
     /// Get a immutable reference to the journal this rollback is operating on.
     #[inline]
-    pub fn journal(&'j mut self) -> &'j Journal<T> {
+    pub fn journal(&self) -> &Journal<T> {
         self.journal
     }
 
     /// Look at the next record and perform the following operations depending on the record type:
     ///
-    /// -   Atom:   get the record's rollback return it. The caller should process the return value
-    ///             and then call [`Self::next_append`] with a datum value.
+    /// -   Atom: get the record's rollback return it. The caller should process the return value
+    ///     and then call [`Self::next_append`] with a datum value.
     ///
     /// -   Commit or no record: if no rollback records have been appended yet, do nothing and
-    ///             return `None`; otherwise, append a commit record to the journal and return
-    ///             `None`.
+    ///     return `None`; otherwise, append a [`Record::RollbackCommit`] record to the journal and
+    ///     return `None`.
     #[inline]
     pub fn next_get(&mut self) -> Option<<T as Rollback>::Output> {
         if self.done {
@@ -105,9 +140,9 @@ where
                 Some(rdata)
             }
             // If reached commit or end, push new commit.
-            Some(Record::Commit) | None => {
+            Some(Record::Commit) | Some(Record::RollbackCommit) | None => {
                 if self.appended {
-                    self.journal.append(Record::Commit);
+                    self.journal.append(Record::RollbackCommit);
                     self.done = true;
                 }
                 None
@@ -127,7 +162,7 @@ where
 
         match self.journal.latest().unwrap_or_else(|| unreachable!()) {
             Record::Atom(datum) => Some(datum),
-            Record::Commit => unreachable!(),
+            Record::Commit | Record::RollbackCommit => unreachable!(),
         }
     }
 }
@@ -148,7 +183,7 @@ where
 
 #[cfg(test)]
 mod test {
-    use super::super::test::{Datum, BACKWARD, COMMIT, FORWARD};
+    use super::super::test::{Datum, BACKWARD, COMMIT, FORWARD, ROLLBACK_COMMIT};
     use super::{Journal, Rollback};
 
     impl Rollback for Datum {
@@ -206,6 +241,8 @@ mod test {
         journal.append(FORWARD);
 
         let mut rollback = journal.rollback();
+        // The uncommitted records should have been committed.
+        records.push(COMMIT);
 
         // Rollback should push a BACKWARD record to the journal on next.
         assert_eq!(Some(&Datum::Backward), rollback.next());
@@ -213,8 +250,8 @@ mod test {
 
         // No more rollback to be done; None.
         assert!(rollback.next().is_none());
-        // COMMIT record should have been pushed.
-        records.push(COMMIT);
+        // ROLLBACK_COMMIT record should have been pushed.
+        records.push(ROLLBACK_COMMIT);
 
         // Same as last
         assert!(rollback.next().is_none());
@@ -250,6 +287,8 @@ mod test {
         journal.append(BACKWARD);
 
         let mut rollback = journal.rollback();
+        // The uncommitted records should have been committed.
+        records.push(COMMIT);
 
         // Rollback should push a FOWARD record to the journal on next.
         assert_eq!(Some(&Datum::Forward), rollback.next());
@@ -261,8 +300,8 @@ mod test {
 
         // No more rollback to be done; None.
         assert!(rollback.next().is_none());
-        // COMMIT record should have been pushed.
-        records.push(COMMIT);
+        // ROLLBACK_COMMIT record should have been pushed.
+        records.push(ROLLBACK_COMMIT);
 
         // Assert that records are correct.
         assert_eq!(&records, journal.records());
@@ -304,10 +343,64 @@ mod test {
 
         // End of transaction; rollback should return none.
         assert!(rollback.next().is_none());
-        // COMMIT record should have been pushed.
-        records.push(COMMIT);
+        // ROLLBACK_COMMIT record should have been pushed.
+        records.push(ROLLBACK_COMMIT);
 
         // Assert that records are correct.
         assert_eq!(&records, journal.records());
     }
+
+    #[test]
+    fn test_rollback_last_repeated() {
+        let mut journal = Journal::new();
+
+        let mut records = vec![FORWARD, COMMIT, BACKWARD, BACKWARD, COMMIT];
+        journal.append(FORWARD);
+        journal.append(COMMIT);
+        journal.append(BACKWARD);
+        journal.append(BACKWARD);
+        journal.append(COMMIT);
+
+        // Rollback the second transaction.
+        let mut rollback = journal.rollback_last().unwrap();
+        assert_eq!(Some(&Datum::Forward), rollback.next());
+        assert_eq!(Some(&Datum::Forward), rollback.next());
+        assert!(rollback.next().is_none());
+        records.extend([FORWARD, FORWARD, ROLLBACK_COMMIT]);
+
+        // The rollback is skipped, so the first transaction is rolled back next.
+        let mut rollback = journal.rollback_last().unwrap();
+        assert_eq!(Some(&Datum::Backward), rollback.next());
+        assert!(rollback.next().is_none());
+        records.extend([BACKWARD, ROLLBACK_COMMIT]);
+        assert_eq!(&records, journal.records());
+
+        // Everything has been rolled back.
+        assert!(journal.rollback_last().is_none());
+
+        // A new transaction is rolled back before anything else.
+        journal.append(FORWARD);
+        journal.append(COMMIT);
+        assert_eq!(Some(0), journal.last_committed());
+        let mut rollback = journal.rollback_last().unwrap();
+        assert_eq!(Some(&Datum::Backward), rollback.next());
+        assert!(rollback.next().is_none());
+        assert!(journal.rollback_last().is_none());
+    }
+
+    #[test]
+    fn test_rollback_last_recovered() {
+        let mut journal = Journal::new();
+        journal.append(FORWARD);
+        journal.append(COMMIT);
+        journal.append(BACKWARD);
+
+        // Recover the interrupted transaction.
+        let mut rollback = journal.rollback();
+        assert_eq!(Some(&Datum::Forward), rollback.next());
+        assert!(rollback.next().is_none());
+
+        // The recovered transaction is skipped.
+        assert_eq!(Some(4), journal.last_committed());
+    }
 }
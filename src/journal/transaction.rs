@@ -4,7 +4,10 @@ impl<T> Journal<T> {
     /// Start a transaction.
     #[inline]
     pub fn lock(&mut self) -> Transaction<'_, T> {
-        Transaction { journal: self }
+        Transaction {
+            journal: self,
+            appended: false,
+        }
     }
 }
 
@@ -12,10 +15,13 @@ impl<T> Journal<T> {
 ///
 /// # Drop
 ///
-/// When dropped, the transaction is committed.
+/// When dropped, the transaction is committed. If no records were appended, nothing is committed.
 #[derive(Debug)]
 pub struct Transaction<'j, T> {
     journal: &'j mut Journal<T>,
+
+    /// Flag that indicates whether or not any records were appended.
+    appended: bool,
 }
 
 impl<'j, T> Transaction<'j, T> {
@@ -28,7 +34,8 @@ impl<'j, T> Transaction<'j, T> {
     /// Append a new [`Record::Atom`] record to the journal.
     #[inline]
     pub fn append(&mut self, datum: T) {
-        self.journal.append(Record::Atom(datum))
+        self.journal.append(Record::Atom(datum));
+        self.appended = true;
     }
 }
 
@@ -36,13 +43,15 @@ impl<'j, T> Drop for Transaction<'j, T> {
     /// Commit the transaction on drop.
     #[inline]
     fn drop(&mut self) {
-        self.journal.append(Record::Commit);
+        if self.appended {
+            self.journal.append(Record::Commit);
+        }
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::super::test::{Datum::*, COMMIT, FORWARD};
+    use super::super::test::{Datum, Datum::*, COMMIT, FORWARD};
     use super::Journal;
 
     #[test]
@@ -73,4 +82,15 @@ mod test {
 
         assert_eq!(&[FORWARD, FORWARD, COMMIT], journal.records());
     }
+
+    #[test]
+    fn test_drop_empty() {
+        let mut journal: Journal<Datum> = Journal::new();
+
+        {
+            let _t = journal.lock();
+        }
+
+        assert!(journal.is_empty());
+    }
 }
@@ -16,6 +16,8 @@ impl<T> Journal<T>
 where
     T: Serialize,
 {
+    /// Write the records, starting from the record at index `start`, to `w`. Each record is
+    /// serialized as a single line of JSON.
     #[inline]
     pub fn write<W>(&self, w: W, start: usize) -> Result<(), WriteError>
     where
@@ -38,6 +40,7 @@ where
             }
         }
 
+        w.flush()?;
         Ok(())
     }
 }
@@ -54,6 +57,7 @@ impl<T> Journal<T>
 where
     T: DeserializeOwned,
 {
+    /// Read a journal previously written with [`Journal::write`] from `r`.
     #[inline]
     pub fn load<R>(r: R) -> Result<Self, ReadError>
     where
@@ -77,7 +81,7 @@ where
     T: Serialize,
     W: Write,
 {
-    let _ = serde_json::to_writer(&mut w, record)?;
+    serde_json::to_writer(&mut w, record)?;
     w.write_all(b"\n")?;
     Ok(())
 }
@@ -90,3 +94,42 @@ where
     let record = serde_json::from_str(line)?;
     Ok(record)
 }
+
+#[cfg(test)]
+mod test {
+    use super::super::test::{Datum, BACKWARD, COMMIT, FORWARD};
+    use super::Journal;
+
+    #[test]
+    fn test_write_load() {
+        let mut journal = Journal::new();
+        journal.append(FORWARD);
+        journal.append(BACKWARD);
+        journal.append(COMMIT);
+
+        let mut buf = Vec::new();
+        journal.write(&mut buf, 0).unwrap();
+
+        let loaded: Journal<Datum> = Journal::load(&buf[..]).unwrap();
+        assert_eq!(journal.records(), loaded.records());
+    }
+
+    #[test]
+    fn test_write_incremental() {
+        let mut journal = Journal::new();
+        let mut buf = Vec::new();
+
+        journal.append(FORWARD);
+        journal.write(&mut buf, 0).unwrap();
+
+        journal.append(BACKWARD);
+        journal.append(COMMIT);
+        journal.write(&mut buf, 1).unwrap();
+
+        // Writing from the end should do nothing.
+        journal.write(&mut buf, 3).unwrap();
+
+        let loaded: Journal<Datum> = Journal::load(&buf[..]).unwrap();
+        assert_eq!(&[FORWARD, BACKWARD, COMMIT], loaded.records());
+    }
+}
@@ -1,3 +1,5 @@
+#![allow(clippy::result_large_err)]
+
 pub mod action;
 pub mod graph;
 pub mod load;
@@ -5,5 +7,6 @@ pub mod spec;
 
 pub mod journal;
 pub mod op;
+pub mod state;
 
 pub mod fse;
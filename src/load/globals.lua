@@ -5,6 +5,22 @@ function name(value)
     pkg:name(value)
 end
 
+-- override(10)
+
+-- selene: allow(unused_variable)
+function override(priority)
+    pkg:override(priority)
+end
+
+-- array {}
+-- array {'a', 'b'}
+-- json {'s.json', {plugins = array {}}}
+
+-- selene: allow(unused_variable)
+function array(values)
+    return pkg:array(values)
+end
+
 -- dep 'path'
 -- dep {'path', ...}
 -- dep 'path1' 'path2' ...
@@ -22,25 +38,46 @@ end
 -- file {'e.txt', 'f.txt', type = 'copy'}
 -- file {'g.txt', type = 'copy'}
 -- file {'h.txt', optional = true}
+-- file {'i.txt', overwrite = 'backup'}
+-- file {'j.txt', overwrite = 'adopt'}
+-- file {'k.txt', type = 'copy', mode = '0600'}
+-- file {'l.txt', type = 'copy', owner = 'root', group = 'wheel'}
+-- file {'m.txt', relative = true}
+-- file {'n.txt', type = 'hardlink'}
+-- file {'dir', type = 'copy', prune = true}
+--
+-- mode, owner and group can only be set for copies, since links share them with the source.
 
 -- selene: allow(unused_variable)
 function file(arg)
-    local src, dest, link_type, optional
+    local src, dest, link_type, relative, prune, optional, overwrite, mode, owner, group
     if type(arg) == 'string' then
         src = arg
         dest = nil
         link_type = nil
+        relative = nil
+        prune = nil
         optional = nil
+        overwrite = nil
+        mode = nil
+        owner = nil
+        group = nil
     elseif type(arg) == 'table' then
         src = arg[1] or error 'file src path was not provided'
         dest = arg[2]
         link_type = arg.type
+        relative = arg.relative
+        prune = arg.prune
         optional = arg.optional
+        overwrite = arg.overwrite
+        mode = arg.mode
+        owner = arg.owner
+        group = arg.group
     else
         error 'invalid file directive'
     end
 
-    pkg:file(src, dest, link_type, optional)
+    pkg:file(src, dest, link_type, relative, prune, optional, overwrite, mode, owner, group)
 end
 
 -- selene: allow(unused_variable)
@@ -70,24 +107,51 @@ end
 -- tree {'tree', '.config', ignore = '**/*.log'}
 -- tree {'tree', '.config', type = 'copy', ignore = '**/*.log'}
 -- tree {'tree', optional = true}
+-- tree {'tree', overwrite = 'ask'}
+-- tree {'tree', overwrite = 'adopt'}
+-- tree {'tree', type = 'copy', mode = '0600'}
+-- tree {'tree', type = 'copy', owner = 'root', group = 'wheel'}
+-- tree {'tree', relative = true}
+-- tree {'tree', type = 'hardlink'}
+-- tree {'tree', type = 'copy', prune = true}
+-- tree {'tree', gitignore = true}
+-- tree {'tree', fold = true}
+--
+-- mode, owner and group can only be set for copies, as with file.
 
 -- selene: allow(unused_variable)
 function tree(arg)
-    local src, dest, link_type, globs, ignore, optional
+    local src, dest, link_type, relative, prune, globs, ignore, gitignore, fold, optional, overwrite, mode, owner, group
     if type(arg) == 'string' then
         src = arg
         dest = nil
         link_type = nil
+        relative = nil
+        prune = nil
         globs = nil
         ignore = nil
+        gitignore = nil
+        fold = nil
         optional = nil
+        overwrite = nil
+        mode = nil
+        owner = nil
+        group = nil
     elseif type(arg) == 'table' then
         src = arg[1] or error 'tree src path was not provided'
         dest = arg[2]
         link_type = arg.type
+        relative = arg.relative
+        prune = arg.prune
         globs = arg.globs
         ignore = arg.ignore
+        gitignore = arg.gitignore
+        fold = arg.fold
         optional = arg.optional
+        overwrite = arg.overwrite
+        mode = arg.mode
+        owner = arg.owner
+        group = arg.group
 
         if type(globs) == 'string' then
             globs = { globs }
@@ -99,7 +163,7 @@ function tree(arg)
         error 'tree arg must be a string or table'
     end
 
-    pkg:tree(src, dest, link_type, globs, ignore, optional)
+    pkg:tree(src, dest, link_type, relative, prune, globs, ignore, gitignore, fold, optional, overwrite, mode, owner, group)
 end
 
 -- template {'d.hbs', 'j.txt', engine = 'handlebars', vars = {}}
@@ -124,6 +188,9 @@ end
 
 -- hbs {'b.hbs', 'h.txt', vars = {}}
 -- hbs {'b.hbs', 'h.txt', vars = {}, optional = true}
+-- hbs {'b.hbs', 'h.txt', vars = {}, overwrite = 'skip'}
+-- hbs {'b.hbs', 'h.txt', vars = {}, mode = '0600'}
+-- hbs {'b.hbs', 'h.txt', vars = {}, owner = 'root', group = 'wheel'}
 
 -- selene: allow(unused_variable)
 function hbs(arg)
@@ -132,12 +199,19 @@ function hbs(arg)
     local vars = arg.vars or error 'template vars was not provided'
     local partials = arg.partials or {}
     local optional = arg.optional
+    local overwrite = arg.overwrite
+    local mode = arg.mode
+    local owner = arg.owner
+    local group = arg.group
 
-    pkg:hbs(src, dest, vars, partials, optional)
+    pkg:hbs(src, dest, vars, partials, optional, overwrite, mode, owner, group)
 end
 
 -- liquid {'b.tmpl', 'i.txt', vars = {}}
 -- liquid {'b.tmpl', 'i.txt', vars = {}, optional = true}
+-- liquid {'b.tmpl', 'i.txt', vars = {}, overwrite = 'skip'}
+-- liquid {'b.tmpl', 'i.txt', vars = {}, mode = '0600'}
+-- liquid {'b.tmpl', 'i.txt', vars = {}, owner = 'root', group = 'wheel'}
 
 -- selene: allow(unused_variable)
 function liquid(arg)
@@ -145,12 +219,19 @@ function liquid(arg)
     local dest = arg[2] or error 'template dest was not provided'
     local vars = arg.vars or error 'template vars was not provided'
     local optional = arg.optional
+    local overwrite = arg.overwrite
+    local mode = arg.mode
+    local owner = arg.owner
+    local group = arg.group
 
-    pkg:liquid(src, dest, vars, optional)
+    pkg:liquid(src, dest, vars, optional, overwrite, mode, owner, group)
 end
 
 -- empty 'l.txt'
 -- empty {'m.txt'}
+-- empty {'m.txt', overwrite = 'error'}
+-- empty {'m.txt', mode = '0600'}
+-- empty {'m.txt', owner = 'root', group = 'wheel'}
 
 -- selene: allow(unused_variable)
 function empty(arg)
@@ -158,27 +239,42 @@ function empty(arg)
         pkg:empty(arg)
     elseif type(arg) == 'table' then
         local path = arg[1] or error 'empty dest was not provided'
-        pkg:empty(path)
+        pkg:empty(path, arg.overwrite, arg.mode, arg.owner, arg.group)
     else
         error 'empty dest must be a string or table'
     end
 end
 
 -- string {'n.txt', 'contents'}
+-- string {'n.txt', 'contents', mode = '0600'}
+-- string {'n.txt', 'contents', owner = 'root', group = 'wheel'}
 
 -- selene: allow(unused_variable)
 function str(arg)
     if type(arg) == 'table' then
         local dest = arg[1] or error 'str dest was not provided'
         local contents = arg[2] or error 'str contents was not provided'
-        pkg:str(dest, contents)
+        pkg:str(dest, contents, arg.overwrite, arg.mode, arg.owner, arg.group)
     else
         error 'str arg must be a table'
     end
 end
 
+-- The array merge strategy of a generated file, or nil if it isn't merged. Merging rewrites the
+-- existing file, so its comments and formatting are not preserved.
+local function merge_arg(arg)
+    if arg.merge then
+        return arg.arrays or 'replace'
+    end
+    return nil
+end
+
 -- yaml {'o.txt', {}}
 -- yaml {'p.txt', {}, header = '# header'}
+-- yaml {'p.txt', {}, mode = '0600'}
+-- yaml {'p.txt', {}, owner = 'root', group = 'wheel'}
+-- yaml {'p.txt', {}, merge = true}
+-- yaml {'p.txt', {}, merge = true, arrays = 'union'}
 
 -- selene: allow(unused_variable)
 function yaml(arg)
@@ -186,7 +282,8 @@ function yaml(arg)
         local dest = arg[1] or error 'yaml dest was not provided'
         local values = arg[2] or error 'yaml values were not provided'
         local header = arg.header
-        pkg:yaml(dest, values, header)
+        local merge = merge_arg(arg)
+        pkg:yaml(dest, values, header, merge, arg.overwrite, arg.mode, arg.owner, arg.group)
     else
         error 'yaml arg must be a table'
     end
@@ -194,6 +291,10 @@ end
 
 -- toml {'q.txt', {}}
 -- toml {'r.txt', {}, header = '# header'}
+-- toml {'r.txt', {}, mode = '0600'}
+-- toml {'r.txt', {}, owner = 'root', group = 'wheel'}
+-- toml {'r.txt', {}, merge = true}
+-- toml {'r.txt', {}, merge = true, arrays = 'append'}
 
 -- selene: allow(unused_variable)
 function toml(arg)
@@ -201,34 +302,96 @@ function toml(arg)
         local dest = arg[1] or error 'toml dest was not provided'
         local values = arg[2] or error 'toml values were not provided'
         local header = arg.header
-        pkg:toml(dest, values, header)
+        local merge = merge_arg(arg)
+        pkg:toml(dest, values, header, merge, arg.overwrite, arg.mode, arg.owner, arg.group)
     else
         error 'toml arg must be a table'
     end
 end
 
 -- json {'s.txt', {}}
+-- json {'s.txt', {}, mode = '0600'}
+-- json {'s.txt', {}, owner = 'root', group = 'wheel'}
+-- json {'s.txt', {}, merge = true}
+-- json {'s.txt', {}, merge = true, arrays = 'union'}
+--
+-- The existing file may have comments and trailing commas, as in VS Code's settings.json; they
+-- are dropped when it is merged into.
 
 -- selene: allow(unused_variable)
 function json(arg)
     if type(arg) == 'table' then
         local dest = arg[1] or error 'toml dest was not provided'
         local values = arg[2] or error 'toml values were not provided'
-        pkg:json(dest, values)
+        local merge = merge_arg(arg)
+        pkg:json(dest, values, merge, arg.overwrite, arg.mode, arg.owner, arg.group)
     else
         error 'json arg must be a table'
     end
 end
 
+-- ini {'t.txt', {}}
+-- ini {'u.txt', {}, header = '# header'}
+-- ini {'u.txt', {}, mode = '0600'}
+-- ini {'u.txt', {}, owner = 'root', group = 'wheel'}
+
+-- selene: allow(unused_variable)
+function ini(arg)
+    if type(arg) == 'table' then
+        local dest = arg[1] or error 'ini dest was not provided'
+        local values = arg[2] or error 'ini values were not provided'
+        local header = arg.header
+        pkg:ini(dest, values, header, arg.overwrite, arg.mode, arg.owner, arg.group)
+    else
+        error 'ini arg must be a table'
+    end
+end
+
+-- env {'v.txt', {}}
+-- env {'w.txt', {}, header = '# header'}
+-- env {'w.txt', {}, mode = '0600'}
+-- env {'w.txt', {}, owner = 'root', group = 'wheel'}
+
+-- selene: allow(unused_variable)
+function env(arg)
+    if type(arg) == 'table' then
+        local dest = arg[1] or error 'env dest was not provided'
+        local values = arg[2] or error 'env values were not provided'
+        local header = arg.header
+        pkg:env(dest, values, header, arg.overwrite, arg.mode, arg.owner, arg.group)
+    else
+        error 'env arg must be a table'
+    end
+end
+
+-- block {'.bashrc', [[alias ll='ls -l']]}
+-- block {'.bashrc', [[alias ll='ls -l']], marker = 'aliases'}
+-- block {'.vimrc', [[set number]], comment = '"'}
+-- block {'.bashrc', [[alias ll='ls -l']], mode = '0600'}
+-- block {'.bashrc', [[alias ll='ls -l']], owner = 'root', group = 'wheel'}
+
+-- selene: allow(unused_variable)
+function block(arg)
+    if type(arg) == 'table' then
+        local dest = arg[1] or error 'block dest was not provided'
+        local contents = arg[2] or error 'block contents were not provided'
+        pkg:block(dest, contents, arg.marker, arg.comment, arg.mode, arg.owner, arg.group)
+    else
+        error 'block arg must be a table'
+    end
+end
+
 -- mkdir 'd'
 -- mkdir {'d'}
+-- mkdir {'d', parents = true, mode = '0700'}
+-- mkdir {'d', parents = true, owner = 'root', group = 'wheel'}
 
 -- selene: allow(unused_variable)
 function mkdir(arg)
     if type(arg) == 'table' then
         local dest = arg[1] or error 'mkdir dest was not provided'
         local parents = arg.parents or error 'mkdir parents was not provided'
-        pkg:mkdir(dest, parents)
+        pkg:mkdir(dest, parents, arg.mode, arg.owner, arg.group)
     else
         pkg:mkdir(arg, true)
     end
@@ -1,16 +1,18 @@
 use std::collections::HashMap;
 
 use mlua::{
-    Error as LuaError, FromLua, Function, UserData, UserDataMethods, Value as LuaValue, Variadic,
+    Error as LuaError, FromLua, Function, Lua, LuaSerdeExt, Table, UserData, UserDataMethods,
+    Value as LuaValue, Variadic,
 };
 use uuid::Uuid;
 
+use crate::action::owner::{self, LookupError};
 use crate::spec::{
-    CmdHook, Dep, DirFile, Directive, EmptyGeneratedFile, File, FunHook, GeneratedFile,
-    GeneratedFileTyp, HandlebarsTemplatedFile, Hook, JsonGeneratedFile, LinkType,
-    LiquidTemplatedFile, NonZeroExitBehavior, Object, ObjectValue, Patterns, RegularFile, Spec,
-    StringGeneratedFile, TemplatedFile, TemplatedFileType, TomlGeneratedFile, TreeFile,
-    YamlGeneratedFile,
+    ArrayMerge, BlockFile, CmdHook, Dep, DirFile, Directive, EmptyGeneratedFile, EnvGeneratedFile,
+    File, FunHook, GeneratedFile, GeneratedFileTyp, HandlebarsTemplatedFile, Hook,
+    IniGeneratedFile, JsonGeneratedFile, LinkType, LiquidTemplatedFile, Mode, NonZeroExitBehavior,
+    Object, ObjectValue, Overwrite, Owner, Patterns, RegularFile, Spec, StringGeneratedFile,
+    TemplatedFile, TemplatedFileType, TomlGeneratedFile, TreeFile, YamlGeneratedFile,
 };
 
 pub trait SpecLoaderState {}
@@ -27,6 +29,7 @@ impl SpecObject {
             spec: Spec {
                 name: String::new(),
                 deps: Vec::new(),
+                priority: None,
                 directives: Vec::new(),
             },
         }
@@ -71,26 +74,59 @@ impl UserData for SpecObject {
             Ok(())
         });
 
-        method!("file"; (src; String, dest; Option<String>, link_type; Option<LinkType>, optional; Option<bool>);
+        methods.add_method_mut("override", |_, this, priority: u32| {
+            this.spec.priority = Some(priority);
+            Ok(())
+        });
+
+        // Mark a table as an array, so that it's converted to one even if it's empty.
+        methods.add_method("array", |lua, _, table: Option<Table>| {
+            let table = match table {
+                Some(table) => table,
+                None => lua.create_table()?,
+            };
+            table.set_metatable(Some(lua.array_metatable()));
+            Ok(table)
+        });
+
+        method!("file"; (src; String, dest; Option<String>, link_type; Option<LinkType>, relative; Option<bool>, prune; Option<bool>,
+                         optional; Option<bool>, overwrite; Option<Overwrite>, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         File; File::Regular(RegularFile {
             src: src.into(),
             dest: dest.map(Into::into),
             link_type: link_type.unwrap_or(LinkType::Link),
-            optional: optional.unwrap_or(false)
+            relative,
+            prune: prune.unwrap_or(false),
+            optional: optional.unwrap_or(false),
+            overwrite,
+            mode,
+            owner: get_owner(owner, group)?
         }));
 
-        method!("tree"; (src; String, dest; Option<String>, link_type; Option<LinkType>,
-                         globs; Option<Patterns>, ignore; Option<Patterns>, optional; Option<bool>);
+        method!("tree"; (src; String, dest; Option<String>, link_type; Option<LinkType>, relative; Option<bool>, prune; Option<bool>,
+                         globs; Option<Patterns>, ignore; Option<Patterns>, gitignore; Option<bool>, fold; Option<bool>, optional; Option<bool>,
+                         overwrite; Option<Overwrite>, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         File; File::Tree(TreeFile {
             src: src.into(),
             dest: dest.map(Into::into),
             globs,
             ignore,
+            gitignore: gitignore.unwrap_or(false),
+            fold: fold.unwrap_or(false),
             link_type: link_type.unwrap_or(LinkType::Link),
-            optional: optional.unwrap_or(false)
+            relative,
+            prune: prune.unwrap_or(false),
+            optional: optional.unwrap_or(false),
+            overwrite,
+            mode,
+            owner: get_owner(owner, group)?
         }));
 
-        method!("hbs"; (src; String, dest; String, vars; Object, partials; HashMap<String, String>, optional; Option<bool>);
+        method!("hbs"; (src; String, dest; String, vars; Object, partials; HashMap<String, String>, optional; Option<bool>,
+                        overwrite; Option<Overwrite>, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         File; {
             let partials = partials.into_iter().map(|(k, v)| (k, v.into())).collect();
             File::Templated(TemplatedFile {
@@ -98,44 +134,97 @@ impl UserData for SpecObject {
                 dest: dest.into(),
                 vars,
                 typ: TemplatedFileType::Handlebars(HandlebarsTemplatedFile { partials }),
-                optional: optional.unwrap_or(false)
+                optional: optional.unwrap_or(false),
+                overwrite,
+                mode,
+                owner: get_owner(owner, group)?
             })
         });
 
-        method!("liquid"; (src; String, dest; String, vars; Object, optional; Option<bool>);
+        method!("liquid"; (src; String, dest; String, vars; Object, optional; Option<bool>,
+                           overwrite; Option<Overwrite>, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         File; File::Templated(TemplatedFile {
             src: src.into(),
             dest: dest.into(),
             vars,
             typ: TemplatedFileType::Liquid(LiquidTemplatedFile {}),
-            optional: optional.unwrap_or(false)
+            optional: optional.unwrap_or(false),
+            overwrite,
+            mode,
+            owner: get_owner(owner, group)?
         }));
 
-        method!("empty"; (dest; String);
+        method!("empty"; (dest; String, overwrite; Option<Overwrite>, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
+        Gen; GeneratedFile {
+            dest: dest.into(), typ: GeneratedFileTyp::Empty(EmptyGeneratedFile), overwrite, mode,
+            owner: get_owner(owner, group)?
+        });
+        method!("str"; (dest; String, contents; String, overwrite; Option<Overwrite>, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
+        Gen; GeneratedFile {
+            dest: dest.into(), typ: GeneratedFileTyp::String(StringGeneratedFile { contents }), overwrite, mode,
+            owner: get_owner(owner, group)?
+        });
+        method!("yaml"; (dest; String, values; Object, header; Option<String>, merge; Option<ArrayMerge>,
+                         overwrite; Option<Overwrite>,
+                         mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         Gen; GeneratedFile {
-            dest: dest.into(), typ: GeneratedFileTyp::Empty(EmptyGeneratedFile)
+            dest: dest.into(), typ: GeneratedFileTyp::Yaml(YamlGeneratedFile { values, header, merge }), overwrite, mode,
+            owner: get_owner(owner, group)?
         });
-        method!("str"; (dest; String, contents; String);
+        method!("toml"; (dest; String, values; Object, header; Option<String>, merge; Option<ArrayMerge>,
+                         overwrite; Option<Overwrite>,
+                         mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         Gen; GeneratedFile {
-            dest: dest.into(), typ: GeneratedFileTyp::String(StringGeneratedFile { contents })
+            dest: dest.into(), typ: GeneratedFileTyp::Toml(TomlGeneratedFile { values, header, merge }), overwrite, mode,
+            owner: get_owner(owner, group)?
         });
-        method!("yaml"; (dest; String, values; Object, header; Option<String>);
+        method!("json"; (dest; String, values; Object, merge; Option<ArrayMerge>,
+                         overwrite; Option<Overwrite>, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         Gen; GeneratedFile {
-            dest: dest.into(), typ: GeneratedFileTyp::Yaml(YamlGeneratedFile { values, header })
+            dest: dest.into(), typ: GeneratedFileTyp::Json(JsonGeneratedFile { values, merge }), overwrite, mode,
+            owner: get_owner(owner, group)?
         });
-        method!("toml"; (dest; String, values; Object, header; Option<String>);
+
+        method!("ini"; (dest; String, values; Object, header; Option<String>, overwrite; Option<Overwrite>,
+                        mode; Option<Mode>,
+                        owner; Option<String>, group; Option<String>);
         Gen; GeneratedFile {
-            dest: dest.into(), typ: GeneratedFileTyp::Toml(TomlGeneratedFile { values, header })
+            dest: dest.into(), typ: GeneratedFileTyp::Ini(IniGeneratedFile { values, header }), overwrite, mode,
+            owner: get_owner(owner, group)?
         });
-        method!("json"; (dest; String, values; Object);
+        method!("env"; (dest; String, values; Object, header; Option<String>, overwrite; Option<Overwrite>,
+                        mode; Option<Mode>,
+                        owner; Option<String>, group; Option<String>);
         Gen; GeneratedFile {
-            dest: dest.into(), typ: GeneratedFileTyp::Json(JsonGeneratedFile { values })
+            dest: dest.into(), typ: GeneratedFileTyp::Env(EnvGeneratedFile { values, header }), overwrite, mode,
+            owner: get_owner(owner, group)?
         });
 
-        method!("mkdir"; (dest; String, parents; bool);
+        method!("block"; (dest; String, contents; String, marker; Option<String>, comment; Option<String>,
+                          mode; Option<Mode>,
+                          owner; Option<String>, group; Option<String>);
+        File; File::Block(BlockFile {
+            dest: dest.into(),
+            contents,
+            marker,
+            comment,
+            mode,
+            owner: get_owner(owner, group)?,
+        }));
+
+        method!("mkdir"; (dest; String, parents; bool, mode; Option<Mode>,
+                         owner; Option<String>, group; Option<String>);
         File; File::Dir(DirFile {
             dest: dest.into(),
             parents,
+            mode,
+            owner: get_owner(owner, group)?,
         }));
 
         method!("cmd"; (command; String, start; Option<String>, shell; Option<String>,
@@ -175,6 +264,18 @@ impl UserData for SpecObject {
     }
 }
 
+/// Look up the owner and group given by name or numeric id.
+#[inline]
+fn get_owner(user: Option<String>, group: Option<String>) -> mlua::Result<Owner> {
+    let lookup = || -> Result<Owner, LookupError> {
+        Ok(Owner {
+            uid: user.as_deref().map(owner::user_id).transpose()?,
+            gid: group.as_deref().map(owner::group_id).transpose()?,
+        })
+    };
+    lookup().map_err(LuaError::external)
+}
+
 impl<'lua> FromLua<'lua> for ObjectValue {
     #[inline]
     fn from_lua(lua_value: LuaValue<'lua>, lua: &'lua mlua::Lua) -> mlua::Result<Self> {
@@ -184,6 +285,11 @@ impl<'lua> FromLua<'lua> for ObjectValue {
             LuaValue::Integer(i) => ObjectValue::Int(i),
             LuaValue::Number(n) => ObjectValue::Float(n),
             LuaValue::String(s) => ObjectValue::Str(s.to_str()?.to_string()),
+            // Tables with only the keys 1..n are sequences. Empty tables are objects, unless they
+            // were marked as arrays.
+            LuaValue::Table(t) if is_sequence(lua, &t)? => {
+                ObjectValue::Array(FromLua::from_lua(LuaValue::Table(t), lua)?)
+            }
             LuaValue::Table(t) => ObjectValue::Object(FromLua::from_lua(LuaValue::Table(t), lua)?),
             LuaValue::Function(_)
             | LuaValue::Thread(_)
@@ -204,6 +310,30 @@ impl<'lua> FromLua<'lua> for ObjectValue {
     }
 }
 
+/// Return true if `table` is an array, i.e. it was marked as one by `array` or it's a non-empty
+/// sequence whose keys are exactly 1..n.
+#[inline]
+fn is_sequence(lua: &Lua, table: &Table<'_>) -> mlua::Result<bool> {
+    if table.get_metatable() == Some(lua.array_metatable()) {
+        return Ok(true);
+    }
+
+    let len = table.raw_len();
+    if len == 0 {
+        return Ok(false);
+    }
+
+    let mut count = 0;
+    for pair in table.clone().pairs::<LuaValue, LuaValue>() {
+        match pair?.0 {
+            LuaValue::Integer(i) if 1 <= i && i <= len => count += 1,
+            _ => return Ok(false),
+        }
+    }
+
+    Ok(count == len)
+}
+
 impl<'lua> FromLua<'lua> for Object {
     #[inline]
     fn from_lua(lua_value: LuaValue<'lua>, lua: &'lua mlua::Lua) -> mlua::Result<Self> {
@@ -217,3 +347,39 @@ impl<'lua> FromLua<'lua> for Object {
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use mlua::Lua;
+
+    use super::SpecObject;
+    use crate::spec::ObjectValue;
+
+    #[test]
+    fn test_array() -> mlua::Result<()> {
+        let lua = Lua::new();
+        lua.globals().set("pkg", SpecObject::new())?;
+        let eval = |chunk: &str| lua.load(chunk).eval::<ObjectValue>();
+
+        assert!(matches!(
+            eval("return {'a', 2}")?,
+            ObjectValue::Array(values) if values.len() == 2
+        ));
+        assert!(matches!(
+            eval("return {'a', b = 2}")?,
+            ObjectValue::Object(values) if values.len() == 2
+        ));
+
+        // Empty tables are objects, unless they're marked as arrays.
+        assert!(matches!(eval("return {}")?, ObjectValue::Object(values) if values.is_empty()));
+        assert!(
+            matches!(eval("return pkg:array()")?, ObjectValue::Array(values) if values.is_empty())
+        );
+        assert!(matches!(
+            eval("return pkg:array {'a'}")?,
+            ObjectValue::Array(values) if values.len() == 1
+        ));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,228 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use static_assertions as sa;
+
+use super::ctx::FinishCtx;
+use super::error::{MetadataError, PermissionsError};
+use super::{Finish, Rollback};
+
+sa::assert_impl_all!(ChmodOp: Finish<Output = ChmodFinish, Error = ChmodOpError>);
+sa::assert_impl_all!(ChmodFinish: Rollback<Output = ChmodUndoOp>);
+sa::assert_impl_all!(ChmodUndoOp: Finish<Output = ChmodUndoFinish, Error = ChmodUndoOpError>);
+sa::assert_impl_all!(ChmodUndoFinish: Rollback<Output = ChmodOp>);
+
+/// Error encountered when finishing [`ChmodOp`].
+#[derive(Debug, thiserror::Error)]
+pub enum ChmodOpError {
+    #[error("metadata error")]
+    Metadata(#[from] MetadataError),
+    #[error("permissions error")]
+    Permissions(#[from] PermissionsError),
+}
+
+/// Operation to set the permission bits of the file at `path` to `mode`. Symlinks are followed.
+///
+/// # Errors
+///
+/// The operation will error if `path` does not exist or its permissions cannot be changed.
+///
+/// # Undo
+///
+/// Undoing will restore the permission bits that the file had before. This set of operations
+/// functions in the following cycle:
+///
+/// [`ChmodOp`] --> [`ChmodFinish`] --> [`ChmodUndoOp`] --> [`ChmodUndoFinish`] --> [`ChmodOp`] --> ...
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChmodOp {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Permission bits to set.
+    pub mode: u32,
+}
+
+/// The output of [`ChmodOp`]. See its documentation for information.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChmodFinish {
+    /// See [`ChmodOp`].
+    pub path: PathBuf,
+    /// See [`ChmodOp`].
+    pub mode: u32,
+    /// Permission bits of the file before the operation.
+    pub prev: u32,
+}
+
+impl Finish for ChmodOp {
+    type Output = ChmodFinish;
+    type Error = ChmodOpError;
+
+    #[inline]
+    fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
+        let Self { path, mode } = self;
+
+        let prev = chmod::<Self::Error>(path, *mode)?;
+        Ok(Self::Output {
+            path: path.clone(),
+            mode: *mode,
+            prev,
+        })
+    }
+}
+
+impl Rollback for ChmodFinish {
+    type Output = ChmodUndoOp;
+
+    #[inline]
+    fn rollback(&self) -> Self::Output {
+        let Self { path, mode, prev } = self;
+
+        Self::Output {
+            path: path.clone(),
+            mode: *mode,
+            prev: *prev,
+        }
+    }
+}
+
+/// Error encountered when finishing [`ChmodUndoOp`].
+#[derive(Debug, thiserror::Error)]
+pub enum ChmodUndoOpError {
+    #[error("metadata error")]
+    Metadata(#[from] MetadataError),
+    #[error("permissions error")]
+    Permissions(#[from] PermissionsError),
+}
+
+/// The undo of [`ChmodOp`] (see its documentation), created by rolling back [`ChmodFinish`].
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChmodUndoOp {
+    /// See [`ChmodOp`].
+    pub path: PathBuf,
+    /// See [`ChmodOp`].
+    pub mode: u32,
+    /// See [`ChmodFinish`].
+    pub prev: u32,
+}
+
+/// The output of [`ChmodUndoOp`]. See its documentation for information.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChmodUndoFinish {
+    /// See [`ChmodOp`].
+    pub path: PathBuf,
+    /// See [`ChmodOp`].
+    pub mode: u32,
+    /// See [`ChmodFinish`].
+    pub prev: u32,
+}
+
+impl Finish for ChmodUndoOp {
+    type Output = ChmodUndoFinish;
+    type Error = ChmodUndoOpError;
+
+    #[inline]
+    fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
+        let Self { path, mode, prev } = self;
+
+        chmod::<Self::Error>(path, *prev)?;
+        Ok(Self::Output {
+            path: path.clone(),
+            mode: *mode,
+            prev: *prev,
+        })
+    }
+}
+
+impl Rollback for ChmodUndoFinish {
+    type Output = ChmodOp;
+
+    #[inline]
+    fn rollback(&self) -> Self::Output {
+        let Self { path, mode, .. } = self;
+
+        Self::Output {
+            path: path.clone(),
+            mode: *mode,
+        }
+    }
+}
+
+/// Set the permission bits of `path` to `mode`, returning the previous permission bits.
+#[cfg(unix)]
+#[inline]
+fn chmod<E>(path: &Path, mode: u32) -> Result<u32, E>
+where
+    E: From<MetadataError> + From<PermissionsError>,
+{
+    use std::fs::{self, Permissions};
+    use std::os::unix::fs::PermissionsExt;
+
+    let meta = fs::metadata(path).map_err(|inner| MetadataError {
+        path: path.to_path_buf(),
+        inner,
+    })?;
+    let prev = meta.permissions().mode() & 0o7777;
+
+    fs::set_permissions(path, Permissions::from_mode(mode)).map_err(|inner| PermissionsError {
+        path: path.to_path_buf(),
+        inner,
+    })?;
+
+    Ok(prev)
+}
+
+/// Set the permission bits of `path` to `mode`. Permission bits aren't supported on this
+/// platform, so this always fails.
+#[cfg(not(unix))]
+#[inline]
+fn chmod<E>(path: &Path, _mode: u32) -> Result<u32, E>
+where
+    E: From<MetadataError> + From<PermissionsError>,
+{
+    use std::io;
+
+    Err(PermissionsError {
+        path: path.to_path_buf(),
+        inner: io::Error::new(
+            io::ErrorKind::Unsupported,
+            "permission bits are not supported on this platform",
+        ),
+    }
+    .into())
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::PermissionsExt;
+
+    use super::super::test;
+    use super::{ChmodOp, Finish, Rollback};
+
+    #[test]
+    fn test_chmod() -> test::Result<()> {
+        test::with_tempdir(|dir, ctx| {
+            let (file, path) = test::new_file(dir, "a")?;
+            file.set_permissions(fs::Permissions::from_mode(0o644))?;
+            let mode =
+                || -> test::Result<u32> { Ok(fs::metadata(&path)?.permissions().mode() & 0o7777) };
+
+            let op = ChmodOp {
+                path: path.clone(),
+                mode: 0o600,
+            };
+
+            let opf = op.finish(ctx)?;
+            assert_eq!(opf.prev, 0o644);
+            assert_eq!(mode()?, 0o600);
+
+            let undo = opf.rollback();
+            let undof = undo.finish(ctx)?;
+            assert_eq!(mode()?, 0o644);
+
+            let op2 = undof.rollback();
+            assert_eq!(op, op2);
+
+            Ok(())
+        })
+    }
+}
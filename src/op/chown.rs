@@ -0,0 +1,292 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use static_assertions as sa;
+
+use super::ctx::FinishCtx;
+use super::error::{ChownError, ChownPermissionError, MetadataError};
+use super::{Finish, Rollback};
+
+sa::assert_impl_all!(ChownOp: Finish<Output = ChownFinish, Error = ChownOpError>);
+sa::assert_impl_all!(ChownFinish: Rollback<Output = ChownUndoOp>);
+sa::assert_impl_all!(ChownUndoOp: Finish<Output = ChownUndoFinish, Error = ChownUndoOpError>);
+sa::assert_impl_all!(ChownUndoFinish: Rollback<Output = ChownOp>);
+
+/// Error encountered when finishing [`ChownOp`].
+#[derive(Debug, thiserror::Error)]
+pub enum ChownOpError {
+    #[error("metadata error")]
+    Metadata(#[from] MetadataError),
+    #[error("chown error")]
+    Chown(#[from] ChownError),
+    #[error("chown permission error")]
+    NotPermitted(#[from] ChownPermissionError),
+}
+
+/// Operation to change the owner and group of the file at `path` to `uid` and `gid`. An id of
+/// `None` is left unchanged. Symlinks are followed.
+///
+/// # Errors
+///
+/// The operation will error if `path` does not exist or its owner cannot be changed. Changing the
+/// owner usually requires root privileges; if they are lacking, the operation errors with
+/// [`ChownPermissionError`].
+///
+/// # Undo
+///
+/// Undoing will restore the owner and group that the file had before. This set of operations
+/// functions in the following cycle:
+///
+/// [`ChownOp`] --> [`ChownFinish`] --> [`ChownUndoOp`] --> [`ChownUndoFinish`] --> [`ChownOp`] --> ...
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChownOp {
+    /// Path of the file.
+    pub path: PathBuf,
+    /// Id of the user to set as owner.
+    pub uid: Option<u32>,
+    /// Id of the group to set.
+    pub gid: Option<u32>,
+}
+
+/// The output of [`ChownOp`]. See its documentation for information.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChownFinish {
+    /// See [`ChownOp`].
+    pub path: PathBuf,
+    /// See [`ChownOp`].
+    pub uid: Option<u32>,
+    /// See [`ChownOp`].
+    pub gid: Option<u32>,
+    /// Id of the owner of the file before the operation.
+    pub prev_uid: u32,
+    /// Id of the group of the file before the operation.
+    pub prev_gid: u32,
+}
+
+impl Finish for ChownOp {
+    type Output = ChownFinish;
+    type Error = ChownOpError;
+
+    #[inline]
+    fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
+        let Self { path, uid, gid } = self;
+
+        let (prev_uid, prev_gid) = chown::<Self::Error>(path, *uid, *gid)?;
+        Ok(Self::Output {
+            path: path.clone(),
+            uid: *uid,
+            gid: *gid,
+            prev_uid,
+            prev_gid,
+        })
+    }
+}
+
+impl Rollback for ChownFinish {
+    type Output = ChownUndoOp;
+
+    #[inline]
+    fn rollback(&self) -> Self::Output {
+        let Self {
+            path,
+            uid,
+            gid,
+            prev_uid,
+            prev_gid,
+        } = self;
+
+        Self::Output {
+            path: path.clone(),
+            uid: *uid,
+            gid: *gid,
+            prev_uid: *prev_uid,
+            prev_gid: *prev_gid,
+        }
+    }
+}
+
+/// Error encountered when finishing [`ChownUndoOp`].
+#[derive(Debug, thiserror::Error)]
+pub enum ChownUndoOpError {
+    #[error("metadata error")]
+    Metadata(#[from] MetadataError),
+    #[error("chown error")]
+    Chown(#[from] ChownError),
+    #[error("chown permission error")]
+    NotPermitted(#[from] ChownPermissionError),
+}
+
+/// The undo of [`ChownOp`] (see its documentation), created by rolling back [`ChownFinish`].
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChownUndoOp {
+    /// See [`ChownOp`].
+    pub path: PathBuf,
+    /// See [`ChownOp`].
+    pub uid: Option<u32>,
+    /// See [`ChownOp`].
+    pub gid: Option<u32>,
+    /// See [`ChownFinish`].
+    pub prev_uid: u32,
+    /// See [`ChownFinish`].
+    pub prev_gid: u32,
+}
+
+/// The output of [`ChownUndoOp`]. See its documentation for information.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct ChownUndoFinish {
+    /// See [`ChownOp`].
+    pub path: PathBuf,
+    /// See [`ChownOp`].
+    pub uid: Option<u32>,
+    /// See [`ChownOp`].
+    pub gid: Option<u32>,
+    /// See [`ChownFinish`].
+    pub prev_uid: u32,
+    /// See [`ChownFinish`].
+    pub prev_gid: u32,
+}
+
+impl Finish for ChownUndoOp {
+    type Output = ChownUndoFinish;
+    type Error = ChownUndoOpError;
+
+    #[inline]
+    fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
+        let Self {
+            path,
+            uid,
+            gid,
+            prev_uid,
+            prev_gid,
+        } = self;
+
+        chown::<Self::Error>(path, Some(*prev_uid), Some(*prev_gid))?;
+        Ok(Self::Output {
+            path: path.clone(),
+            uid: *uid,
+            gid: *gid,
+            prev_uid: *prev_uid,
+            prev_gid: *prev_gid,
+        })
+    }
+}
+
+impl Rollback for ChownUndoFinish {
+    type Output = ChownOp;
+
+    #[inline]
+    fn rollback(&self) -> Self::Output {
+        let Self { path, uid, gid, .. } = self;
+
+        Self::Output {
+            path: path.clone(),
+            uid: *uid,
+            gid: *gid,
+        }
+    }
+}
+
+/// Change the owner and group of `path`, returning the previous owner and group.
+#[cfg(unix)]
+#[inline]
+fn chown<E>(path: &Path, uid: Option<u32>, gid: Option<u32>) -> Result<(u32, u32), E>
+where
+    E: From<MetadataError> + From<ChownError> + From<ChownPermissionError>,
+{
+    use std::fs;
+    use std::os::unix::fs::{self as unix_fs, MetadataExt};
+
+    let meta = fs::metadata(path).map_err(|inner| MetadataError {
+        path: path.to_path_buf(),
+        inner,
+    })?;
+    let prev = (meta.uid(), meta.gid());
+
+    unix_fs::chown(path, uid, gid).map_err(|inner| -> E {
+        let path = path.to_path_buf();
+        match inner.kind() {
+            io::ErrorKind::PermissionDenied => ChownPermissionError {
+                path,
+                uid,
+                gid,
+                inner,
+            }
+            .into(),
+            _ => ChownError { path, inner }.into(),
+        }
+    })?;
+
+    Ok(prev)
+}
+
+/// Change the owner and group of `path`. Ownership isn't supported on this platform, so this
+/// always fails.
+#[cfg(not(unix))]
+#[inline]
+fn chown<E>(path: &Path, _uid: Option<u32>, _gid: Option<u32>) -> Result<(u32, u32), E>
+where
+    E: From<MetadataError> + From<ChownError> + From<ChownPermissionError>,
+{
+    Err(ChownError {
+        path: path.to_path_buf(),
+        inner: io::Error::new(
+            io::ErrorKind::Unsupported,
+            "ownership is not supported on this platform",
+        ),
+    }
+    .into())
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+
+    use super::super::test;
+    use super::{ChownOp, Finish, Rollback};
+
+    /// Test changing to the current owner, which is always permitted.
+    #[test]
+    fn test_chown() -> test::Result<()> {
+        test::with_tempdir(|dir, ctx| {
+            let (_, path) = test::new_file(dir, "a")?;
+            let meta = fs::metadata(&path)?;
+
+            let op = ChownOp {
+                path: path.clone(),
+                uid: Some(meta.uid()),
+                gid: Some(meta.gid()),
+            };
+
+            let opf = op.finish(ctx)?;
+            assert_eq!((opf.prev_uid, opf.prev_gid), (meta.uid(), meta.gid()));
+
+            let undo = opf.rollback();
+            let undof = undo.finish(ctx)?;
+
+            let op2 = undof.rollback();
+            assert_eq!(op, op2);
+
+            Ok(())
+        })
+    }
+
+    /// Test for nonexistent file.
+    #[test]
+    fn test_nonexistent_file() -> test::Result<()> {
+        test::with_tempdir(|dir, ctx| {
+            let op = ChownOp {
+                path: dir.join("a"),
+                uid: None,
+                gid: None,
+            };
+
+            if op.finish(ctx).is_ok() {
+                panic!("op succeeded")
+            }
+
+            Ok(())
+        })
+    }
+}
@@ -82,7 +82,7 @@ impl Finish for CommandOp {
 
         let mut cmd = Command::new(shell);
         // TODO: Don't hardcode this
-        cmd.args(&["-c", command]);
+        cmd.args(["-c", command]);
         cmd.current_dir(start);
 
         cmd.stdout(Stdio::piped());
@@ -76,7 +76,7 @@ impl Finish for CopyOp {
             fs_extra::dir::copy(src, dest, &opts).map_err(|inner| CopyError {
                 src: src.clone(),
                 dest: dest.clone(),
-                inner: io::Error::new(io::ErrorKind::Other, format!("{}", inner)),
+                inner: io::Error::other(format!("{}", inner)),
             })?;
         } else {
             fs::copy(src, dest).map_err(|inner| CopyError {
@@ -148,7 +148,7 @@ impl Finish for CopyUndoOp {
 
         // Remove copied file.
         let res = if *dir {
-            fs::remove_dir(dest)
+            fs::remove_dir_all(dest)
         } else {
             fs::remove_file(dest)
         };
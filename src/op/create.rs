@@ -1,11 +1,13 @@
-use std::fs::{self, File};
+use std::fs;
 use std::path::PathBuf;
 
 use serde::{Deserialize, Serialize};
 use static_assertions as sa;
 
 use super::ctx::FinishCtx;
-use super::error::{CreateError, RemoveError};
+use super::error::{
+    CreateError, MetadataError, PermissionsError, RemoveError, RenameError, WriteError,
+};
 use super::{Finish, Rollback};
 
 sa::assert_impl_all!(CreateOp: Finish<Output = CreateFinish, Error = CreateOpError>);
@@ -16,12 +18,22 @@ sa::assert_impl_all!(CreateUndoFinish: Rollback<Output = CreateOp>);
 /// Error encountered when finishing [`CreateOp`].
 #[derive(Debug, thiserror::Error)]
 pub enum CreateOpError {
+    #[error("metadata error")]
+    Metadata(#[from] MetadataError),
     #[error("create error")]
     Create(#[from] CreateError),
+    #[error("write error")]
+    Write(#[from] WriteError),
+    #[error("permissions error")]
+    Permissions(#[from] PermissionsError),
+    #[error("rename error")]
+    Rename(#[from] RenameError),
 }
 
 /// Operation to create a regular file at `path`.
 ///
+/// The file is created atomically, like with [`super::WriteOp`].
+///
 /// # Errors
 ///
 /// The operation will truncate existing data if `path` points to an existing file or error if
@@ -54,10 +66,7 @@ impl Finish for CreateOp {
         let Self { path } = self;
 
         // Create the file.
-        let _ = File::create(path).map_err(|inner| CreateError {
-            path: path.clone(),
-            inner,
-        })?;
+        super::write::write_atomic::<Self::Error>(path, &[])?;
 
         Ok(Self::Output { path: path.clone() })
     }
@@ -104,7 +113,7 @@ impl Finish for CreateUndoOp {
         let Self { path } = self;
 
         // Remove the created file.
-        fs::remove_file(&path).map_err(|inner| RemoveError {
+        fs::remove_file(path).map_err(|inner| RemoveError {
             path: path.clone(),
             inner,
         })?;
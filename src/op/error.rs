@@ -38,6 +38,16 @@ pub struct SymlinkError {
     pub inner: io::Error,
 }
 
+/// Error encountered when hard linking a file.
+#[derive(Debug, thiserror::Error)]
+#[error("i/o hard link error")]
+pub struct HardlinkError {
+    pub src: PathBuf,
+    pub dest: PathBuf,
+    #[source]
+    pub inner: io::Error,
+}
+
 /// Error encountered when copying a file.
 #[derive(Debug, thiserror::Error)]
 #[error("i/o copy error")]
@@ -124,3 +134,33 @@ pub struct SpawnError {
     #[source]
     pub inner: io::Error,
 }
+
+/// Error encountered when setting file permissions.
+#[derive(Debug, thiserror::Error)]
+#[error("i/o set permissions error")]
+pub struct PermissionsError {
+    pub path: PathBuf,
+    #[source]
+    pub inner: io::Error,
+}
+
+/// Error encountered when changing the owner of a file.
+#[derive(Debug, thiserror::Error)]
+#[error("i/o chown error")]
+pub struct ChownError {
+    pub path: PathBuf,
+    #[source]
+    pub inner: io::Error,
+}
+
+/// Error encountered when changing the owner of a file without the privileges to do so, e.g.
+/// when not running as root.
+#[derive(Debug, thiserror::Error)]
+#[error("chown not permitted")]
+pub struct ChownPermissionError {
+    pub path: PathBuf,
+    pub uid: Option<u32>,
+    pub gid: Option<u32>,
+    #[source]
+    pub inner: io::Error,
+}
@@ -0,0 +1,186 @@
+use std::fs;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+use static_assertions as sa;
+
+use super::ctx::FinishCtx;
+use super::error::{HardlinkError, RemoveError};
+use super::{Finish, Rollback};
+
+sa::assert_impl_all!(HardlinkOp: Finish<Output = HardlinkFinish, Error = HardlinkOpError>);
+sa::assert_impl_all!(HardlinkFinish: Rollback<Output = HardlinkUndoOp>);
+sa::assert_impl_all!(
+    HardlinkUndoOp: Finish<Output = HardlinkUndoFinish, Error = HardlinkUndoOpError>
+);
+sa::assert_impl_all!(HardlinkUndoFinish: Rollback<Output = HardlinkOp>);
+
+/// Error encountered when finishing [`HardlinkOp`].
+#[derive(Debug, thiserror::Error)]
+pub enum HardlinkOpError {
+    #[error("hard link error")]
+    Hardlink(#[from] HardlinkError),
+}
+
+/// Operation to hard link a file from `src` to `dest`. It corresponds to [`std::fs::hard_link`].
+///
+/// # Errors
+///
+/// It is assumed that `src` points to an existing regular file, that no file exists at `dest`
+/// (which must be writable), and that both are on the same filesystem. These premises are not
+/// checked, and the operation will error if they are not met.
+///
+/// # Undo
+///
+/// Undoing will delete the hard link; the contents remain at `src`. This set of operations
+/// functions in the following cycle:
+///
+/// [`HardlinkOp`] --> [`HardlinkFinish`] --> [`HardlinkUndoOp`] --> [`HardlinkUndoFinish`] --> [`HardlinkOp`] --> ...
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HardlinkOp {
+    /// Path to file to link.
+    pub src: PathBuf,
+    /// Path to destination of link.
+    pub dest: PathBuf,
+}
+
+/// The output of [`HardlinkOp`]. See its documentation for information.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HardlinkFinish {
+    /// See [`HardlinkOp`].
+    pub src: PathBuf,
+    /// See [`HardlinkOp`].
+    pub dest: PathBuf,
+}
+
+impl Finish for HardlinkOp {
+    type Output = HardlinkFinish;
+    type Error = HardlinkOpError;
+
+    #[inline]
+    fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
+        let Self { src, dest } = self;
+
+        // Perform hard link.
+        fs::hard_link(src, dest).map_err(|inner| HardlinkError {
+            src: src.clone(),
+            dest: dest.clone(),
+            inner,
+        })?;
+
+        Ok(Self::Output {
+            src: src.clone(),
+            dest: dest.clone(),
+        })
+    }
+}
+
+impl Rollback for HardlinkFinish {
+    type Output = HardlinkUndoOp;
+
+    #[inline]
+    fn rollback(&self) -> Self::Output {
+        let Self { src, dest } = self;
+
+        Self::Output {
+            src: src.clone(),
+            dest: dest.clone(),
+        }
+    }
+}
+
+/// Error encountered when finishing [`HardlinkUndoOp`].
+#[derive(Debug, thiserror::Error)]
+pub enum HardlinkUndoOpError {
+    #[error("remove error")]
+    Remove(#[from] RemoveError),
+}
+
+/// The undo of [`HardlinkOp`] (see its documentation), created by rolling back
+/// [`HardlinkFinish`].
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HardlinkUndoOp {
+    /// See [`HardlinkOp`].
+    pub src: PathBuf,
+    /// See [`HardlinkOp`].
+    pub dest: PathBuf,
+}
+
+/// The output of [`HardlinkUndoOp`]. See its documentation for information.
+#[derive(Debug, Clone, Deserialize, Serialize, PartialEq, Eq, PartialOrd, Ord)]
+pub struct HardlinkUndoFinish {
+    /// See [`HardlinkOp`].
+    pub src: PathBuf,
+    /// See [`HardlinkOp`].
+    pub dest: PathBuf,
+}
+
+impl Finish for HardlinkUndoOp {
+    type Output = HardlinkUndoFinish;
+    type Error = HardlinkUndoOpError;
+
+    #[inline]
+    fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
+        let Self { src, dest } = self;
+
+        // Remove hard link.
+        fs::remove_file(dest).map_err(|inner| RemoveError {
+            path: dest.clone(),
+            inner,
+        })?;
+
+        Ok(Self::Output {
+            src: src.clone(),
+            dest: dest.clone(),
+        })
+    }
+}
+
+impl Rollback for HardlinkUndoFinish {
+    type Output = HardlinkOp;
+
+    #[inline]
+    fn rollback(&self) -> Self::Output {
+        let Self { src, dest } = self;
+
+        Self::Output {
+            src: src.clone(),
+            dest: dest.clone(),
+        }
+    }
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::MetadataExt;
+
+    use super::super::test;
+    use super::{Finish, HardlinkOp, Rollback};
+
+    #[test]
+    fn test_hardlink() -> test::Result<()> {
+        test::with_tempdir(|dir, ctx| {
+            let (_, src) = test::new_file(dir, "a")?;
+            let dest = dir.join("b");
+
+            let op = HardlinkOp {
+                src: src.clone(),
+                dest: dest.clone(),
+            };
+
+            let opf = op.finish(ctx)?;
+            assert_eq!(fs::metadata(&src)?.ino(), fs::metadata(&dest)?.ino());
+
+            let undo = opf.rollback();
+            let undof = undo.finish(ctx)?;
+            assert!(!dest.exists());
+            assert!(src.exists());
+
+            let op2 = undof.rollback();
+            assert_eq!(op, op2);
+
+            Ok(())
+        })
+    }
+}
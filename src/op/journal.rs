@@ -1,11 +1,20 @@
+use std::io::{Read, Write};
+
 use serde::{Deserialize, Serialize};
 
-use crate::journal::{self, Journal, Record, Rollback};
+use crate::journal::{
+    self,
+    writer::{ReadError, WriteError},
+    Journal, Record, Rollback,
+};
+
+use crate::state::Entry;
 
 use super::ctx::FinishCtx;
 use super::{
-    CopyOp, CopyUndoOp, CreateOp, CreateUndoOp, Finish, Finished, FinishedError, LinkOp,
-    LinkUndoOp, MkdirOp, MkdirUndoOp, RmOp, RmUndoOp, Undo, UndoFinished, WriteOp, WriteUndoOp,
+    ChmodOp, ChmodUndoOp, ChownOp, ChownUndoOp, CopyOp, CopyUndoOp, CreateOp, CreateUndoOp, Finish,
+    Finished, FinishedError, HardlinkOp, HardlinkUndoOp, LinkOp, LinkUndoOp, MkdirOp, MkdirUndoOp,
+    RmOp, RmUndoOp, Undo, UndoFinished, WriteOp, WriteUndoOp,
 };
 
 #[derive(Debug, thiserror::Error)]
@@ -14,6 +23,10 @@ pub enum JournalOpError {
     Link(#[from] FinishedError<LinkOp>),
     #[error("link undo op error")]
     LinkUndo(#[from] FinishedError<LinkUndoOp>),
+    #[error("hardlink op error")]
+    Hardlink(#[from] FinishedError<HardlinkOp>),
+    #[error("hardlink undo op error")]
+    HardlinkUndo(#[from] FinishedError<HardlinkUndoOp>),
     #[error("copy op error")]
     Copy(#[from] FinishedError<CopyOp>),
     #[error("copy undo op error")]
@@ -34,12 +47,22 @@ pub enum JournalOpError {
     Rm(#[from] FinishedError<RmOp>),
     #[error("rm undo op error")]
     RmUndo(#[from] FinishedError<RmUndoOp>),
+    #[error("chmod op error")]
+    Chmod(#[from] FinishedError<ChmodOp>),
+    #[error("chmod undo op error")]
+    ChmodUndo(#[from] FinishedError<ChmodUndoOp>),
+    #[error("chown op error")]
+    Chown(#[from] FinishedError<ChownOp>),
+    #[error("chown undo op error")]
+    ChownUndo(#[from] FinishedError<ChownUndoOp>),
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum JournalOp {
     Link(LinkOp),
     LinkUndo(Undo<LinkOp>),
+    Hardlink(HardlinkOp),
+    HardlinkUndo(Undo<HardlinkOp>),
     Copy(CopyOp),
     CopyUndo(Undo<CopyOp>),
     Create(CreateOp),
@@ -50,6 +73,10 @@ pub enum JournalOp {
     MkdirUndo(Undo<MkdirOp>),
     Rm(RmOp),
     RmUndo(Undo<RmOp>),
+    Chmod(ChmodOp),
+    ChmodUndo(Undo<ChmodOp>),
+    Chown(ChownOp),
+    ChownUndo(Undo<ChownOp>),
 }
 
 /// Generate [`From`], [`Finish`] implementations for [`Op`].
@@ -86,6 +113,8 @@ macro_rules! Op_impls {
 Op_impls!(
     Link => LinkOp,
     LinkUndo => Undo<LinkOp>,
+    Hardlink => HardlinkOp,
+    HardlinkUndo => Undo<HardlinkOp>,
     Copy => CopyOp,
     CopyUndo => Undo<CopyOp>,
     Create => CreateOp,
@@ -95,13 +124,19 @@ Op_impls!(
     Mkdir => MkdirOp,
     MkdirUndo => Undo<MkdirOp>,
     Rm => RmOp,
-    RmUndo => Undo<RmOp>
+    RmUndo => Undo<RmOp>,
+    Chmod => ChmodOp,
+    ChmodUndo => Undo<ChmodOp>,
+    Chown => ChownOp,
+    ChownUndo => Undo<ChownOp>
 );
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub enum JournalOpFinish {
     Link(Finished<LinkOp>),
     LinkUndo(UndoFinished<LinkOp>),
+    Hardlink(Finished<HardlinkOp>),
+    HardlinkUndo(UndoFinished<HardlinkOp>),
     Copy(Finished<CopyOp>),
     CopyUndo(UndoFinished<CopyOp>),
     Create(Finished<CreateOp>),
@@ -112,6 +147,10 @@ pub enum JournalOpFinish {
     MkdirUndo(UndoFinished<MkdirOp>),
     Rm(Finished<RmOp>),
     RmUndo(UndoFinished<RmOp>),
+    Chmod(Finished<ChmodOp>),
+    ChmodUndo(UndoFinished<ChmodOp>),
+    Chown(Finished<ChownOp>),
+    ChownUndo(UndoFinished<ChownOp>),
 }
 
 macro_rules! JournalOpFinish_impls {
@@ -143,6 +182,8 @@ macro_rules! JournalOpFinish_impls {
 JournalOpFinish_impls!(
     Link => Finished<LinkOp>,
     LinkUndo => UndoFinished<LinkOp>,
+    Hardlink => Finished<HardlinkOp>,
+    HardlinkUndo => UndoFinished<HardlinkOp>,
     Copy => Finished<CopyOp>,
     CopyUndo => UndoFinished<CopyOp>,
     Create => Finished<CreateOp>,
@@ -152,13 +193,24 @@ JournalOpFinish_impls!(
     Mkdir => Finished<MkdirOp>,
     MkdirUndo => UndoFinished<MkdirOp>,
     Rm => Finished<RmOp>,
-    RmUndo => UndoFinished<RmOp>
+    RmUndo => UndoFinished<RmOp>,
+    Chmod => Finished<ChmodOp>,
+    ChmodUndo => UndoFinished<ChmodOp>,
+    Chown => Finished<ChownOp>,
+    ChownUndo => UndoFinished<ChownOp>
 );
 
 #[derive(Debug, Deserialize, Serialize)]
 struct JournalOpAtom {
     op: JournalOpFinish,
     ctx: FinishCtx,
+    /// State entry of the destination that the op deploys or removes, after the op.
+    #[serde(default)]
+    entry: Option<Entry>,
+    /// State entry of the destination before the op, which is restored when the op is rolled
+    /// back. The rollback of the op carries the two entries swapped.
+    #[serde(default)]
+    prev_entry: Option<Entry>,
 }
 
 impl Rollback for JournalOpAtom {
@@ -172,6 +224,8 @@ impl Rollback for JournalOpAtom {
         Ok(Self {
             op: undof,
             ctx: self.ctx.clone(),
+            entry: self.prev_entry.clone(),
+            prev_entry: self.entry.clone(),
         })
     }
 }
@@ -231,6 +285,26 @@ impl OpJournal {
     pub fn get_back(&self, idx: usize) -> Option<Record<&JournalOpFinish>> {
         self.inner.get_back(idx).map(map_record)
     }
+
+    /// Read a journal previously written with [`OpJournal::write`] from `r`.
+    #[inline]
+    pub fn load<R>(r: R) -> Result<Self, ReadError>
+    where
+        R: Read,
+    {
+        let inner = Journal::load(r)?;
+        Ok(Self::new_parts(inner))
+    }
+
+    /// Write the records, starting from the record at index `start`, to `w`. See
+    /// [`Journal::write`].
+    #[inline]
+    pub fn write<W>(&self, w: W, start: usize) -> Result<(), WriteError>
+    where
+        W: Write,
+    {
+        self.inner.write(w, start)
+    }
 }
 
 /// Iterator on a journal.
@@ -288,13 +362,20 @@ impl OpJournal {
         RollbackIter::new(inner)
     }
 
-    /// Return a [`RollbackIter`] if the latest record is the a commit. See
-    /// [`journal::RollbackIter`].
+    /// Return a [`RollbackIter`] on the last transaction that hasn't already been rolled back. See
+    /// [`Journal::rollback_last`].
     #[inline]
     pub fn rollback_last(&mut self) -> Option<RollbackIter<'_>> {
         let inner = self.inner.rollback_last()?;
         Some(RollbackIter::new(inner))
     }
+
+    /// Return the reverse index of the commit record of the transaction that
+    /// [`OpJournal::rollback_last`] would roll back.
+    #[inline]
+    pub fn last_committed(&self) -> Option<usize> {
+        self.inner.last_committed()
+    }
 }
 
 impl<'j> RollbackIter<'j> {
@@ -305,11 +386,34 @@ impl<'j> RollbackIter<'j> {
 }
 
 impl<'j> RollbackIter<'j> {
+    /// Return the number of records in the journal being rolled back.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.inner.journal().size()
+    }
+
+    /// Write the records of the journal being rolled back, starting from the record at index
+    /// `start`, to `w`. See [`Journal::write`].
+    #[inline]
+    pub fn write<W>(&self, w: W, start: usize) -> Result<(), WriteError>
+    where
+        W: Write,
+    {
+        self.inner.journal().write(w, start)
+    }
+
+    /// Roll back the next op, and return the finished rollback along with the state entry that the
+    /// op replaced, which should be restored.
     #[inline]
     #[allow(clippy::should_implement_trait)]
-    pub fn next(&mut self) -> Option<Result<&'_ JournalOpFinish, JournalOpError>> {
+    pub fn next(
+        &mut self,
+    ) -> Option<Result<(&'_ JournalOpFinish, Option<&'_ Entry>), JournalOpError>> {
         match self.inner.next_get()? {
-            Ok(datum) => self.inner.next_append(datum).map(|datum| &datum.op).map(Ok),
+            Ok(datum) => self
+                .inner
+                .next_append(datum)
+                .map(|datum| Ok((&datum.op, datum.entry.as_ref()))),
             Err(err) => Some(Err(err)),
         }
     }
@@ -332,13 +436,32 @@ impl OpJournal {
 }
 
 impl<'j> Transaction<'j> {
+    /// Return the number of records in the journal on which this transaction is operating.
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.inner.journal().size()
+    }
+
+    /// Write the records of the journal on which this transaction is operating, starting from the
+    /// record at index `start`, to `w`. See [`Journal::write`].
+    #[inline]
+    pub fn write<W>(&self, w: W, start: usize) -> Result<(), WriteError>
+    where
+        W: Write,
+    {
+        self.inner.journal().write(w, start)
+    }
+
     /// Append a new action record to the journal by finishing the op, and return the result of
-    /// finishing.
+    /// finishing. `entry` and `prev_entry` are the state entries of the destination that the op
+    /// deploys or removes after and before it is finished.
     #[inline]
     pub fn append_finish<O>(
         &mut self,
         op: O,
         ctx: &FinishCtx,
+        entry: Option<Entry>,
+        prev_entry: Option<Entry>,
     ) -> Result<&JournalOpFinish, <O as Finish>::Error>
     where
         O: Finish,
@@ -348,12 +471,14 @@ impl<'j> Transaction<'j> {
         let atom = JournalOpAtom {
             op: fin.into(),
             ctx: ctx.clone(),
+            entry,
+            prev_entry,
         };
 
         self.inner.append(atom);
         match self.inner.journal().latest().unwrap() {
             Record::Atom(ref atom) => Ok(&atom.op),
-            Record::Commit => unreachable!(),
+            Record::Commit | Record::RollbackCommit => unreachable!(),
         }
     }
 }
@@ -362,6 +487,7 @@ impl<'j> Transaction<'j> {
 fn map_record(record: &Record<JournalOpAtom>) -> Record<&JournalOpFinish> {
     match record {
         Record::Atom(datum) => Record::Atom(&datum.op),
-        Record::Commit => todo!(),
+        Record::Commit => Record::Commit,
+        Record::RollbackCommit => Record::RollbackCommit,
     }
 }
@@ -4,6 +4,8 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 use static_assertions as sa;
 
+use crate::fse;
+
 use super::ctx::FinishCtx;
 use super::error::{RemoveError, SymlinkError};
 use super::{Finish, Rollback};
@@ -23,6 +25,9 @@ pub enum LinkOpError {
 /// Operation to link a file from `src` to `dest`. It roughly corresponds to
 /// [`std::os::unix::fs::symlink`] on Unix and (???) on Windows.
 ///
+/// If `relative` is true, the symlink points to `src` by a path relative to the directory of
+/// `dest` instead of by its absolute path.
+///
 /// # Errors
 ///
 /// It is assumed that `src` points to an readable file, and that no file exists at `dest` (which
@@ -41,6 +46,9 @@ pub struct LinkOp {
     pub src: PathBuf,
     /// Path to destination of link.
     pub dest: PathBuf,
+    /// Link by a relative path.
+    #[serde(default)]
+    pub relative: bool,
 }
 
 /// The output of [`LinkOp`]. See its documentation for information.
@@ -50,6 +58,9 @@ pub struct LinkFinish {
     pub src: PathBuf,
     /// See [`LinkOp`].
     pub dest: PathBuf,
+    /// See [`LinkOp`].
+    #[serde(default)]
+    pub relative: bool,
 }
 
 impl Finish for LinkOp {
@@ -58,7 +69,11 @@ impl Finish for LinkOp {
 
     #[inline]
     fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
-        let Self { src, dest } = self;
+        let Self {
+            src,
+            dest,
+            relative,
+        } = self;
 
         // Perform symlink.
         self.symlink()?;
@@ -66,6 +81,7 @@ impl Finish for LinkOp {
         Ok(Self::Output {
             src: src.clone(),
             dest: dest.clone(),
+            relative: *relative,
         })
     }
 }
@@ -76,9 +92,17 @@ impl LinkOp {
     fn symlink(&self) -> Result<(), SymlinkError> {
         use std::os::unix;
 
-        let Self { src, dest } = self;
-
-        unix::fs::symlink(src, dest).map_err(|inner| SymlinkError {
+        let Self {
+            src,
+            dest,
+            relative,
+        } = self;
+
+        let target = match dest.parent() {
+            Some(dir) if *relative => fse::relative(src, dir),
+            _ => src.clone(),
+        };
+        unix::fs::symlink(target, dest).map_err(|inner| SymlinkError {
             src: src.clone(),
             dest: dest.clone(),
             inner,
@@ -98,11 +122,16 @@ impl Rollback for LinkFinish {
 
     #[inline]
     fn rollback(&self) -> Self::Output {
-        let Self { src, dest } = self;
+        let Self {
+            src,
+            dest,
+            relative,
+        } = self;
 
         Self::Output {
             src: src.clone(),
             dest: dest.clone(),
+            relative: *relative,
         }
     }
 }
@@ -121,6 +150,9 @@ pub struct LinkUndoOp {
     pub src: PathBuf,
     /// See [`LinkOp`].
     pub dest: PathBuf,
+    /// See [`LinkOp`].
+    #[serde(default)]
+    pub relative: bool,
 }
 
 /// The output of [`LinkUndoOp`]. See its documentation for information.
@@ -130,6 +162,9 @@ pub struct LinkUndoFinish {
     pub src: PathBuf,
     /// See [`LinkOp`].
     pub dest: PathBuf,
+    /// See [`LinkOp`].
+    #[serde(default)]
+    pub relative: bool,
 }
 
 impl Finish for LinkUndoOp {
@@ -138,7 +173,11 @@ impl Finish for LinkUndoOp {
 
     #[inline]
     fn finish(&self, _ctx: &FinishCtx) -> Result<Self::Output, Self::Error> {
-        let Self { src, dest } = self;
+        let Self {
+            src,
+            dest,
+            relative,
+        } = self;
 
         // Remove symlink.
         fs::remove_file(dest).map_err(|inner| RemoveError {
@@ -149,6 +188,7 @@ impl Finish for LinkUndoOp {
         Ok(Self::Output {
             src: src.clone(),
             dest: dest.clone(),
+            relative: *relative,
         })
     }
 }
@@ -158,11 +198,16 @@ impl Rollback for LinkUndoFinish {
 
     #[inline]
     fn rollback(&self) -> Self::Output {
-        let Self { src, dest } = self;
+        let Self {
+            src,
+            dest,
+            relative,
+        } = self;
 
         Self::Output {
             src: src.clone(),
             dest: dest.clone(),
+            relative: *relative,
         }
     }
 }
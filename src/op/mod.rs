@@ -3,10 +3,13 @@ pub mod journal;
 
 pub mod error;
 
+pub mod chmod;
+pub mod chown;
 pub mod command;
 pub mod copy;
 pub mod create;
 pub mod function;
+pub mod hardlink;
 pub mod link;
 pub mod mkdir;
 pub mod rm;
@@ -15,10 +18,13 @@ pub mod write;
 pub(super) use crate::journal::Rollback;
 
 pub use self::{
+    chmod::{ChmodOp, ChmodUndoOp},
+    chown::{ChownOp, ChownUndoOp},
     command::CommandOp,
     copy::{CopyOp, CopyUndoOp},
     create::{CreateOp, CreateUndoOp},
     function::FunctionOp,
+    hardlink::{HardlinkOp, HardlinkUndoOp},
     link::{LinkOp, LinkUndoOp},
     mkdir::{MkdirOp, MkdirUndoOp},
     rm::{RmOp, RmUndoOp},
@@ -78,6 +84,8 @@ pub type UndoFinished<O> = Finished<Undo<O>>;
 pub enum OpError {
     #[error("link op error")]
     Link(#[from] FinishedError<LinkOp>),
+    #[error("hardlink op error")]
+    Hardlink(#[from] FinishedError<HardlinkOp>),
     #[error("copy op error")]
     Copy(#[from] FinishedError<CopyOp>),
     #[error("create op error")]
@@ -88,6 +96,10 @@ pub enum OpError {
     Mkdir(#[from] FinishedError<MkdirOp>),
     #[error("rm op error")]
     Rm(#[from] FinishedError<RmOp>),
+    #[error("chmod op error")]
+    Chmod(#[from] FinishedError<ChmodOp>),
+    #[error("chown op error")]
+    Chown(#[from] FinishedError<ChownOp>),
     #[error("command op error")]
     Command(#[from] FinishedError<CommandOp>),
     #[error("function op error")]
@@ -98,6 +110,8 @@ pub enum OpError {
 pub enum Op<'lua> {
     Link(LinkOp),
     LinkUndo(Undo<LinkOp>),
+    Hardlink(HardlinkOp),
+    HardlinkUndo(Undo<HardlinkOp>),
     Copy(CopyOp),
     CopyUndo(Undo<CopyOp>),
     Create(CreateOp),
@@ -108,6 +122,10 @@ pub enum Op<'lua> {
     MkdirUndo(Undo<MkdirOp>),
     Rm(RmOp),
     RmUndo(Undo<RmOp>),
+    Chmod(ChmodOp),
+    ChmodUndo(Undo<ChmodOp>),
+    Chown(ChownOp),
+    ChownUndo(Undo<ChownOp>),
     Command(CommandOp),
     Function(FunctionOp<'lua>),
 }
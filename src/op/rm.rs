@@ -163,7 +163,7 @@ impl Finish for RmUndoOp {
             safepath,
         } = self;
 
-        match rename_with_fallback(path, safepath) {
+        match rename_with_fallback(safepath, path) {
             Ok(()) => {}
             Err(err) => match err {
                 RmOpError::Rename(err) => return Err(err.into()),
@@ -202,7 +202,7 @@ impl Rollback for RmUndoFinish {
 fn rename_with_fallback(path: &Path, safepath: &Path) -> Result<(), RmOpError> {
     // TODO: Try to lift detection of filesystem up to action level?
     // First try a rename, then fallback to copying and removing.
-    if fs::rename(path, &safepath).is_err() {
+    if fs::rename(path, safepath).is_err() {
         let metadata = path.symlink_metadata().map_err(|err| MetadataError {
             path: path.to_path_buf(),
             inner: err,
@@ -231,7 +231,7 @@ fn rename_with_fallback(path: &Path, safepath: &Path) -> Result<(), RmOpError> {
                 #[cfg(unix)]
                 {
                     use std::os::unix;
-                    unix::fs::symlink(&target, &safepath)
+                    unix::fs::symlink(&target, safepath)
                 }
 
                 #[cfg(windows)]
@@ -253,16 +253,16 @@ fn rename_with_fallback(path: &Path, safepath: &Path) -> Result<(), RmOpError> {
                 inner: err,
             })?;
 
-            fs::remove_file(&path).map_err(|err| RemoveError {
+            fs::remove_file(path).map_err(|err| RemoveError {
                 path: path.to_path_buf(),
                 inner: err,
             })?;
         } else if ft.is_dir() {
             if safepath.exists() {
                 let remove_res = if safepath.is_dir() {
-                    fs::remove_dir_all(&safepath)
+                    fs::remove_dir_all(safepath)
                 } else {
-                    fs::remove_file(&safepath)
+                    fs::remove_file(safepath)
                 };
                 remove_res.map_err(|err| RemoveError {
                     path: safepath.to_path_buf(),
@@ -274,15 +274,15 @@ fn rename_with_fallback(path: &Path, safepath: &Path) -> Result<(), RmOpError> {
                 copy_inside: true,
                 ..Default::default()
             };
-            if let Err(err) = fs_extra::dir::move_dir(path, &safepath, &opts) {
+            if let Err(err) = fs_extra::dir::move_dir(path, safepath, &opts) {
                 return Err(RmOpError::Move(MoveError {
                     src: path.to_path_buf(),
                     dest: safepath.to_path_buf(),
-                    inner: io::Error::new(io::ErrorKind::Other, format!("{}", err)),
+                    inner: io::Error::other(format!("{}", err)),
                 }));
             }
         } else {
-            fs::copy(path, &safepath).map_err(|err| CopyError {
+            fs::copy(path, safepath).map_err(|err| CopyError {
                 src: path.to_path_buf(),
                 dest: safepath.to_path_buf(),
                 inner: err,
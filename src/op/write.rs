@@ -1,12 +1,15 @@
-use std::fs::File;
-use std::io::{Read, Write};
+use std::fs::{self, File, Metadata, OpenOptions};
+use std::io::{self, Read, Write};
 use std::path::{Path, PathBuf};
 
 use serde::{Deserialize, Serialize};
 use static_assertions as sa;
+use uuid::Uuid;
 
 use super::ctx::FinishCtx;
-use super::error::{OpenError, ReadError, WriteError};
+use super::error::{
+    CreateError, MetadataError, OpenError, PermissionsError, ReadError, RenameError, WriteError,
+};
 use super::{Finish, Rollback};
 
 sa::assert_impl_all!(WriteOp: Finish<Output = WriteFinish, Error = WriteOpError>);
@@ -21,15 +24,28 @@ pub enum WriteOpError {
     Open(#[from] OpenError),
     #[error("read error")]
     Read(#[from] ReadError),
+    #[error("metadata error")]
+    Metadata(#[from] MetadataError),
+    #[error("create error")]
+    Create(#[from] CreateError),
     #[error("write error")]
     Write(#[from] WriteError),
+    #[error("permissions error")]
+    Permissions(#[from] PermissionsError),
+    #[error("rename error")]
+    Rename(#[from] RenameError),
 }
 
 /// Operation to overwite the contents of `path` with `contents`.
 ///
+/// The file is replaced atomically: the new contents are written to a temporary file next to it,
+/// which is then renamed over `path`. If `path` is a symlink, the file it points to is replaced
+/// and the symlink is kept. The permissions and owner of the file are kept.
+///
 /// # Errors
 ///
-/// The file must already exist, or the operation will fail with no data being written.
+/// The file must already exist, or the operation will fail with no data being written. If the
+/// operation fails, the file is left with its original contents.
 ///
 /// # Undo
 ///
@@ -102,8 +118,16 @@ pub enum WriteUndoOpError {
     Open(#[from] OpenError),
     #[error("read error")]
     Read(#[from] ReadError),
+    #[error("metadata error")]
+    Metadata(#[from] MetadataError),
+    #[error("create error")]
+    Create(#[from] CreateError),
     #[error("write error")]
     Write(#[from] WriteError),
+    #[error("permissions error")]
+    Permissions(#[from] PermissionsError),
+    #[error("rename error")]
+    Rename(#[from] RenameError),
 }
 
 /// The undo of [`WriteOp`] (see its documentation), created by rolling back [`WriteFinish`].
@@ -163,13 +187,19 @@ impl Rollback for WriteUndoFinish {
     }
 }
 
-/// Open the file at `path`, read the contents into `overwritten`, and write `contents` to the
-/// file.
+/// Read the contents of the file at `path` into `overwritten`, and atomically replace them with
+/// `contents`.
 #[inline]
 fn read_write_swap<P, E>(path: P, contents: &[u8], overwritten: &mut Vec<u8>) -> Result<(), E>
 where
     P: AsRef<Path>,
-    E: From<OpenError> + From<ReadError> + From<WriteError>,
+    E: From<OpenError>
+        + From<ReadError>
+        + From<MetadataError>
+        + From<CreateError>
+        + From<WriteError>
+        + From<PermissionsError>
+        + From<RenameError>,
 {
     // Open file.
     let mut file = File::open(&path).map_err(|inner| OpenError {
@@ -184,10 +214,208 @@ where
     })?;
 
     // Ovewrite contents.
+    write_atomic(path.as_ref(), contents)
+}
+
+/// Atomically replace the file at `path` with one containing `contents`, creating it if it
+/// doesn't exist.
+///
+/// The contents are written to a temporary file in the same directory and synced to disk before
+/// the temporary file is renamed over `path`, so that the file is never left partially written.
+/// Symlinks are followed, so that if `path` is one, the link is kept and its target is replaced.
+/// The permissions of an existing file are kept, as is its owner, if permitted.
+#[inline]
+pub(super) fn write_atomic<E>(path: &Path, contents: &[u8]) -> Result<(), E>
+where
+    E: From<MetadataError>
+        + From<CreateError>
+        + From<WriteError>
+        + From<PermissionsError>
+        + From<RenameError>,
+{
+    let path = resolve_links(path);
+    let existing = match fs::metadata(&path) {
+        Ok(meta) => Some(meta),
+        Err(inner) if inner.kind() == io::ErrorKind::NotFound => None,
+        Err(inner) => return Err(MetadataError { path, inner }.into()),
+    };
+
+    let tmp = tmp_path(&path);
+    let res = write_tmp(&tmp, &path, contents, existing.as_ref()).and_then(|_| {
+        fs::rename(&tmp, &path).map_err(|inner| {
+            RenameError {
+                src: tmp.clone(),
+                dest: path.clone(),
+                inner,
+            }
+            .into()
+        })
+    });
+
+    if res.is_err() {
+        // Don't leave the temporary file behind.
+        let _ = fs::remove_file(&tmp);
+        return res;
+    }
+
+    // Sync the directory so that the rename is durable. Failing here loses no data, so errors are
+    // ignored.
+    if let Some(dir) = path.parent().and_then(|parent| File::open(parent).ok()) {
+        let _ = dir.sync_all();
+    }
+
+    Ok(())
+}
+
+/// Write `contents` to a new temporary file at `tmp` that will replace `path`, giving it the
+/// owner and permissions in `existing`.
+#[inline]
+fn write_tmp<E>(
+    tmp: &Path,
+    path: &Path,
+    contents: &[u8],
+    existing: Option<&Metadata>,
+) -> Result<(), E>
+where
+    E: From<CreateError> + From<WriteError> + From<PermissionsError>,
+{
+    let mut file = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(tmp)
+        .map_err(|inner| CreateError {
+            path: tmp.to_path_buf(),
+            inner,
+        })?;
+
     file.write_all(contents).map_err(|inner| WriteError {
-        path: path.as_ref().to_path_buf(),
+        path: path.to_path_buf(),
+        inner,
+    })?;
+
+    if let Some(meta) = existing {
+        // Changing the owner may fail without root privileges, in which case the file is owned by
+        // the current user. It also clears setuid and setgid bits, so it goes first.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::{self as unix_fs, MetadataExt};
+            let _ = unix_fs::fchown(&file, Some(meta.uid()), Some(meta.gid()));
+        }
+        file.set_permissions(meta.permissions())
+            .map_err(|inner| PermissionsError {
+                path: path.to_path_buf(),
+                inner,
+            })?;
+    }
+
+    file.sync_all().map_err(|inner| WriteError {
+        path: path.to_path_buf(),
         inner,
     })?;
 
     Ok(())
 }
+
+/// Follow the chain of symlinks starting at `path`, returning the path that the last one points
+/// to. That path may not exist.
+#[inline]
+fn resolve_links(path: &Path) -> PathBuf {
+    // Bound the number of links followed, in case of a cycle.
+    const MAX_LINKS: usize = 40;
+
+    let mut path = path.to_path_buf();
+    for _ in 0..MAX_LINKS {
+        match fs::read_link(&path) {
+            // Relative targets are relative to the directory of the link.
+            Ok(target) => path = path.with_file_name("").join(target),
+            Err(_) => break,
+        }
+    }
+
+    path
+}
+
+/// Return the path of a temporary file that will replace `path`.
+#[inline]
+fn tmp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}.shelf-tmp", name, Uuid::new_v4().simple()))
+}
+
+#[cfg(all(test, unix))]
+mod test {
+    use std::fs;
+    use std::os::unix::fs::{symlink, PermissionsExt};
+
+    use super::super::test;
+    use super::{Finish, Rollback, WriteOp};
+
+    #[test]
+    fn test_write() -> test::Result<()> {
+        test::with_tempdir(|dir, ctx| {
+            let (file, path) = test::new_file(dir, "a")?;
+            fs::write(&path, "old")?;
+            file.set_permissions(fs::Permissions::from_mode(0o640))?;
+
+            let op = WriteOp {
+                path: path.clone(),
+                contents: b"new".to_vec(),
+            };
+
+            let opf = op.finish(ctx)?;
+            assert_eq!(opf.overwritten, b"old");
+            assert_eq!(fs::read(&path)?, b"new");
+            assert_eq!(fs::metadata(&path)?.permissions().mode() & 0o7777, 0o640);
+            // No temporary files are left behind.
+            assert_eq!(fs::read_dir(dir)?.count(), 1);
+
+            let undo = opf.rollback();
+            let undof = undo.finish(ctx)?;
+            assert_eq!(fs::read(&path)?, b"old");
+
+            let op2 = undof.rollback();
+            assert_eq!(op, op2);
+
+            Ok(())
+        })
+    }
+
+    /// Test that writing to a symlink replaces its target and keeps the link.
+    #[test]
+    fn test_write_symlink() -> test::Result<()> {
+        test::with_tempdir(|dir, ctx| {
+            let (_, target) = test::new_file(dir, "target")?;
+            let link = dir.join("link");
+            symlink("target", &link)?;
+
+            let op = WriteOp {
+                path: link.clone(),
+                contents: b"new".to_vec(),
+            };
+            op.finish(ctx)?;
+
+            assert!(fs::symlink_metadata(&link)?.file_type().is_symlink());
+            assert_eq!(fs::read(&target)?, b"new");
+
+            Ok(())
+        })
+    }
+
+    /// Test for nonexistent file.
+    #[test]
+    fn test_nonexistent_file() -> test::Result<()> {
+        test::with_tempdir(|dir, ctx| {
+            let op = WriteOp {
+                path: dir.join("a"),
+                contents: b"new".to_vec(),
+            };
+
+            if op.finish(ctx).is_ok() {
+                panic!("op succeeded")
+            }
+            assert_eq!(fs::read_dir(dir)?.count(), 0);
+
+            Ok(())
+        })
+    }
+}
@@ -1,6 +1,6 @@
 use mlua::{Error as LuaError, FromLua, Value as LuaValue};
 
-use super::{LinkType, NonZeroExitBehavior};
+use super::{ArrayMerge, LinkType, Mode, NonZeroExitBehavior, Overwrite};
 
 impl<'lua> FromLua<'lua> for LinkType {
     #[inline]
@@ -9,13 +9,18 @@ impl<'lua> FromLua<'lua> for LinkType {
             LuaValue::String(s) => match s.to_str()? {
                 "link" => Ok(Self::Link),
                 "copy" => Ok(Self::Copy),
+                "hardlink" => Ok(Self::Hardlink),
                 _ => conv_err(
                     LuaValue::String(s),
                     "LinkType",
-                    r#"string ("link" or "copy")"#,
+                    r#"string ("link", "copy", or "hardlink")"#,
                 ),
             },
-            _ => conv_err(lua_value, "LinkType", r#"string ("link" or "copy")"#),
+            _ => conv_err(
+                lua_value,
+                "LinkType",
+                r#"string ("link", "copy", or "hardlink")"#,
+            ),
         }
     }
 }
@@ -43,6 +48,52 @@ impl<'lua> FromLua<'lua> for NonZeroExitBehavior {
     }
 }
 
+impl<'lua> FromLua<'lua> for Overwrite {
+    #[inline]
+    fn from_lua(lua_value: LuaValue<'lua>, _lua: &'lua mlua::Lua) -> mlua::Result<Self> {
+        let should = r#"string ("replace", "skip", "backup", "adopt", "ask", or "error")"#;
+        match lua_value {
+            LuaValue::String(s) => match s.to_str()?.parse() {
+                Ok(overwrite) => Ok(overwrite),
+                Err(_) => conv_err(LuaValue::String(s), "Overwrite", should),
+            },
+            _ => conv_err(lua_value, "Overwrite", should),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for ArrayMerge {
+    #[inline]
+    fn from_lua(lua_value: LuaValue<'lua>, _lua: &'lua mlua::Lua) -> mlua::Result<Self> {
+        let should = r#"string ("replace", "append", or "union")"#;
+        match lua_value {
+            LuaValue::String(s) => match s.to_str()?.parse() {
+                Ok(arrays) => Ok(arrays),
+                Err(_) => conv_err(LuaValue::String(s), "ArrayMerge", should),
+            },
+            _ => conv_err(lua_value, "ArrayMerge", should),
+        }
+    }
+}
+
+impl<'lua> FromLua<'lua> for Mode {
+    #[inline]
+    fn from_lua(lua_value: LuaValue<'lua>, _lua: &'lua mlua::Lua) -> mlua::Result<Self> {
+        let should = r#"octal string (e.g. "0600") or integer of octal digits (e.g. 600)"#;
+        // Lua has no octal literals, so the decimal digits of integers are read as octal.
+        let parsed = match &lua_value {
+            LuaValue::String(s) => s.to_str()?.parse(),
+            LuaValue::Integer(i) => i.to_string().parse(),
+            _ => return conv_err(lua_value, "Mode", should),
+        };
+
+        match parsed {
+            Ok(mode) => Ok(mode),
+            Err(_) => conv_err(lua_value, "Mode", should),
+        }
+    }
+}
+
 fn conv_err<R>(value: LuaValue<'_>, to: &'static str, should: &str) -> mlua::Result<R> {
     Err(LuaError::FromLuaConversionError {
         from: value.type_name(),
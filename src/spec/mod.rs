@@ -5,7 +5,11 @@ use std::path::PathBuf;
 use serde::{Deserialize, Serialize};
 
 pub use crate::action::{
+    merge::ArrayMerge,
+    mode::Mode,
     object::{Object, Value as ObjectValue},
+    overwrite::Overwrite,
+    owner::Owner,
     template::hbs::HandlebarsPartials,
     tree::Patterns,
 };
@@ -15,6 +19,9 @@ pub use crate::op::command::EnvMap;
 pub struct Spec {
     pub name: String,
     pub deps: Vec<Dep>,
+    /// Priority of the package's destinations over the same destinations declared by other
+    /// packages; if `None`, conflicting destinations are an error.
+    pub priority: Option<u32>,
     /// List of file link directives; order matters.
     pub directives: Vec<Directive>,
 }
@@ -36,11 +43,10 @@ pub enum File {
     Tree(TreeFile),
     Templated(TemplatedFile),
     Generated(GeneratedFile),
+    Block(BlockFile),
     Dir(DirFile),
 }
 
-// FIXME existing file replacement options
-// FIXME: permission
 #[derive(Debug, Clone)]
 pub struct RegularFile {
     pub src: PathBuf,
@@ -50,7 +56,16 @@ pub struct RegularFile {
 
     /// Files can be symlinked or copied to the destination.
     pub link_type: LinkType,
+    /// Symlinks can point to the source by a relative path instead of an absolute one.
+    pub relative: Option<bool>,
+    pub prune: bool,
     pub optional: bool,
+    /// Policy for an existing destination.
+    pub overwrite: Option<Overwrite>,
+    /// Permission bits of the destination.
+    pub mode: Option<Mode>,
+    /// Owner and group of the destination.
+    pub owner: Owner,
 }
 
 #[derive(Debug, Clone)]
@@ -60,18 +75,27 @@ pub struct TreeFile {
 
     pub globs: Option<Patterns>,
     pub ignore: Option<Patterns>,
+    /// `.gitignore` files in the tree can be honoured along with `.shelfignore` files.
+    pub gitignore: bool,
 
     pub link_type: LinkType,
+    pub relative: Option<bool>,
+    pub prune: bool,
+    /// Trees can symlink whole directories instead of each file in them.
+    pub fold: bool,
     pub optional: bool,
+    pub overwrite: Option<Overwrite>,
+    pub mode: Option<Mode>,
+    pub owner: Owner,
 }
 
 #[derive(Debug, Clone)]
 pub enum LinkType {
     Link,
     Copy,
+    Hardlink,
 }
 
-// FIXME: permissions
 #[derive(Debug, Clone)]
 pub struct TemplatedFile {
     pub src: PathBuf,
@@ -82,6 +106,9 @@ pub struct TemplatedFile {
     pub typ: TemplatedFileType,
 
     pub optional: bool,
+    pub overwrite: Option<Overwrite>,
+    pub mode: Option<Mode>,
+    pub owner: Owner,
 }
 
 // FIXME more template engine options
@@ -101,11 +128,14 @@ pub struct HandlebarsTemplatedFile {
 #[derive(Debug, Clone)]
 pub struct LiquidTemplatedFile {}
 
-// FIXME: permissions
 #[derive(Debug, Clone)]
 pub struct GeneratedFile {
     pub dest: PathBuf,
     pub typ: GeneratedFileTyp,
+
+    pub overwrite: Option<Overwrite>,
+    pub mode: Option<Mode>,
+    pub owner: Owner,
 }
 
 #[derive(Debug, Clone)]
@@ -115,6 +145,8 @@ pub enum GeneratedFileTyp {
     Yaml(YamlGeneratedFile),
     Toml(TomlGeneratedFile),
     Json(JsonGeneratedFile),
+    Ini(IniGeneratedFile),
+    Env(EnvGeneratedFile),
 }
 
 #[derive(Debug, Clone)]
@@ -129,24 +161,54 @@ pub struct StringGeneratedFile {
 pub struct YamlGeneratedFile {
     pub values: Object,
     pub header: Option<String>,
+    pub merge: Option<ArrayMerge>,
 }
 
 #[derive(Debug, Clone)]
 pub struct TomlGeneratedFile {
     pub values: Object,
     pub header: Option<String>,
+    pub merge: Option<ArrayMerge>,
 }
 
 #[derive(Debug, Clone)]
 pub struct JsonGeneratedFile {
     pub values: Object,
+    pub merge: Option<ArrayMerge>,
+}
+
+#[derive(Debug, Clone)]
+pub struct IniGeneratedFile {
+    pub values: Object,
+    pub header: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct EnvGeneratedFile {
+    pub values: Object,
+    pub header: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockFile {
+    pub dest: PathBuf,
+    pub contents: String,
+    /// Name that identifies the block in its marker lines; if `None`, `shelf:<package name>` is
+    /// used.
+    pub marker: Option<String>,
+    /// Comment prefix of the marker lines; if `None`, `#` is used.
+    pub comment: Option<String>,
+
+    pub mode: Option<Mode>,
+    pub owner: Owner,
 }
 
-// TODO: permissions
 #[derive(Debug, Clone)]
 pub struct DirFile {
     pub dest: PathBuf,
     pub parents: bool,
+    pub mode: Option<Mode>,
+    pub owner: Owner,
 }
 
 #[derive(Debug, Clone)]
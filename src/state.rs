@@ -0,0 +1,787 @@
+use std::collections::{BTreeMap, HashSet};
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::action::Action;
+use crate::fse;
+use crate::op::{journal::JournalOpFinish, Op};
+
+/// Persistent record of the destinations deployed by shelf, keyed by destination path.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+pub struct State {
+    entries: BTreeMap<PathBuf, Entry>,
+}
+
+/// Record of a deployed destination.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub struct Entry {
+    /// Path of the package that deployed the destination.
+    pub package: PathBuf,
+    /// Kind of action that deployed the destination.
+    pub kind: Kind,
+    /// Path of the file the destination was deployed from, if any.
+    pub src: Option<PathBuf>,
+    /// Hash of the deployed contents, if the destination is a regular file.
+    pub hash: Option<Hash>,
+}
+
+/// Kind of action that deployed a destination.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+pub enum Kind {
+    Link,
+    Copy,
+    Hardlink,
+    Tree,
+    Write,
+    Handlebars,
+    Liquid,
+    Yaml,
+    Toml,
+    Json,
+    Ini,
+    Env,
+    Mkdir,
+}
+
+/// Hex-encoded SHA-256 hash of file contents.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
+#[serde(transparent)]
+pub struct Hash(String);
+
+#[derive(Debug, thiserror::Error)]
+pub enum Error {
+    #[error("i/o error")]
+    Io(#[from] io::Error),
+    #[error("serde error")]
+    Serde(#[from] serde_json::Error),
+}
+
+impl Kind {
+    /// Return the kind of `action`, or `None` if it doesn't deploy any destinations of its own.
+    /// Files that are merged into aren't owned by the action, so they are never pruned.
+    #[inline]
+    pub fn of(action: &Action<'_>) -> Option<Self> {
+        if action.merges() {
+            return None;
+        }
+
+        let kind = match action {
+            Action::Link(action) if action.copy => Self::Copy,
+            Action::Link(action) if action.hard => Self::Hardlink,
+            Action::Link(_) => Self::Link,
+            Action::Tree(_) => Self::Tree,
+            Action::Write(_) => Self::Write,
+            Action::Handlebars(_) => Self::Handlebars,
+            Action::Liquid(_) => Self::Liquid,
+            Action::Yaml(_) => Self::Yaml,
+            Action::Toml(_) => Self::Toml,
+            Action::Json(_) => Self::Json,
+            Action::Ini(_) => Self::Ini,
+            Action::Env(_) => Self::Env,
+            Action::Mkdir(_) => Self::Mkdir,
+            Action::Block(_) | Action::Command(_) | Action::Function(_) => return None,
+        };
+
+        Some(kind)
+    }
+}
+
+impl Entry {
+    /// Return true if the file at `dest` still matches what was deployed.
+    #[inline]
+    pub fn matches<P>(&self, dest: P) -> bool
+    where
+        P: AsRef<Path>,
+    {
+        let dest = dest.as_ref();
+        let meta = match fs::symlink_metadata(dest) {
+            Ok(meta) => meta,
+            Err(_) => return false,
+        };
+
+        match (&self.hash, self.kind) {
+            // Regular files must have the same contents.
+            (Some(hash), _) => meta.is_file() && Hash::of_file(dest).ok().as_ref() == Some(hash),
+            // Created and copied directories must still be directories.
+            (None, Kind::Mkdir | Kind::Copy) => meta.is_dir(),
+            // Hard links must still be the same file as the source.
+            (None, _) if meta.is_file() => self
+                .src
+                .as_ref()
+                .is_some_and(|src| fse::same_file(dest, src)),
+            // Symlinks must still point to the source.
+            (None, _) => {
+                meta.is_symlink()
+                    && self
+                        .src
+                        .as_ref()
+                        .is_some_and(|src| fse::links_to(dest, src))
+            }
+        }
+    }
+}
+
+impl Hash {
+    /// Hash `contents`.
+    #[inline]
+    pub fn of(contents: &[u8]) -> Self {
+        Self::from_digest(&Sha256::digest(contents))
+    }
+
+    /// Hash the contents of the file at `path`.
+    #[inline]
+    pub fn of_file<P>(path: P) -> io::Result<Self>
+    where
+        P: AsRef<Path>,
+    {
+        fse::hash_file(path).map(|digest| Self::from_digest(&digest))
+    }
+
+    #[inline]
+    fn from_digest(digest: &[u8]) -> Self {
+        Self(digest.iter().map(|b| format!("{:02x}", b)).collect())
+    }
+
+    /// Return the hex-encoded hash.
+    #[inline]
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl State {
+    /// Create a new, empty state.
+    #[inline]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Read a state from `r`.
+    #[inline]
+    pub fn load<R>(r: R) -> Result<Self, Error>
+    where
+        R: Read,
+    {
+        Ok(serde_json::from_reader(r)?)
+    }
+
+    /// Write the state to `w`.
+    #[inline]
+    pub fn write<W>(&self, mut w: W) -> Result<(), Error>
+    where
+        W: Write,
+    {
+        serde_json::to_writer_pretty(&mut w, self)?;
+        w.flush()?;
+        Ok(())
+    }
+
+    /// Return the entry for the destination `dest`.
+    #[inline]
+    pub fn get<P>(&self, dest: P) -> Option<&Entry>
+    where
+        P: AsRef<Path>,
+    {
+        self.entries.get(dest.as_ref())
+    }
+
+    /// Insert the entry for the destination `dest`, returning the previous entry.
+    #[inline]
+    pub fn insert(&mut self, dest: PathBuf, entry: Entry) -> Option<Entry> {
+        self.entries.insert(dest, entry)
+    }
+
+    /// Remove the entry for the destination `dest`.
+    #[inline]
+    pub fn remove<P>(&mut self, dest: P) -> Option<Entry>
+    where
+        P: AsRef<Path>,
+    {
+        self.entries.remove(dest.as_ref())
+    }
+
+    /// Return true if there are no entries.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Return an iterator over all destinations and their entries, ordered by destination.
+    #[inline]
+    pub fn iter(&self) -> impl Iterator<Item = (&Path, &Entry)> {
+        self.entries
+            .iter()
+            .map(|(dest, entry)| (dest.as_path(), entry))
+    }
+
+    /// Return an iterator over the destinations deployed by the package at `package` that are not
+    /// in `declared`, i.e. that the package no longer deploys.
+    #[inline]
+    pub fn orphans<'s>(
+        &'s self,
+        package: &'s Path,
+        declared: &'s HashSet<PathBuf>,
+    ) -> impl Iterator<Item = (&'s Path, &'s Entry)> {
+        self.package(package)
+            .filter(move |(dest, _)| !declared.contains(*dest))
+    }
+
+    /// Return an iterator over the destinations deployed by the package at `package`.
+    #[inline]
+    pub fn package<'s>(&'s self, package: &'s Path) -> impl Iterator<Item = (&'s Path, &'s Entry)> {
+        self.iter()
+            .filter(move |(_, entry)| entry.package == package)
+    }
+}
+
+impl State {
+    /// Update the state with the effects of `op`, which was finished while processing `action`
+    /// of the package at `package`.
+    #[inline]
+    pub fn record(&mut self, package: &Path, action: &Action<'_>, op: &Op<'_>) {
+        let entry = self.op_entry(package, action, op);
+        self.apply(op, entry);
+    }
+
+    /// Return the entry of the destination that `op` deploys or removes, as it should be after the
+    /// op is finished while processing `action` of the package at `package`. This must be called
+    /// before the op is finished, so that the entry can be journaled with it (see
+    /// [`State::record_rollback`]).
+    #[inline]
+    pub fn op_entry(&self, package: &Path, action: &Action<'_>, op: &Op<'_>) -> Option<Entry> {
+        // Actions that don't deploy destinations (e.g. commands) don't create entries.
+        let entry = |src: Option<&Path>, hash: Option<Hash>| {
+            Kind::of(action).map(|kind| Entry {
+                package: package.to_path_buf(),
+                kind,
+                src: src.map(Path::to_path_buf),
+                hash,
+            })
+        };
+
+        let deployed = match op {
+            Op::Link(op) if deploys(action, &op.src, &op.dest) => entry(Some(&op.src), None),
+            Op::Hardlink(op) if deploys(action, &op.src, &op.dest) => entry(Some(&op.src), None),
+            Op::Copy(op) if deploys(action, &op.src, &op.dest) => {
+                let hash = if op.dir {
+                    None
+                } else {
+                    Hash::of_file(&op.src).ok()
+                };
+                entry(Some(&op.src), hash)
+            }
+            Op::Create(_) => entry(action_src(action), Some(Hash::of(&[]))),
+            Op::Write(op) => entry(action_src(action), Some(Hash::of(&op.contents))),
+            // Only the directory created by a mkdir action is recorded, not parent directories.
+            Op::Mkdir(op) if matches!(action, Action::Mkdir(a) if a.path == op.path) => {
+                entry(None, None)
+            }
+            _ => None,
+        };
+
+        match Effect::of(op) {
+            Effect::Deploy(path) => deployed.or_else(|| self.get(path).cloned()),
+            Effect::Modify(path) => self.get(path).cloned(),
+            Effect::Remove(_) | Effect::None => None,
+        }
+    }
+
+    /// Return the current entry of the destination that `op` deploys, modifies or removes. It is
+    /// journaled with the op, so that rolling the op back can restore it.
+    #[inline]
+    pub fn prev_entry(&self, op: &Op<'_>) -> Option<&Entry> {
+        Effect::of(op).path().and_then(|path| self.get(path))
+    }
+
+    /// Refresh the entry of the destination `dest` of `action` of the package at `package`, which
+    /// is already up to date. Only destinations that the package already owns are refreshed; a
+    /// destination that merely matches isn't taken over, since shelf may not have deployed it.
+    #[inline]
+    pub fn record_up_to_date(&mut self, package: &Path, action: &Action<'_>, dest: &Path) {
+        let kind = match Kind::of(action) {
+            Some(kind) => kind,
+            None => return,
+        };
+        match self.get(dest) {
+            Some(entry) if entry.package == package => {}
+            Some(_) | None => return,
+        }
+
+        let src = match action {
+            Action::Tree(action) => match dest.strip_prefix(&action.dest) {
+                // The whole tree was folded into a single link.
+                Ok(rel) if rel.as_os_str().is_empty() => Some(action.src.clone()),
+                Ok(rel) => Some(action.src.join(rel)),
+                Err(_) => None,
+            },
+            _ => action_src(action).map(Path::to_path_buf),
+        };
+
+        // Symlinks, hard links and directories are matched by what they are rather than by their
+        // contents (see `Entry::matches`).
+        let hashed = match action {
+            Action::Link(action) => action.copy,
+            Action::Tree(action) => action.copy,
+            Action::Mkdir(_) => false,
+            _ => true,
+        };
+        let hash = if hashed && fs::symlink_metadata(dest).is_ok_and(|meta| meta.is_file()) {
+            Hash::of_file(dest).ok()
+        } else {
+            None
+        };
+
+        self.insert(
+            dest.to_path_buf(),
+            Entry {
+                package: package.to_path_buf(),
+                kind,
+                src,
+                hash,
+            },
+        );
+    }
+
+    /// Update the state with the effects of `op`, where `entry` is the entry returned by
+    /// [`State::op_entry`] before it was finished.
+    #[inline]
+    pub fn apply(&mut self, op: &Op<'_>, entry: Option<Entry>) {
+        if let Some(path) = Effect::of(op).path() {
+            self.set(path, entry);
+        }
+    }
+
+    /// Update the state with the effects of `fin`, an op finished while rolling back the journal.
+    /// `entry` is the entry that was journaled as the previous entry of the op that was rolled
+    /// back, which is restored; if there was none, the destination is forgotten.
+    #[inline]
+    pub fn record_rollback(&mut self, fin: &JournalOpFinish, entry: Option<&Entry>) {
+        if let Some(path) = Effect::of_finish(fin).path() {
+            self.set(path, entry.cloned());
+        }
+    }
+
+    #[inline]
+    fn set(&mut self, path: &Path, entry: Option<Entry>) {
+        match entry {
+            Some(entry) => {
+                self.insert(path.to_path_buf(), entry);
+            }
+            None => {
+                self.remove(path);
+            }
+        }
+    }
+}
+
+/// Effect of an op on the destination that it operates on.
+#[derive(Debug, Clone, Copy)]
+enum Effect<'a> {
+    /// The op (re)creates the destination.
+    Deploy(&'a Path),
+    /// The op changes the contents of the destination.
+    Modify(&'a Path),
+    /// The op removes the destination.
+    Remove(&'a Path),
+    /// The op doesn't create, change or remove anything.
+    None,
+}
+
+impl<'a> Effect<'a> {
+    #[inline]
+    fn of(op: &'a Op<'_>) -> Self {
+        match op {
+            Op::Link(op) => Self::Deploy(&op.dest),
+            Op::Hardlink(op) => Self::Deploy(&op.dest),
+            Op::Copy(op) => Self::Deploy(&op.dest),
+            Op::Create(op) => Self::Deploy(&op.path),
+            Op::Write(op) => Self::Deploy(&op.path),
+            Op::Mkdir(op) => Self::Deploy(&op.path),
+            Op::RmUndo(op) => Self::Deploy(&op.path),
+            Op::Rm(op) => Self::Remove(&op.path),
+            Op::LinkUndo(op) => Self::Remove(&op.dest),
+            Op::HardlinkUndo(op) => Self::Remove(&op.dest),
+            Op::CopyUndo(op) => Self::Remove(&op.dest),
+            Op::CreateUndo(op) => Self::Remove(&op.path),
+            Op::MkdirUndo(op) => Self::Remove(&op.path),
+            Op::WriteUndo(op) => Self::Modify(&op.path),
+            Op::Chmod(_)
+            | Op::ChmodUndo(_)
+            | Op::Chown(_)
+            | Op::ChownUndo(_)
+            | Op::Command(_)
+            | Op::Function(_) => Self::None,
+        }
+    }
+
+    #[inline]
+    fn of_finish(fin: &'a JournalOpFinish) -> Self {
+        match fin {
+            JournalOpFinish::Link(fin) => Self::Deploy(&fin.dest),
+            JournalOpFinish::Hardlink(fin) => Self::Deploy(&fin.dest),
+            JournalOpFinish::Copy(fin) => Self::Deploy(&fin.dest),
+            JournalOpFinish::Create(fin) => Self::Deploy(&fin.path),
+            JournalOpFinish::Write(fin) => Self::Deploy(&fin.path),
+            JournalOpFinish::Mkdir(fin) => Self::Deploy(&fin.path),
+            JournalOpFinish::RmUndo(fin) => Self::Deploy(&fin.path),
+            JournalOpFinish::Rm(fin) => Self::Remove(&fin.path),
+            JournalOpFinish::LinkUndo(fin) => Self::Remove(&fin.dest),
+            JournalOpFinish::HardlinkUndo(fin) => Self::Remove(&fin.dest),
+            JournalOpFinish::CopyUndo(fin) => Self::Remove(&fin.dest),
+            JournalOpFinish::CreateUndo(fin) => Self::Remove(&fin.path),
+            JournalOpFinish::MkdirUndo(fin) => Self::Remove(&fin.path),
+            JournalOpFinish::WriteUndo(fin) => Self::Modify(&fin.path),
+            JournalOpFinish::Chmod(_)
+            | JournalOpFinish::ChmodUndo(_)
+            | JournalOpFinish::Chown(_)
+            | JournalOpFinish::ChownUndo(_) => Self::None,
+        }
+    }
+
+    /// Return the destination that the op operates on, if any.
+    #[inline]
+    fn path(self) -> Option<&'a Path> {
+        match self {
+            Self::Deploy(path) | Self::Modify(path) | Self::Remove(path) => Some(path),
+            Self::None => None,
+        }
+    }
+}
+
+/// Return the source path of `action`, if it has one.
+#[inline]
+fn action_src<'a>(action: &'a Action<'_>) -> Option<&'a Path> {
+    match action {
+        Action::Link(action) => Some(&action.src),
+        Action::Tree(action) => Some(&action.src),
+        Action::Handlebars(action) => Some(&action.src),
+        Action::Liquid(action) => Some(&action.src),
+        _ => None,
+    }
+}
+
+/// Return true if a link or copy from `src` to `dest` deploys a destination of `action`, rather
+/// than e.g. backing up or adopting an existing file.
+#[inline]
+fn deploys(action: &Action<'_>, src: &Path, dest: &Path) -> bool {
+    match action {
+        Action::Link(action) => action.src == src && action.dest == dest,
+        Action::Tree(action) => src.starts_with(&action.src) && dest.starts_with(&action.dest),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::collections::HashSet;
+    use std::fs;
+    use std::io;
+    use std::path::{Path, PathBuf};
+
+    use super::{Entry, Hash, Kind, State};
+    use crate::action::{Action, LinkAction, WriteAction};
+    use crate::op::{
+        journal::JournalOpFinish,
+        link::{LinkFinish, LinkUndoFinish},
+        write::WriteUndoFinish,
+        CopyOp, LinkOp, Op, RmOp, WriteOp,
+    };
+
+    #[test]
+    fn test_record_link() {
+        let mut state = State::new();
+        let action = Action::Link(LinkAction {
+            src: "/pkg/a".into(),
+            dest: "/home/a".into(),
+            copy: false,
+            hard: false,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        });
+        let op = Op::Link(LinkOp {
+            src: "/pkg/a".into(),
+            dest: "/home/a".into(),
+            relative: false,
+        });
+
+        state.record(Path::new("/pkg"), &action, &op);
+        assert_eq!(
+            state.get("/home/a"),
+            Some(&Entry {
+                package: "/pkg".into(),
+                kind: Kind::Link,
+                src: Some("/pkg/a".into()),
+                hash: None,
+            })
+        );
+
+        let op = Op::Rm(RmOp {
+            path: "/home/a".into(),
+            dir: false,
+        });
+        state.record(Path::new("/pkg"), &action, &op);
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn test_record_write() {
+        let mut state = State::new();
+        let action = Action::Write(WriteAction {
+            dest: "/home/b".into(),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        });
+        let op = Op::Write(WriteOp {
+            path: "/home/b".into(),
+            contents: b"contents".to_vec(),
+        });
+
+        state.record(Path::new("/pkg"), &action, &op);
+        let entry = state.get("/home/b").unwrap();
+        assert_eq!(entry.kind, Kind::Write);
+        assert_eq!(entry.hash, Some(Hash::of(b"contents")));
+
+        // Backups of overwritten files aren't deployed by the action.
+        let op = Op::Copy(CopyOp {
+            src: "/home/b".into(),
+            dest: "/home/b.bak".into(),
+            dir: false,
+        });
+        state.record(Path::new("/pkg"), &action, &op);
+        assert_eq!(state.get("/home/b.bak"), None);
+    }
+
+    #[test]
+    fn test_record_up_to_date() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        let dest = dir.join("dest");
+        fs::write(&src, "contents")?;
+        fs::write(&dest, "contents")?;
+
+        let mut state = State::new();
+        let action = Action::Write(WriteAction {
+            dest: dest.clone(),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        });
+
+        // Destinations that aren't owned aren't taken over, even if they match.
+        state.record_up_to_date(Path::new("/pkg"), &action, &dest);
+        assert!(state.is_empty());
+
+        state.insert(
+            dest.clone(),
+            Entry {
+                package: "/pkg".into(),
+                kind: Kind::Write,
+                src: None,
+                hash: None,
+            },
+        );
+        state.record_up_to_date(Path::new("/other"), &action, &dest);
+        assert_eq!(state.get(&dest).unwrap().package, Path::new("/pkg"));
+
+        state.record_up_to_date(Path::new("/pkg"), &action, &dest);
+        let entry = state.get(&dest).unwrap();
+        assert_eq!(entry.hash, Some(Hash::of(b"contents")));
+        assert!(entry.matches(&dest));
+
+        // Links are matched by their source, not by their contents.
+        fs::remove_file(&dest)?;
+        std::os::unix::fs::symlink(&src, &dest)?;
+        let action = Action::Link(LinkAction {
+            src: src.clone(),
+            dest: dest.clone(),
+            copy: false,
+            hard: false,
+            relative: None,
+            prune: false,
+            optional: false,
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        });
+        state.record_up_to_date(Path::new("/pkg"), &action, &dest);
+        let entry = state.get(&dest).unwrap();
+        assert_eq!((entry.kind, entry.hash.as_ref()), (Kind::Link, None));
+        assert!(entry.matches(&dest));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_forget() {
+        let mut state = State::new();
+        let entry = Entry {
+            package: "/pkg".into(),
+            kind: Kind::Link,
+            src: Some("/pkg/a".into()),
+            hash: None,
+        };
+        state.insert("/home/a".into(), entry.clone());
+
+        // Rolling back the deployment forgets the destination, which had no entry before.
+        state.record_rollback(
+            &JournalOpFinish::LinkUndo(LinkUndoFinish {
+                src: "/pkg/a".into(),
+                dest: "/home/a".into(),
+                relative: false,
+            }),
+            None,
+        );
+        assert!(state.is_empty());
+
+        // Rolling back the removal restores the entry.
+        state.record_rollback(
+            &JournalOpFinish::Link(LinkFinish {
+                src: "/pkg/a".into(),
+                dest: "/home/a".into(),
+                relative: false,
+            }),
+            Some(&entry),
+        );
+        assert_eq!(state.get("/home/a"), Some(&entry));
+
+        // Rolling back an overwrite of a file that wasn't deployed forgets it too.
+        state.record_rollback(
+            &JournalOpFinish::WriteUndo(WriteUndoFinish {
+                path: "/home/a".into(),
+                contents: b"user contents".to_vec(),
+            }),
+            None,
+        );
+        assert!(state.is_empty());
+    }
+
+    #[test]
+    fn test_op_entry() {
+        let mut state = State::new();
+        let action = Action::Write(WriteAction {
+            dest: "/home/b".into(),
+            contents: b"contents".to_vec(),
+            overwrite: None,
+            mode: None,
+            owner: Default::default(),
+        });
+        let write = Op::Write(WriteOp {
+            path: "/home/b".into(),
+            contents: b"contents".to_vec(),
+        });
+        let rm = Op::Rm(RmOp {
+            path: "/home/b".into(),
+            dir: false,
+        });
+
+        assert_eq!(state.prev_entry(&write), None);
+        let entry = state.op_entry(Path::new("/pkg"), &action, &write);
+        state.apply(&write, entry.clone());
+        assert!(entry.is_some());
+
+        // Removing ops journal the entry they remove as the previous one.
+        assert_eq!(state.op_entry(Path::new("/pkg"), &action, &rm), None);
+        assert_eq!(state.prev_entry(&rm), entry.as_ref());
+    }
+
+    #[test]
+    fn test_write_load() {
+        let mut state = State::new();
+        state.insert(
+            "/home/a".into(),
+            Entry {
+                package: "/pkg".into(),
+                kind: Kind::Copy,
+                src: Some("/pkg/a".into()),
+                hash: Some(Hash::of(b"a")),
+            },
+        );
+
+        let mut buf = Vec::new();
+        state.write(&mut buf).unwrap();
+        let loaded = State::load(&buf[..]).unwrap();
+
+        let entries: Vec<_> = loaded.iter().collect();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].0, PathBuf::from("/home/a"));
+        assert_eq!(Some(entries[0].1), state.get("/home/a"));
+    }
+
+    #[test]
+    fn test_orphans() {
+        let mut state = State::new();
+        for dest in ["/home/a", "/home/b"] {
+            state.insert(
+                dest.into(),
+                Entry {
+                    package: "/pkg".into(),
+                    kind: Kind::Link,
+                    src: None,
+                    hash: None,
+                },
+            );
+        }
+
+        let declared: HashSet<_> = vec![PathBuf::from("/home/a")].into_iter().collect();
+        let orphans: Vec<_> = state.orphans(Path::new("/pkg"), &declared).collect();
+        assert_eq!(orphans.len(), 1);
+        assert_eq!(orphans[0].0, Path::new("/home/b"));
+
+        let orphans: Vec<_> = state.orphans(Path::new("/other"), &declared).collect();
+        assert!(orphans.is_empty());
+    }
+
+    #[test]
+    fn test_matches() -> io::Result<()> {
+        let tempdir = tempfile::tempdir()?;
+        let dir = tempdir.path();
+
+        let src = dir.join("src");
+        let file = dir.join("file");
+        let link = dir.join("link");
+        let hardlink = dir.join("hardlink");
+        fs::write(&src, "src")?;
+        fs::write(&file, "contents")?;
+        std::os::unix::fs::symlink(&src, &link)?;
+        fs::hard_link(&src, &hardlink)?;
+
+        let entry = |kind, src: Option<&Path>, hash| Entry {
+            package: "/pkg".into(),
+            kind,
+            src: src.map(Path::to_path_buf),
+            hash,
+        };
+
+        assert!(entry(Kind::Write, None, Some(Hash::of(b"contents"))).matches(&file));
+        assert!(!entry(Kind::Write, None, Some(Hash::of(b"other"))).matches(&file));
+        assert!(entry(Kind::Link, Some(&src), None).matches(&link));
+        assert!(!entry(Kind::Link, Some(&file), None).matches(&link));
+        assert!(!entry(Kind::Link, Some(&src), None).matches(dir.join("missing")));
+        assert!(entry(Kind::Hardlink, Some(&src), None).matches(&hardlink));
+        assert!(!entry(Kind::Hardlink, Some(&src), None).matches(&file));
+        assert!(entry(Kind::Mkdir, None, None).matches(dir));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_hash() {
+        assert_eq!(
+            Hash::of(b"").as_str(),
+            "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+    }
+}